@@ -48,8 +48,9 @@ echo "🚀 Deploying Micro-Bet App..."
 
 # Deploy micro-bet
 MICRO_BET_APP_ID=$(linera project publish-and-create micro-bet \
-  --json-argument "{\"fungible_app_id\": \"$FUNGIBLE_APP_ID\", \"_dummy\": null}" \
-  --json-parameters "\"$FUNGIBLE_APP_ID\"" 2>&1 | tail -1)
+  --json-argument '{"admin": null}' \
+  --json-parameters "{\"fungible_app_id\": \"$FUNGIBLE_APP_ID\"}" \
+  --required-application-ids "$FUNGIBLE_APP_ID" 2>&1 | tail -1)
 
 echo "Micro-Bet App ID: $MICRO_BET_APP_ID"
 
@@ -1,17 +1,29 @@
 // StormCast Micro-Bet Contract
-// Handles bet creation, placement, and resolution
+// Handles bet creation, placement, resolution, and payouts
 
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
 mod state;
 
+use async_graphql::ComplexObject;
 use linera_sdk::{
-    linera_base_types::{AccountOwner, Amount, WithContractAbi},
-    views::{RootView, View},
+    ensure,
+    linera_base_types::{
+        Account, AccountOwner, Amount, ChainId, StreamUpdate, TimeDelta, Timestamp, WithContractAbi,
+    },
+    views::{linera_views::store::KeyRange, RootView, View},
     Contract, ContractRuntime,
 };
-use micro_bet::{Bet, BetStatus, InstantiationArgument, Message, Operation, UserBet, MicroBetAbi};
-use state::MicroBetState;
+use micro_bet::{
+    label_key, market_maker, market_of_stream, market_stream, mul_div, parse_decimal, status_key,
+    time_key, volume_key, ArbiterVote, Bet, BetEvent, BetStatus, BookOrder, Challenge,
+    InstantiationArgument, LiquidityPosition, MarketKind, MarketMetadata, MarketSort, MarketStats,
+    Message, MicroBetAbi, MicroBetError, MicroBetResponse, Operation, OracleSource, Parameters,
+    Parlay, ParlayLeg, ParlayLegInput, ParlayStatus, PricingMode, Resolution, TradeSide, UserBet,
+    BINARY_OUTCOMES, DEFAULT_ARBITRATION_WINDOW, DEFAULT_DISPUTE_WINDOW, MAX_BET_DURATION,
+    MAX_EXPIRED_PER_CLOSE, MAX_FEE_BPS, MAX_OUTCOMES, MAX_PARLAY_LEGS,
+};
+use state::{BookEntry, MicroBetState};
 
 pub struct MicroBetContract {
     state: MicroBetState,
@@ -27,8 +39,8 @@ impl WithContractAbi for MicroBetContract {
 impl Contract for MicroBetContract {
     type Message = Message;
     type InstantiationArgument = InstantiationArgument;
-    type Parameters = ();
-    type EventValue = ();
+    type Parameters = Parameters;
+    type EventValue = BetEvent;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = MicroBetState::load(runtime.root_view_storage_context())
@@ -38,34 +50,245 @@ impl Contract for MicroBetContract {
     }
 
     async fn instantiate(&mut self, argument: InstantiationArgument) {
+        // Validate that the application parameters were configured correctly.
+        let parameters = self.runtime.application_parameters();
+        assert!(
+            parameters.protocol_fee_bps <= MAX_FEE_BPS,
+            "Protocol fee is too high"
+        );
+        if let (Some(min_stake), Some(max_stake)) = (argument.min_stake, argument.max_stake) {
+            assert!(
+                min_stake <= max_stake,
+                "Minimum stake exceeds maximum stake"
+            );
+        }
+
         self.state.instantiation_argument.set(Some(argument));
         self.state.bet_counter.set(0);
     }
 
     async fn execute_operation(&mut self, operation: Operation) -> Self::Response {
         match operation {
-            Operation::CreateBet { question, duration_seconds } => {
-                self.create_bet(question, duration_seconds).await;
+            Operation::Subscribe { bet_id } => return self.subscribe(bet_id),
+            Operation::Unsubscribe { bet_id } => {
+                let hub = self.runtime.application_creator_chain_id();
+                let app_id = self.runtime.application_id().forget_abi();
+                self.runtime
+                    .unsubscribe_from_events(hub, app_id, market_stream(&bet_id));
+                return Ok(MicroBetResponse::Ok);
+            }
+            _ => {}
+        }
+
+        // Users on other chains trade against the markets kept on the hub chain
+        if self.runtime.chain_id() != self.runtime.application_creator_chain_id() {
+            return self.execute_remote_operation(operation);
+        }
+
+        match operation {
+            Operation::CreateBet {
+                question,
+                duration_seconds,
+                outcomes,
+                liquidity,
+                seed,
+                oracle,
+                creator_fee_bps,
+                lp_fee_bps,
+                metadata,
+            } => {
+                self.create_bet(
+                    question,
+                    duration_seconds,
+                    outcomes,
+                    liquidity,
+                    seed,
+                    oracle,
+                    creator_fee_bps,
+                    lp_fee_bps,
+                    metadata,
+                )
+                .await
+            }
+            Operation::PlaceBet {
+                bet_id,
+                outcome_index,
+                amount,
+            } => self.place_bet(bet_id, outcome_index, amount).await,
+            Operation::BuyShares {
+                bet_id,
+                outcome_index,
+                amount,
+                min_shares,
+            } => {
+                self.buy_shares(bet_id, outcome_index, amount, min_shares)
+                    .await
+            }
+            Operation::SellShares {
+                bet_id,
+                outcome_index,
+                amount,
+                max_shares,
+            } => {
+                self.sell_shares(bet_id, outcome_index, amount, max_shares)
+                    .await
+            }
+            Operation::CloseBet { bet_id } => self.close_bet(bet_id).await,
+            Operation::ResolveBet { bet_id, resolution } => {
+                self.resolve_bet(bet_id, resolution).await
+            }
+            Operation::CloseExpired => {
+                self.close_expired().await;
+                Ok(MicroBetResponse::Ok)
+            }
+            Operation::ResolveFromOracle { bet_id } => self.resolve_from_oracle(bet_id).await,
+            Operation::TransferPosition {
+                bet_id,
+                outcome_index,
+                shares,
+                recipient,
+            } => {
+                self.transfer_position(bet_id, outcome_index, shares, recipient)
+                    .await
             }
-            Operation::PlaceBet { bet_id, side, amount } => {
-                self.place_bet(bet_id, side, amount).await;
+            Operation::PlaceOrder {
+                bet_id,
+                outcome_index,
+                side,
+                shares,
+                price,
+            } => {
+                self.place_order(bet_id, outcome_index, side, shares, price)
+                    .await
             }
-            Operation::CloseBet { bet_id } => {
-                self.close_bet(bet_id).await;
+            Operation::CancelOrder { order_id } => self.cancel_order(order_id).await,
+            Operation::PlaceParlay { legs, stake } => self.place_parlay(legs, stake).await,
+            Operation::FundParlayReserve { amount } => self.fund_parlay_reserve(amount),
+            Operation::CancelBet { bet_id } => self.cancel_bet(bet_id).await,
+            Operation::ChallengeResolution { bet_id, proposed } => {
+                self.challenge_resolution(bet_id, proposed).await
             }
-            Operation::ResolveBet { bet_id, outcome } => {
-                self.resolve_bet(bet_id, outcome).await;
+            Operation::SettleDispute { bet_id, resolution } => {
+                self.settle_dispute(bet_id, resolution).await
             }
+            Operation::ClaimWinnings { bet_id } => self.claim_winnings(bet_id).await,
+            Operation::AddLiquidity { bet_id, amount } => self.add_liquidity(bet_id, amount).await,
+            Operation::WithdrawLiquidity { bet_id } => self.withdraw_liquidity(bet_id).await,
+            Operation::WithdrawFees => self.withdraw_fees().await,
+            Operation::Subscribe { .. } | Operation::Unsubscribe { .. } => unreachable!(),
         }
     }
 
     async fn execute_message(&mut self, message: Message) {
+        let is_bouncing = self.runtime.message_is_bouncing() == Some(true);
+
         match message {
             Message::SyncBet { bet } => {
+                self.assert_from_hub();
                 // Store synced bet from another chain
                 let bet_id = bet.id.clone();
-                self.state.bets.insert(&bet_id, bet)
+                self.reindex_status(&bet).await;
+                self.state
+                    .bets
+                    .insert(&bet_id, bet.clone())
                     .expect("Failed to store synced bet");
+                self.index_sort_orders(&bet);
+            }
+            Message::PlaceBet { .. }
+            | Message::BuyShares { .. }
+            | Message::SellShares { .. }
+            | Message::ClaimWinnings { .. }
+                if is_bouncing =>
+            {
+                // The hub rejected the request. Any stake was sent along with it and bounces
+                // back to its owner on its own.
+            }
+            Message::PlaceBet {
+                owner,
+                bet_id,
+                outcome_index,
+                amount,
+            } => {
+                self.assert_signed_by(owner);
+                let user_bet = self
+                    .record_wager(owner, bet_id, outcome_index, amount)
+                    .await
+                    .unwrap_or_else(|error| panic!("{error}"));
+                self.send_receipt(user_bet).await;
+            }
+            Message::BuyShares {
+                owner,
+                bet_id,
+                outcome_index,
+                amount,
+                min_shares,
+            } => {
+                self.assert_signed_by(owner);
+                let user_bet = self
+                    .record_purchase(owner, bet_id, outcome_index, amount, min_shares)
+                    .await
+                    .unwrap_or_else(|error| panic!("{error}"));
+                self.send_receipt(user_bet).await;
+            }
+            Message::SellShares {
+                owner,
+                bet_id,
+                outcome_index,
+                amount,
+                max_shares,
+            } => {
+                self.assert_signed_by(owner);
+                self.record_sale(owner, bet_id.clone(), outcome_index, amount, max_shares)
+                    .await
+                    .unwrap_or_else(|error| panic!("{error}"));
+                self.pay_remote(owner, amount);
+                self.send_positions(owner, bet_id).await;
+            }
+            Message::ClaimWinnings { owner, bet_id } => {
+                self.assert_signed_by(owner);
+                let payout = self
+                    .collect_winnings(owner, &bet_id)
+                    .await
+                    .unwrap_or_else(|error| panic!("{error}"));
+                self.pay_remote(owner, payout);
+                self.send_positions(owner, bet_id).await;
+            }
+            Message::PositionReceipt { user_bet } => {
+                self.assert_from_hub();
+                self.add_position(user_bet).await;
+            }
+            Message::SyncPositions {
+                owner,
+                bet_id,
+                positions,
+            } => {
+                self.assert_from_hub();
+                self.set_positions(owner, &bet_id, positions).await;
+            }
+        }
+    }
+
+    async fn process_streams(&mut self, updates: Vec<StreamUpdate>) {
+        let app_id = self.runtime.application_id().forget_abi();
+        for update in updates {
+            assert_eq!(update.stream_id.application_id, app_id.into());
+            let bet_id = market_of_stream(&update.stream_id.stream_name)
+                .expect("Not a market stream")
+                .to_string();
+            // Only the latest state of the bet matters to a mirror
+            if let Some(index) = update.new_indices().last() {
+                let event = self.runtime.read_event(
+                    update.chain_id,
+                    update.stream_id.stream_name.clone(),
+                    index,
+                );
+                let bet = event.bet();
+                self.reindex_status(bet).await;
+                self.state
+                    .bets
+                    .insert(&bet_id, bet.clone())
+                    .expect("Failed to store mirrored bet");
+                self.index_sort_orders(bet);
             }
         }
     }
@@ -76,138 +299,2958 @@ impl Contract for MicroBetContract {
 }
 
 impl MicroBetContract {
-    /// Create a new prediction market bet
-    async fn create_bet(&mut self, question: String, duration_seconds: u64) {
+    /// Follow the event stream of a market on the hub chain
+    fn subscribe(&mut self, bet_id: String) -> Result<MicroBetResponse, MicroBetError> {
+        let hub = self.runtime.application_creator_chain_id();
+        ensure!(self.runtime.chain_id() != hub, MicroBetError::AlreadyOnHub);
+        let app_id = self.runtime.application_id().forget_abi();
+        self.runtime
+            .subscribe_to_events(hub, app_id, market_stream(&bet_id));
+        Ok(MicroBetResponse::Ok)
+    }
+
+    /// Publish an event on the stream of the market it is about
+    fn emit(&mut self, event: BetEvent) {
+        let stream_name = market_stream(&event.bet().id);
+        self.runtime.emit(stream_name, &event);
+    }
+
+    /// Forward an operation submitted on a user chain to the hub chain, sending the stake of
+    /// wagers and purchases along to the hub's escrow account
+    fn execute_remote_operation(
+        &mut self,
+        operation: Operation,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let owner = self.signer()?;
+
+        let message = match operation {
+            Operation::PlaceBet {
+                bet_id,
+                outcome_index,
+                amount,
+            } => {
+                ensure!(amount > Amount::ZERO, MicroBetError::ZeroAmount);
+                self.send_to_hub(owner, amount);
+                Message::PlaceBet {
+                    owner,
+                    bet_id,
+                    outcome_index,
+                    amount,
+                }
+            }
+            Operation::BuyShares {
+                bet_id,
+                outcome_index,
+                amount,
+                min_shares,
+            } => {
+                ensure!(amount > Amount::ZERO, MicroBetError::ZeroAmount);
+                self.send_to_hub(owner, amount);
+                Message::BuyShares {
+                    owner,
+                    bet_id,
+                    outcome_index,
+                    amount,
+                    min_shares,
+                }
+            }
+            Operation::SellShares {
+                bet_id,
+                outcome_index,
+                amount,
+                max_shares,
+            } => Message::SellShares {
+                owner,
+                bet_id,
+                outcome_index,
+                amount,
+                max_shares,
+            },
+            Operation::ClaimWinnings { bet_id } => Message::ClaimWinnings { owner, bet_id },
+            _ => return Err(MicroBetError::HubOnly),
+        };
+
+        // Tracked, so that a rejected request bounces back, together with the stake sent in the
+        // same transaction
+        let hub = self.runtime.application_creator_chain_id();
+        self.runtime
+            .prepare_message(message)
+            .with_authentication()
+            .with_tracking()
+            .send_to(hub);
+        Ok(MicroBetResponse::Forwarded)
+    }
+
+    /// Answer a wager or purchase from a user chain with the recorded position and the
+    /// updated bet
+    async fn send_receipt(&mut self, user_bet: UserBet) {
+        let origin = self.message_origin();
+        let bet = self
+            .state
+            .bets
+            .get(&user_bet.bet_id)
+            .await
+            .expect("Failed to read bet")
+            .expect("Bet not found");
+        self.runtime
+            .prepare_message(Message::SyncBet { bet })
+            .send_to(origin);
+        self.runtime
+            .prepare_message(Message::PositionReceipt { user_bet })
+            .send_to(origin);
+    }
+
+    /// Send the owner's remaining positions on a bet, and the bet itself, back to the user
+    /// chain a message came from
+    async fn send_positions(&mut self, owner: AccountOwner, bet_id: String) {
+        let origin = self.message_origin();
+        let bet = self
+            .state
+            .bets
+            .get(&bet_id)
+            .await
+            .expect("Failed to read bet")
+            .expect("Bet not found");
+        let positions = self.positions(owner, &bet_id).await;
+        self.runtime
+            .prepare_message(Message::SyncBet { bet })
+            .send_to(origin);
+        self.runtime
+            .prepare_message(Message::SyncPositions {
+                owner,
+                bet_id,
+                positions,
+            })
+            .send_to(origin);
+    }
+
+    /// Transfer tokens from the hub's escrow account to the owner on the user chain a message
+    /// came from
+    fn pay_remote(&mut self, owner: AccountOwner, amount: Amount) {
+        let target_account = Account {
+            chain_id: self.message_origin(),
+            owner,
+        };
+        let escrow = self.runtime.application_id().into();
+        self.transfer(escrow, amount, target_account);
+    }
+
+    fn message_origin(&mut self) -> ChainId {
+        self.runtime
+            .message_origin_chain_id()
+            .expect("Incoming message origin chain ID has to be available when executing a message")
+    }
+
+    /// Requests from user chains must be signed by the owner they act for
+    fn assert_signed_by(&mut self, owner: AccountOwner) {
+        assert_eq!(
+            self.runtime.application_creator_chain_id(),
+            self.runtime.chain_id(),
+            "Only the hub chain handles wagers from user chains"
+        );
+        assert_eq!(
+            self.runtime.authenticated_signer(),
+            Some(owner),
+            "Message must be signed by the position owner"
+        );
+    }
+
+    /// Bets and positions are only synced from the hub chain
+    fn assert_from_hub(&mut self) {
+        assert_eq!(
+            self.message_origin(),
+            self.runtime.application_creator_chain_id(),
+            "Bets are only synced from the hub chain"
+        );
+    }
+
+    /// The owner who signed the current operation
+    fn signer(&mut self) -> Result<AccountOwner, MicroBetError> {
+        self.runtime
+            .authenticated_signer()
+            .ok_or(MicroBetError::NotAuthenticated)
+    }
+
+    /// Load a bet
+    async fn bet(&self, bet_id: &str) -> Result<Bet, MicroBetError> {
+        self.state
+            .bets
+            .get(bet_id)
+            .await
+            .expect("Failed to read bet")
+            .ok_or_else(|| MicroBetError::BetNotFound(bet_id.to_string()))
+    }
+
+    /// Whether `owner` is the admin
+    fn is_admin(&self, owner: AccountOwner) -> bool {
+        self.state
+            .instantiation_argument
+            .get()
+            .as_ref()
+            .and_then(|arg| arg.admin)
+            .map(|admin| admin == owner)
+            .unwrap_or(false)
+    }
+
+    /// Create a new prediction market bet, with the given outcome labels or YES/NO, and
+    /// optionally a market maker or pools seeded by the creator and an oracle source to
+    /// resolve it
+    #[allow(clippy::too_many_arguments)]
+    async fn create_bet(
+        &mut self,
+        question: String,
+        duration_seconds: u64,
+        outcomes: Option<Vec<String>>,
+        liquidity: Option<Amount>,
+        seed: Option<Amount>,
+        oracle: Option<OracleSource>,
+        creator_fee_bps: Option<u16>,
+        lp_fee_bps: Option<u16>,
+        metadata: Option<MarketMetadata>,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let invalid = |reason: &str| MicroBetError::InvalidBet(reason.to_string());
+        let (kind, outcomes) = match outcomes {
+            None => (
+                MarketKind::Binary,
+                BINARY_OUTCOMES
+                    .iter()
+                    .map(|label| label.to_string())
+                    .collect::<Vec<_>>(),
+            ),
+            Some(outcomes) => {
+                ensure!(
+                    outcomes.len() >= 2,
+                    invalid("A bet needs at least two outcomes")
+                );
+                ensure!(outcomes.len() <= MAX_OUTCOMES, invalid("Too many outcomes"));
+                ensure!(
+                    outcomes.iter().all(|label| !label.trim().is_empty()),
+                    invalid("Outcome labels must not be empty")
+                );
+                (MarketKind::Categorical, outcomes)
+            }
+        };
+
+        if let Some(oracle) = &oracle {
+            ensure!(
+                oracle.is_valid_for(kind, outcomes.len()),
+                invalid("Invalid oracle source for this bet")
+            );
+        }
+        ensure!(
+            liquidity.is_none_or(|liquidity| liquidity > Amount::ZERO),
+            invalid("Market maker liquidity must be positive")
+        );
+        ensure!(
+            seed.is_none_or(|seed| seed > Amount::ZERO),
+            invalid("Seed liquidity must be positive")
+        );
+        ensure!(
+            liquidity.is_none() || seed.is_none(),
+            invalid("Market maker bets are funded with liquidity, not seeded")
+        );
+        let creator_fee_bps = creator_fee_bps.unwrap_or(0);
+        ensure!(
+            creator_fee_bps <= MAX_FEE_BPS,
+            invalid("Creator fee is too high")
+        );
+        let lp_fee_bps = lp_fee_bps.unwrap_or(0);
+        ensure!(
+            lp_fee_bps <= MAX_FEE_BPS,
+            invalid("Liquidity provider fee is too high")
+        );
+        let duration = TimeDelta::from_secs(duration_seconds);
+        ensure!(
+            duration <= MAX_BET_DURATION,
+            invalid("Duration is too long")
+        );
+        let metadata = metadata
+            .unwrap_or_default()
+            .normalized()
+            .map_err(MicroBetError::InvalidBet)?;
+        let parameters = self.runtime.application_parameters();
+        let protocol_fee_bps = match parameters.fee_recipient {
+            Some(_) => parameters.protocol_fee_bps,
+            None => 0,
+        };
+
+        let creator = self.signer()?;
+        let argument = self
+            .state
+            .instantiation_argument
+            .get()
+            .clone()
+            .unwrap_or_default();
+        let open_markets = self
+            .state
+            .open_markets
+            .get(&creator)
+            .await
+            .expect("Failed to read open markets")
+            .unwrap_or_default();
+        if let Some(max_open_markets) = argument.max_open_markets {
+            ensure!(
+                open_markets < max_open_markets,
+                MicroBetError::TooManyOpenMarkets(max_open_markets)
+            );
+        }
+
         let bet_id = self.state.next_bet_id();
-        let creator = self.runtime.authenticated_signer()
-            .expect("Operation must be authenticated")
-            .into();
+        let deposit = argument.creation_deposit.unwrap_or_default();
+        if deposit > Amount::ZERO {
+            self.receive_from_account(creator, deposit);
+        }
+
+        let (pricing, reserves, seeded_pools) = match liquidity {
+            None => (
+                PricingMode::Parimutuel,
+                Vec::new(),
+                vec![Amount::ZERO; outcomes.len()],
+            ),
+            Some(_) => (
+                PricingMode::MarketMaker,
+                vec![Amount::ZERO; outcomes.len()],
+                Vec::new(),
+            ),
+        };
         let now = self.runtime.system_time();
-        let expires_at = now.saturating_add(linera_sdk::linera_base_types::TimeDelta::from_micros(duration_seconds * 1_000_000));
+        let expires_at = now.saturating_add(duration);
+        let resolve_by = expires_at.saturating_add(argument.resolution_window());
 
-        let bet = Bet {
+        let mut bet = Bet {
             id: bet_id.clone(),
             question,
-            yes_pool: Amount::ZERO,
-            no_pool: Amount::ZERO,
+            kind,
+            pools: vec![Amount::ZERO; outcomes.len()],
+            outcomes,
+            pricing,
+            reserves,
+            liquidity: Amount::ZERO,
+            seeded_pools,
+            lp_shares: Amount::ZERO,
+            lp_fee_bps,
+            lp_fees: Amount::ZERO,
             status: BetStatus::Open,
             creator,
+            protocol_fee_bps,
+            creator_fee_bps,
             resolution: None,
+            oracle,
             created_at: now,
             expires_at,
+            resolve_by,
+            disputable_until: None,
+            challenge: None,
+            metadata,
+            deposit,
         };
 
-        self.state.bets.insert(&bet_id, bet)
+        // The creator is the first liquidity provider. Empty reserves leave no shares over.
+        if let Some(amount) = liquidity.or(seed) {
+            self.receive_from_account(creator, amount);
+            let (shares, _) = bet.add_liquidity(amount);
+            self.record_liquidity(creator, &bet_id, shares, amount)
+                .await;
+        }
+
+        self.state
+            .bets
+            .insert(&bet_id, bet.clone())
             .expect("Failed to insert bet");
+        self.schedule(bet.expires_at, &bet_id);
+        self.state
+            .open_markets
+            .insert(&creator, open_markets + 1)
+            .expect("Failed to update open markets");
+        self.index_metadata(&bet).await;
+        self.index_sort_orders(&bet);
+        self.state
+            .status_markets
+            .insert(status_key(bet.status, &bet_id));
+        self.emit(BetEvent::BetCreated { bet });
+        Ok(MicroBetResponse::BetCreated(bet_id))
+    }
+
+    /// Add a new bet to the category, region and tag indexes
+    async fn index_metadata(&mut self, bet: &Bet) {
+        let metadata = &bet.metadata;
+        let indexes = [
+            (
+                &mut self.state.category_markets,
+                metadata.category.iter().collect::<Vec<_>>(),
+            ),
+            (
+                &mut self.state.region_markets,
+                metadata.region.iter().collect(),
+            ),
+            (&mut self.state.tag_markets, metadata.tags.iter().collect()),
+        ];
+        for (index, labels) in indexes {
+            for label in labels {
+                index
+                    .load_entry_mut(&label_key(label))
+                    .await
+                    .expect("Failed to load market index")
+                    .insert(&bet.id)
+                    .expect("Failed to index bet");
+            }
+        }
     }
 
-    /// Place a bet on an existing market
-    async fn place_bet(&mut self, bet_id: String, side: bool, amount: Amount) {
-        assert!(amount > Amount::ZERO, "Bet amount must be positive");
+    /// Add a bet to the indexes ordering the markets by creation, expiry and volume, unless
+    /// it is there already. Bets enter the volume index with no volume, `add_volume` moving
+    /// them up as they trade.
+    fn index_sort_orders(&mut self, bet: &Bet) {
+        let orders = [
+            (&mut self.state.created_markets, MarketSort::Created),
+            (&mut self.state.expiry_markets, MarketSort::Expiry),
+            (&mut self.state.volume_markets, MarketSort::Volume),
+        ];
+        for (index, order) in orders {
+            index.insert(order.index_key(bet, Amount::ZERO));
+        }
+    }
 
-        let mut bet = self.state.bets.get(&bet_id)
-            .await
-            .expect("Failed to read bet")
-            .expect("Bet not found");
+    /// Place a bet on an existing market, escrowing the wager in the application's account
+    async fn place_bet(
+        &mut self,
+        bet_id: String,
+        outcome_index: u32,
+        amount: Amount,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let owner = self.signer()?;
 
-        assert_eq!(bet.status, BetStatus::Open, "Bet is not open");
-        assert!(
+        let user_bet = self
+            .record_wager(owner, bet_id, outcome_index, amount)
+            .await?;
+
+        // Move the wager into escrow
+        self.receive_from_account(owner, amount);
+        Ok(MicroBetResponse::Position(user_bet))
+    }
+
+    /// Add a parimutuel wager to a bet's pools and to the owner's positions
+    async fn record_wager(
+        &mut self,
+        owner: AccountOwner,
+        bet_id: String,
+        outcome_index: u32,
+        amount: Amount,
+    ) -> Result<UserBet, MicroBetError> {
+        ensure!(amount > Amount::ZERO, MicroBetError::ZeroAmount);
+        self.check_stake(amount)?;
+
+        let mut bet = self.bet(&bet_id).await?;
+
+        ensure!(bet.status == BetStatus::Open, MicroBetError::BetNotOpen);
+        ensure!(
             self.runtime.system_time() < bet.expires_at,
-            "Bet has expired"
+            MicroBetError::BetExpired
+        );
+        // Also reject wagers in blocks validated after the expiry
+        self.runtime.assert_before(bet.expires_at);
+        ensure!(
+            bet.pricing == PricingMode::Parimutuel,
+            MicroBetError::MarketMakerBet
         );
+        self.check_exposure(owner, &bet_id, amount).await?;
 
         // Update the pool
-        if side {
-            bet.yes_pool.saturating_add_assign(amount);
-        } else {
-            bet.no_pool.saturating_add_assign(amount);
-        }
+        bet.pools
+            .get_mut(outcome_index as usize)
+            .ok_or(MicroBetError::InvalidOutcome)?
+            .saturating_add_assign(amount);
 
-        self.state.bets.insert(&bet_id, bet)
+        self.state
+            .bets
+            .insert(&bet_id, bet.clone())
             .expect("Failed to update bet");
 
         // Record user's bet
-        let owner: AccountOwner = self.runtime.authenticated_signer()
-            .expect("Operation must be authenticated")
-            .into();
         let user_bet = UserBet {
-            bet_id: bet_id.clone(),
+            bet_id,
             owner,
-            side,
+            outcome_index,
+            amount,
+            shares: amount,
+            timestamp: self.runtime.system_time(),
+            claimed: false,
+        };
+        self.add_position(user_bet.clone()).await;
+        self.emit(BetEvent::WagerPlaced {
+            bet,
+            user_bet: user_bet.clone(),
+        });
+        Ok(user_bet)
+    }
+
+    /// Buy outcome shares from a bet's market maker, failing if fewer than `min_shares` would
+    /// be received
+    async fn buy_shares(
+        &mut self,
+        bet_id: String,
+        outcome_index: u32,
+        amount: Amount,
+        min_shares: Amount,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let owner = self.signer()?;
+
+        let user_bet = self
+            .record_purchase(owner, bet_id, outcome_index, amount, min_shares)
+            .await?;
+
+        self.receive_from_account(owner, amount);
+        Ok(MicroBetResponse::Position(user_bet))
+    }
+
+    /// Trade `amount` of collateral for shares with a bet's market maker, and add them to the
+    /// owner's positions
+    async fn record_purchase(
+        &mut self,
+        owner: AccountOwner,
+        bet_id: String,
+        outcome_index: u32,
+        amount: Amount,
+        min_shares: Amount,
+    ) -> Result<UserBet, MicroBetError> {
+        ensure!(amount > Amount::ZERO, MicroBetError::ZeroAmount);
+        self.check_stake(amount)?;
+
+        let mut bet = self.market_maker_bet(&bet_id).await?;
+        let index = outcome_index as usize;
+        ensure!(index < bet.reserves.len(), MicroBetError::InvalidOutcome);
+        self.check_exposure(owner, &bet_id, amount).await?;
+
+        let (shares, reserves) = market_maker::buy(&bet.reserves, index, amount);
+        ensure!(shares > Amount::ZERO, MicroBetError::TradeTooSmall);
+        ensure!(shares >= min_shares, MicroBetError::SlippageExceeded);
+
+        bet.reserves = reserves;
+        bet.pools[index].saturating_add_assign(amount);
+        self.state
+            .bets
+            .insert(&bet_id, bet.clone())
+            .expect("Failed to update bet");
+
+        let user_bet = UserBet {
+            bet_id,
+            owner,
+            outcome_index,
             amount,
+            shares,
             timestamp: self.runtime.system_time(),
+            claimed: false,
+        };
+        self.add_position(user_bet.clone()).await;
+        self.emit(BetEvent::WagerPlaced {
+            bet,
+            user_bet: user_bet.clone(),
+        });
+        Ok(user_bet)
+    }
+
+    /// Append a newly wagered position to its owner's positions in the market
+    async fn add_position(&mut self, user_bet: UserBet) {
+        let bet_id = user_bet.bet_id.clone();
+        let amount = user_bet.amount;
+        self.push_position(user_bet).await;
+        self.add_volume(&bet_id, amount).await;
+    }
+
+    /// Append a position to its owner's positions in the market
+    async fn push_position(&mut self, user_bet: UserBet) {
+        let owner = user_bet.owner;
+        let bet_id = user_bet.bet_id.clone();
+        let mut positions = self.positions(owner, &bet_id).await;
+        positions.push(user_bet);
+        self.set_positions(owner, &bet_id, positions).await;
+    }
+
+    /// Take shares of an outcome out of the owner's unclaimed positions, oldest first, and
+    /// return the stake they carried
+    async fn take_shares(
+        &mut self,
+        owner: AccountOwner,
+        bet_id: &str,
+        outcome_index: u32,
+        shares: Amount,
+    ) -> Result<Amount, MicroBetError> {
+        let mut user_bets = self.positions(owner, bet_id).await;
+        let stake = split_off_shares(&mut user_bets, outcome_index, shares)?;
+        self.set_positions(owner, bet_id, user_bets).await;
+        Ok(stake)
+    }
+
+    /// Check that the owner's total stake in a market stays within the configured maximum once
+    /// `added` is staked on top
+    async fn check_exposure(
+        &mut self,
+        owner: AccountOwner,
+        bet_id: &str,
+        added: Amount,
+    ) -> Result<(), MicroBetError> {
+        let max_exposure = self
+            .state
+            .instantiation_argument
+            .get()
+            .as_ref()
+            .and_then(|arg| arg.max_exposure);
+        let Some(max_exposure) = max_exposure else {
+            return Ok(());
         };
+        let exposure: Amount = self
+            .positions(owner, bet_id)
+            .await
+            .iter()
+            .filter(|user_bet| !user_bet.claimed)
+            .map(|user_bet| &user_bet.amount)
+            .sum();
+        ensure!(
+            exposure.saturating_add(added) <= max_exposure,
+            MicroBetError::ExposureExceeded(max_exposure)
+        );
+        Ok(())
+    }
+
+    /// Check that a single stake is within the configured bounds
+    fn check_stake(&self, amount: Amount) -> Result<(), MicroBetError> {
+        match self.state.instantiation_argument.get() {
+            Some(argument) => argument.check_stake(amount),
+            None => Ok(()),
+        }
+    }
+
+    /// The owner's positions in a market
+    async fn positions(&mut self, owner: AccountOwner, bet_id: &str) -> Vec<UserBet> {
+        match self
+            .state
+            .market_positions
+            .try_load_entry(bet_id)
+            .await
+            .expect("Failed to read positions")
+        {
+            Some(positions) => positions
+                .get(&owner)
+                .await
+                .expect("Failed to read positions")
+                .unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
 
-        let mut user_bets = self.state.user_bets.get(&owner)
+    /// Replace the owner's positions in a market, keeping the market's aggregates and the
+    /// owner's index of markets up to date
+    async fn set_positions(&mut self, owner: AccountOwner, bet_id: &str, positions: Vec<UserBet>) {
+        let previous = self.positions(owner, bet_id).await;
+
+        let mut stats = self
+            .state
+            .market_stats
+            .get(bet_id)
             .await
-            .expect("Failed to read user bets")
+            .expect("Failed to read market stats")
             .unwrap_or_default();
-        user_bets.push(user_bet);
-        self.state.user_bets.insert(&owner, user_bets)
-            .expect("Failed to update user bets");
+        stats.positions = stats.positions + positions.len() as u64 - previous.len() as u64;
+        let stake = |positions: &[UserBet]| -> Amount {
+            positions.iter().map(|user_bet| &user_bet.amount).sum()
+        };
+        stats.stake = stats
+            .stake
+            .saturating_add(stake(&positions))
+            .saturating_sub(stake(&previous));
+        if previous.is_empty() && !positions.is_empty() {
+            stats.bettors += 1;
+        } else if !previous.is_empty() && positions.is_empty() {
+            stats.bettors -= 1;
+        }
+        self.state
+            .market_stats
+            .insert(bet_id, stats)
+            .expect("Failed to update market stats");
+
+        let owner_markets = self
+            .state
+            .owner_markets
+            .load_entry_mut(&owner)
+            .await
+            .expect("Failed to read owner markets");
+        if positions.is_empty() {
+            owner_markets
+                .remove(bet_id)
+                .expect("Failed to update owner markets");
+        } else {
+            owner_markets
+                .insert(bet_id)
+                .expect("Failed to update owner markets");
+        }
+
+        let market_positions = self
+            .state
+            .market_positions
+            .load_entry_mut(bet_id)
+            .await
+            .expect("Failed to read positions");
+        if positions.is_empty() {
+            market_positions
+                .remove(&owner)
+                .expect("Failed to update positions");
+        } else {
+            market_positions
+                .insert(&owner, positions)
+                .expect("Failed to update positions");
+        }
     }
 
-    /// Close a bet (stop accepting new wagers)
-    async fn close_bet(&mut self, bet_id: String) {
-        let mut bet = self.state.bets.get(&bet_id)
+    /// Add a trade to a market's total volume
+    async fn add_volume(&mut self, bet_id: &str, amount: Amount) {
+        let mut volume = Amount::ZERO;
+        self.update_stats(bet_id, |stats| {
+            volume = stats.volume;
+            stats.volume.saturating_add_assign(amount);
+        })
+        .await;
+        self.state.volume_markets.remove(volume_key(volume, bet_id));
+        self.state
+            .volume_markets
+            .insert(volume_key(volume.saturating_add(amount), bet_id));
+    }
+
+    /// Update the aggregates of a market
+    async fn update_stats(&mut self, bet_id: &str, update: impl FnOnce(&mut MarketStats)) {
+        let mut stats = self
+            .state
+            .market_stats
+            .get(bet_id)
             .await
-            .expect("Failed to read bet")
-            .expect("Bet not found");
+            .expect("Failed to read market stats")
+            .unwrap_or_default();
+        update(&mut stats);
+        self.state
+            .market_stats
+            .insert(bet_id, stats)
+            .expect("Failed to update market stats");
+    }
 
-        let caller: AccountOwner = self.runtime.authenticated_signer()
-            .expect("Operation must be authenticated")
-            .into();
+    /// Sell outcome shares to a bet's market maker for `amount` tokens, failing if more than
+    /// `max_shares` would be needed
+    async fn sell_shares(
+        &mut self,
+        bet_id: String,
+        outcome_index: u32,
+        amount: Amount,
+        max_shares: Amount,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let owner = self.signer()?;
 
-        // Only creator or admin can close
-        let is_admin = self.state.instantiation_argument.get()
-            .as_ref()
-            .and_then(|arg| arg.admin)
-            .map(|admin| admin == caller)
-            .unwrap_or(false);
+        self.record_sale(owner, bet_id, outcome_index, amount, max_shares)
+            .await?;
 
-        assert!(
-            bet.creator == caller || is_admin,
-            "Only creator or admin can close bet"
-        );
+        self.send_to(amount, owner);
+        Ok(MicroBetResponse::Payout(amount))
+    }
+
+    /// Trade shares back to a bet's market maker for `amount` of collateral, taking them out of
+    /// the owner's positions
+    async fn record_sale(
+        &mut self,
+        owner: AccountOwner,
+        bet_id: String,
+        outcome_index: u32,
+        amount: Amount,
+        max_shares: Amount,
+    ) -> Result<(), MicroBetError> {
+        ensure!(amount > Amount::ZERO, MicroBetError::ZeroAmount);
 
-        assert_eq!(bet.status, BetStatus::Open, "Bet is not open");
+        let mut bet = self.market_maker_bet(&bet_id).await?;
+        let index = outcome_index as usize;
+        ensure!(index < bet.reserves.len(), MicroBetError::InvalidOutcome);
 
-        bet.status = BetStatus::Closed;
-        self.state.bets.insert(&bet_id, bet)
+        let (shares, reserves) = market_maker::sell(&bet.reserves, index, amount)
+            .ok_or(MicroBetError::InsufficientLiquidity)?;
+        ensure!(shares <= max_shares, MicroBetError::SlippageExceeded);
+
+        // Take the shares out of the seller's positions, oldest first
+        self.take_shares(owner, &bet_id, outcome_index, shares)
+            .await?;
+        self.add_volume(&bet_id, amount).await;
+
+        bet.reserves = reserves;
+        bet.pools[index] = bet.pools[index].saturating_sub(amount);
+        self.state
+            .bets
+            .insert(&bet_id, bet.clone())
             .expect("Failed to update bet");
+        self.emit(BetEvent::SharesSold {
+            bet,
+            owner,
+            outcome_index,
+            amount,
+        });
+        Ok(())
+    }
+
+    /// Give shares of the caller's positions on an outcome, with the stake they carry, to
+    /// another owner
+    async fn transfer_position(
+        &mut self,
+        bet_id: String,
+        outcome_index: u32,
+        shares: Amount,
+        recipient: AccountOwner,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let owner = self.signer()?;
+        ensure!(shares > Amount::ZERO, MicroBetError::ZeroAmount);
+        self.bet(&bet_id).await?;
+
+        let mut user_bets = self.positions(owner, &bet_id).await;
+        let stake = split_off_shares(&mut user_bets, outcome_index, shares)?;
+        if recipient != owner {
+            self.check_exposure(recipient, &bet_id, stake).await?;
+        }
+        self.set_positions(owner, &bet_id, user_bets).await;
+
+        let user_bet = UserBet {
+            bet_id,
+            owner: recipient,
+            outcome_index,
+            amount: stake,
+            shares,
+            timestamp: self.runtime.system_time(),
+            claimed: false,
+        };
+        self.push_position(user_bet.clone()).await;
+        Ok(MicroBetResponse::Position(user_bet))
+    }
+
+    /// Place a limit order on the order book of an outcome, locking the tokens it may spend or
+    /// the shares it offers, and fill it against the best resting orders on the other side at
+    /// their prices
+    async fn place_order(
+        &mut self,
+        bet_id: String,
+        outcome_index: u32,
+        side: TradeSide,
+        shares: Amount,
+        price: Amount,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let owner = self.signer()?;
+        ensure!(
+            shares > Amount::ZERO && price > Amount::ZERO,
+            MicroBetError::ZeroAmount
+        );
+        let bet = self.bet(&bet_id).await?;
+        ensure!(bet.is_unresolved(), MicroBetError::TradingClosed);
+        ensure!(
+            (outcome_index as usize) < bet.outcomes.len(),
+            MicroBetError::InvalidOutcome
+        );
+
+        let locked = match side {
+            TradeSide::Buy => {
+                let cost = mul_div(shares, price, Amount::ONE);
+                ensure!(cost > Amount::ZERO, MicroBetError::TradeTooSmall);
+                self.check_stake(cost)?;
+                self.check_exposure(owner, &bet_id, cost).await?;
+                self.receive_from_account(owner, cost);
+                cost
+            }
+            TradeSide::Sell => {
+                let stake = self
+                    .take_shares(owner, &bet_id, outcome_index, shares)
+                    .await?;
+                // Shares offered on the book are still backed by their stake
+                self.update_stats(&bet_id, |stats| stats.stake.saturating_add_assign(stake))
+                    .await;
+                stake
+            }
+        };
+
+        let mut order = BookOrder {
+            id: self.state.next_order_id(),
+            bet_id: bet_id.clone(),
+            outcome_index,
+            owner,
+            side,
+            price,
+            shares,
+            locked,
+            created_at: self.runtime.system_time(),
+        };
+
+        let book_id = (bet_id, outcome_index);
+        let mut book = self
+            .state
+            .order_books
+            .get(&book_id)
+            .await
+            .expect("Failed to read order book")
+            .unwrap_or_default();
+
+        // Match against the other side of the book while the prices cross
+        let mut filled = Amount::ZERO;
+        let resting = match side {
+            TradeSide::Buy => &mut book.asks,
+            TradeSide::Sell => &mut book.bids,
+        };
+        while order.shares > Amount::ZERO {
+            let Some(best) = resting.first() else {
+                break;
+            };
+            let crosses = match side {
+                TradeSide::Buy => best.price <= price,
+                TradeSide::Sell => best.price >= price,
+            };
+            if !crosses {
+                break;
+            }
+            let mut other = self
+                .state
+                .orders
+                .get(&best.order_id)
+                .await
+                .expect("Failed to read order")
+                .expect("Order book entry without an order");
+            let traded = order.shares.min(other.shares);
+            let trade_price = other.price;
+            match side {
+                TradeSide::Buy => self.fill(&mut order, &mut other, traded, trade_price).await,
+                TradeSide::Sell => self.fill(&mut other, &mut order, traded, trade_price).await,
+            }
+            filled.saturating_add_assign(traded);
+            if other.shares == Amount::ZERO {
+                resting.remove(0);
+                self.state
+                    .orders
+                    .remove(&other.id)
+                    .expect("Failed to remove order");
+            } else {
+                let other_id = other.id;
+                self.state
+                    .orders
+                    .insert(&other_id, other)
+                    .expect("Failed to update order");
+            }
+        }
+
+        // Keep the rest on the book, behind the orders with the same price
+        let order_id = order.id;
+        if order.shares > Amount::ZERO {
+            let own = match side {
+                TradeSide::Buy => &mut book.bids,
+                TradeSide::Sell => &mut book.asks,
+            };
+            let position = own
+                .iter()
+                .position(|entry| match side {
+                    TradeSide::Buy => entry.price < price,
+                    TradeSide::Sell => entry.price > price,
+                })
+                .unwrap_or(own.len());
+            own.insert(position, BookEntry { price, order_id });
+            self.state
+                .orders
+                .insert(&order_id, order)
+                .expect("Failed to insert order");
+        }
+        self.state
+            .order_books
+            .insert(&book_id, book)
+            .expect("Failed to update order book");
+
+        Ok(MicroBetResponse::OrderPlaced { order_id, filled })
+    }
+
+    /// Settle `shares` traded between a buy order and a sell order at `price` per share
+    ///
+    /// The seller is paid, and the buyer gets the shares with the stake they carry, as well as
+    /// back the tokens locked beyond the price.
+    async fn fill(
+        &mut self,
+        bid: &mut BookOrder,
+        ask: &mut BookOrder,
+        shares: Amount,
+        price: Amount,
+    ) {
+        let stake = mul_div(ask.locked, shares, ask.shares);
+        ask.locked = ask.locked.saturating_sub(stake);
+        ask.shares = ask.shares.saturating_sub(shares);
+
+        let released = mul_div(bid.locked, shares, bid.shares);
+        bid.locked = bid.locked.saturating_sub(released);
+        bid.shares = bid.shares.saturating_sub(shares);
+
+        let paid = mul_div(shares, price, Amount::ONE).min(released);
+        if paid > Amount::ZERO {
+            self.send_to(paid, ask.owner);
+        }
+        if released > paid {
+            self.send_to(released.saturating_sub(paid), bid.owner);
+        }
+
+        // The stake moves from the book to the buyer's position
+        self.update_stats(&bid.bet_id, |stats| {
+            stats.stake = stats.stake.saturating_sub(stake)
+        })
+        .await;
+        let timestamp = self.runtime.system_time();
+        self.push_position(UserBet {
+            bet_id: bid.bet_id.clone(),
+            owner: bid.owner,
+            outcome_index: bid.outcome_index,
+            amount: stake,
+            shares,
+            timestamp,
+            claimed: false,
+        })
+        .await;
+        self.add_volume(&bid.bet_id, paid).await;
     }
 
-    /// Resolve a bet with the outcome
-    async fn resolve_bet(&mut self, bet_id: String, outcome: bool) {
-        let mut bet = self.state.bets.get(&bet_id)
+    /// Take one of the caller's orders off the book, giving back its locked tokens or shares
+    async fn cancel_order(&mut self, order_id: u64) -> Result<MicroBetResponse, MicroBetError> {
+        let owner = self.signer()?;
+        let order = self
+            .state
+            .orders
+            .get(&order_id)
             .await
-            .expect("Failed to read bet")
-            .expect("Bet not found");
+            .expect("Failed to read order")
+            .ok_or(MicroBetError::OrderNotFound(order_id))?;
+        ensure!(order.owner == owner, MicroBetError::NotOrderOwner);
+
+        let book_id = (order.bet_id.clone(), order.outcome_index);
+        let mut book = self
+            .state
+            .order_books
+            .get(&book_id)
+            .await
+            .expect("Failed to read order book")
+            .unwrap_or_default();
+        book.bids.retain(|entry| entry.order_id != order_id);
+        book.asks.retain(|entry| entry.order_id != order_id);
+        self.state
+            .order_books
+            .insert(&book_id, book)
+            .expect("Failed to update order book");
+        self.state
+            .orders
+            .remove(&order_id)
+            .expect("Failed to remove order");
 
-        let caller: AccountOwner = self.runtime.authenticated_signer()
-            .expect("Operation must be authenticated")
-            .into();
+        match order.side {
+            TradeSide::Buy => self.send_to(order.locked, owner),
+            TradeSide::Sell => {
+                let stake = order.locked;
+                self.update_stats(&order.bet_id, |stats| {
+                    stats.stake = stats.stake.saturating_sub(stake)
+                })
+                .await;
+                let timestamp = self.runtime.system_time();
+                self.push_position(UserBet {
+                    bet_id: order.bet_id,
+                    owner,
+                    outcome_index: order.outcome_index,
+                    amount: stake,
+                    shares: order.shares,
+                    timestamp,
+                    claimed: false,
+                })
+                .await;
+            }
+        }
+        Ok(MicroBetResponse::Ok)
+    }
 
-        // Only creator or admin can resolve
-        let is_admin = self.state.instantiation_argument.get()
-            .as_ref()
-            .and_then(|arg| arg.admin)
-            .map(|admin| admin == caller)
-            .unwrap_or(false);
+    /// Load a market maker bet that is still accepting trades
+    async fn market_maker_bet(&mut self, bet_id: &str) -> Result<Bet, MicroBetError> {
+        let bet = self.bet(bet_id).await?;
 
-        assert!(
-            bet.creator == caller || is_admin,
-            "Only creator or admin can resolve bet"
+        ensure!(bet.status == BetStatus::Open, MicroBetError::BetNotOpen);
+        ensure!(
+            self.runtime.system_time() < bet.expires_at,
+            MicroBetError::BetExpired
         );
+        // Also reject trades in blocks validated after the expiry
+        self.runtime.assert_before(bet.expires_at);
+        ensure!(
+            bet.pricing == PricingMode::MarketMaker,
+            MicroBetError::NoMarketMaker
+        );
+        Ok(bet)
+    }
 
-        assert!(
-            bet.status == BetStatus::Open || bet.status == BetStatus::Closed,
-            "Bet has already been resolved"
+    /// Close a bet (stop accepting new wagers)
+    async fn close_bet(&mut self, bet_id: String) -> Result<MicroBetResponse, MicroBetError> {
+        let mut bet = self.bet(&bet_id).await?;
+        let caller = self.signer()?;
+
+        // Only creator or admin can close
+        ensure!(
+            bet.creator == caller || self.is_admin(caller),
+            MicroBetError::NotCreatorOrAdmin
         );
 
-        bet.status = BetStatus::Resolved;
-        bet.resolution = Some(outcome);
-        self.state.bets.insert(&bet_id, bet)
+        ensure!(bet.status == BetStatus::Open, MicroBetError::BetNotOpen);
+
+        self.set_status(&mut bet, BetStatus::Closed);
+        self.state
+            .bets
+            .insert(&bet_id, bet.clone())
+            .expect("Failed to update bet");
+        self.emit(BetEvent::BetClosed { bet });
+        Ok(MicroBetResponse::Ok)
+    }
+
+    /// Resolve a bet with the winning outcome, or as invalid to refund every wager
+    async fn resolve_bet(
+        &mut self,
+        bet_id: String,
+        resolution: Resolution,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let mut bet = self.bet(&bet_id).await?;
+        let caller = self.signer()?;
+
+        // Only creator or admin can resolve
+        let is_admin = self.is_admin(caller);
+        ensure!(
+            bet.creator == caller || is_admin,
+            MicroBetError::NotCreatorOrAdmin
+        );
+        ensure!(bet.oracle.is_none() || is_admin, MicroBetError::OracleBet);
+
+        ensure!(bet.is_unresolved(), MicroBetError::AlreadyResolved);
+        // Past the deadline the bet can only be voided by `CloseExpired`
+        self.runtime.assert_before(bet.resolve_by);
+
+        if let Resolution::Outcome(index) = resolution {
+            ensure!(
+                (index as usize) < bet.outcomes.len(),
+                MicroBetError::InvalidOutcome
+            );
+        }
+
+        self.set_status(&mut bet, BetStatus::Resolved);
+        bet.resolution = Some(resolution);
+        bet.disputable_until = Some(self.dispute_deadline());
+        self.state
+            .bets
+            .insert(&bet_id, bet.clone())
             .expect("Failed to update bet");
+        self.release_open_market(bet.creator).await;
+        self.settle_parlays_when_final(&bet).await;
+        self.emit(BetEvent::BetResolved { bet });
+        Ok(MicroBetResponse::Ok)
+    }
+
+    /// Close the open bets past their expiry, void the bets left unresolved past their
+    /// resolution deadline or left disputed past their arbitration deadline so that their
+    /// wagers can be refunded, and settle the parlays on bets whose dispute window ended,
+    /// looking only at the bets whose deadlines are due
+    async fn close_expired(&mut self) {
+        let now = self.runtime.system_time();
+
+        // Every deadline up to now sorts before the first key of the next microsecond
+        let end = now.micros().saturating_add(1).to_be_bytes().to_vec();
+        let range = KeyRange::new(..end).with_limit(MAX_EXPIRED_PER_CLOSE);
+        let due_keys = self
+            .state
+            .deadlines
+            .keys_in_range(range)
+            .await
+            .expect("Failed to read deadlines");
+
+        for key in due_keys {
+            let bet_id = bet_of_deadline_key(&key);
+            self.state.deadlines.remove(key);
+            let mut bet = self.bet(&bet_id).await.expect("Scheduled bet not found");
+            if bet.is_unresolved() && now >= bet.resolve_by {
+                self.set_status(&mut bet, BetStatus::Resolved);
+                bet.resolution = Some(Resolution::Invalid);
+                // Voiding an abandoned bet is not up for dispute
+                bet.disputable_until = Some(now);
+                self.forfeit_deposit(&mut bet).await;
+                self.release_open_market(bet.creator).await;
+                self.state
+                    .bets
+                    .insert(&bet_id, bet.clone())
+                    .expect("Failed to update bet");
+                self.emit(BetEvent::BetResolved { bet });
+                self.settle_parlays(&bet_id, now).await;
+            } else if bet.status == BetStatus::Open && now >= bet.expires_at {
+                self.set_status(&mut bet, BetStatus::Closed);
+                self.schedule(bet.resolve_by, &bet_id);
+                self.state
+                    .bets
+                    .insert(&bet_id, bet.clone())
+                    .expect("Failed to update bet");
+                self.emit(BetEvent::BetClosed { bet });
+            } else if bet.status == BetStatus::Closed {
+                // Closed early, so only the resolution deadline is left to watch
+                self.schedule(bet.resolve_by, &bet_id);
+            } else if bet.status == BetStatus::Disputed {
+                let challenge = bet
+                    .challenge
+                    .clone()
+                    .expect("Disputed bet has no challenge");
+                if now
+                    < challenge
+                        .raised_at
+                        .saturating_add(self.arbitration_window())
+                {
+                    // The arbitration deadline is still scheduled
+                    continue;
+                }
+                // The arbiters did not settle the dispute in time, so every stake and the bond
+                // are refunded
+                self.set_status(&mut bet, BetStatus::Resolved);
+                bet.resolution = Some(Resolution::Invalid);
+                bet.disputable_until = Some(now);
+                self.state
+                    .bets
+                    .insert(&bet_id, bet.clone())
+                    .expect("Failed to update bet");
+                self.emit(BetEvent::BetResolved { bet });
+                self.settle_parlays(&bet_id, now).await;
+                self.send_to(challenge.bond, challenge.challenger);
+            } else if bet.is_final_at(now) {
+                // The dispute window ended
+                self.settle_parlays(&bet_id, now).await;
+            }
+        }
+    }
+
+    /// Settle the parlays on a bet that was just resolved, or have `CloseExpired` do it once
+    /// the resolution can no longer be challenged
+    async fn settle_parlays_when_final(&mut self, bet: &Bet) {
+        let now = self.runtime.system_time();
+        match bet.disputable_until {
+            Some(deadline) if deadline > now => self.schedule(deadline, &bet.id),
+            _ => self.settle_parlays(&bet.id, now).await,
+        }
+    }
+
+    /// Move a bet to another status, keeping the index by status up to date
+    fn set_status(&mut self, bet: &mut Bet, status: BetStatus) {
+        self.state
+            .status_markets
+            .remove(status_key(bet.status, &bet.id));
+        bet.status = status;
+        self.state
+            .status_markets
+            .insert(status_key(status, &bet.id));
+    }
+
+    /// Move a mirrored bet to its status on the hub in the index by status, before it
+    /// replaces the stored one
+    async fn reindex_status(&mut self, bet: &Bet) {
+        if let Some(stored) = self
+            .state
+            .bets
+            .get(&bet.id)
+            .await
+            .expect("Failed to read bet")
+        {
+            self.state
+                .status_markets
+                .remove(status_key(stored.status, &bet.id));
+        }
+        self.state
+            .status_markets
+            .insert(status_key(bet.status, &bet.id));
+    }
+
+    /// Have `CloseExpired` look at a bet again once `deadline` passes
+    fn schedule(&mut self, deadline: Timestamp, bet_id: &str) {
+        self.state.deadlines.insert(time_key(deadline, bet_id));
+    }
+
+    /// Resolve an expired bet with the outcome selected by its oracle source
+    async fn resolve_from_oracle(
+        &mut self,
+        bet_id: String,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let mut bet = self.bet(&bet_id).await?;
+
+        let oracle = bet.oracle.clone().ok_or(MicroBetError::NoOracle)?;
+
+        ensure!(bet.is_unresolved(), MicroBetError::AlreadyResolved);
+        ensure!(
+            self.runtime.system_time() >= bet.expires_at,
+            MicroBetError::BetNotExpired
+        );
+        // Past the deadline the bet can only be voided by `CloseExpired`
+        self.runtime.assert_before(bet.resolve_by);
+
+        let value = self.query_oracle_value(&bet_id);
+
+        self.set_status(&mut bet, BetStatus::Resolved);
+        bet.resolution = Some(Resolution::Outcome(oracle.outcome_index(value)));
+        bet.disputable_until = Some(self.dispute_deadline());
+        self.state
+            .bets
+            .insert(&bet_id, bet.clone())
+            .expect("Failed to update bet");
+        self.release_open_market(bet.creator).await;
+        self.settle_parlays_when_final(&bet).await;
+        self.emit(BetEvent::BetResolved { bet });
+        Ok(MicroBetResponse::Ok)
+    }
+
+    /// Place a parlay, locking in the current odds of every leg
+    ///
+    /// The winnings beyond the stake are set aside from the parlay reserve, so the parlay is
+    /// rejected if the reserve cannot cover them.
+    async fn place_parlay(
+        &mut self,
+        legs: Vec<ParlayLegInput>,
+        stake: Amount,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let owner = self.signer()?;
+        ensure!(stake > Amount::ZERO, MicroBetError::ZeroAmount);
+        self.check_stake(stake)?;
+        ensure!(
+            (2..=MAX_PARLAY_LEGS).contains(&legs.len()),
+            MicroBetError::InvalidParlay(format!(
+                "A parlay needs between 2 and {MAX_PARLAY_LEGS} legs"
+            ))
+        );
+
+        let now = self.runtime.system_time();
+        let mut parlay_legs = Vec::with_capacity(legs.len());
+        let mut payout = stake;
+        for ParlayLegInput {
+            bet_id,
+            outcome_index,
+        } in legs
+        {
+            ensure!(
+                parlay_legs
+                    .iter()
+                    .all(|leg: &ParlayLeg| leg.bet_id != bet_id),
+                MicroBetError::InvalidParlay(format!("Bet {bet_id} appears in several legs"))
+            );
+            let bet = self.bet(&bet_id).await?;
+            ensure!(
+                bet.status_at(now) == BetStatus::Open,
+                MicroBetError::BetNotOpen
+            );
+            ensure!(
+                (outcome_index as usize) < bet.outcomes.len(),
+                MicroBetError::InvalidOutcome
+            );
+            let odds = bet
+                .odds(outcome_index)
+                .ok_or_else(|| MicroBetError::UnpricedLeg(bet_id.clone()))?;
+            payout = mul_div(payout, odds, Amount::ONE);
+            parlay_legs.push(ParlayLeg {
+                bet_id,
+                outcome_index,
+                odds,
+            });
+        }
+
+        let liability = payout.saturating_sub(stake);
+        let reserve = *self.state.parlay_reserve.get();
+        ensure!(liability <= reserve, MicroBetError::InsufficientReserve);
+        self.state
+            .parlay_reserve
+            .set(reserve.saturating_sub(liability));
+
+        self.receive_from_account(owner, stake);
+
+        let parlay = Parlay {
+            id: self.state.next_parlay_id(),
+            owner,
+            legs: parlay_legs,
+            stake,
+            payout,
+            status: ParlayStatus::Open,
+            paid: Amount::ZERO,
+            created_at: now,
+        };
+        self.state
+            .parlays
+            .insert(&parlay.id, parlay.clone())
+            .expect("Failed to store parlay");
+        for leg in &parlay.legs {
+            self.state
+                .leg_parlays
+                .load_entry_mut(&leg.bet_id)
+                .await
+                .expect("Failed to load leg parlays")
+                .insert(&parlay.id)
+                .expect("Failed to index parlay");
+        }
+        self.state
+            .owner_parlays
+            .load_entry_mut(&owner)
+            .await
+            .expect("Failed to load owner parlays")
+            .insert(&parlay.id)
+            .expect("Failed to index parlay");
+        Ok(MicroBetResponse::Parlay(parlay))
+    }
+
+    /// Add the caller's tokens to the parlay reserve
+    fn fund_parlay_reserve(&mut self, amount: Amount) -> Result<MicroBetResponse, MicroBetError> {
+        let owner = self.signer()?;
+        ensure!(amount > Amount::ZERO, MicroBetError::ZeroAmount);
+        self.receive_from_account(owner, amount);
+        let reserve = self.state.parlay_reserve.get_mut();
+        reserve.saturating_add_assign(amount);
+        Ok(MicroBetResponse::Ok)
+    }
+
+    /// Settle the parlays on a bet resolved for good or cancelled, once they have a losing leg
+    /// or all their legs are decided
+    ///
+    /// Voided and cancelled legs are left out of the payout. Whatever the parlay does not
+    /// pay out of its potential payout goes back into the reserve.
+    async fn settle_parlays(&mut self, bet_id: &str, now: Timestamp) {
+        let parlay_ids = match self
+            .state
+            .leg_parlays
+            .try_load_entry(bet_id)
+            .await
+            .expect("Failed to load leg parlays")
+        {
+            Some(parlays) => parlays.indices().await.expect("Failed to read leg parlays"),
+            None => return,
+        };
+        // The bet is decided, so it has nothing left to trigger
+        self.state
+            .leg_parlays
+            .remove_entry(bet_id)
+            .expect("Failed to update leg parlays");
+
+        for parlay_id in parlay_ids {
+            let mut parlay = self
+                .state
+                .parlays
+                .get(&parlay_id)
+                .await
+                .expect("Failed to read parlay")
+                .expect("Parlay not found");
+
+            let mut status = ParlayStatus::Void;
+            let mut payout = parlay.stake;
+            for leg in &parlay.legs {
+                let bet = self
+                    .state
+                    .bets
+                    .get(&leg.bet_id)
+                    .await
+                    .expect("Failed to read bet")
+                    .expect("Bet not found");
+                if bet.status == BetStatus::Cancelled {
+                    continue;
+                }
+                if !bet.is_final_at(now) {
+                    status = ParlayStatus::Open;
+                    continue;
+                }
+                match bet.resolution {
+                    Some(Resolution::Outcome(index)) if index == leg.outcome_index => {
+                        payout = mul_div(payout, leg.odds, Amount::ONE);
+                        if status == ParlayStatus::Void {
+                            status = ParlayStatus::Won;
+                        }
+                    }
+                    Some(Resolution::Outcome(_)) => {
+                        status = ParlayStatus::Lost;
+                        break;
+                    }
+                    Some(Resolution::Invalid) | None => {}
+                }
+            }
+            if status == ParlayStatus::Open {
+                continue;
+            }
+
+            parlay.paid = match status {
+                ParlayStatus::Lost => Amount::ZERO,
+                _ => payout,
+            };
+            parlay.status = status;
+            if parlay.paid > Amount::ZERO {
+                self.send_to(parlay.paid, parlay.owner);
+            }
+            let reserve = self.state.parlay_reserve.get_mut();
+            reserve.saturating_add_assign(parlay.payout.saturating_sub(parlay.paid));
+
+            for leg in &parlay.legs {
+                if leg.bet_id != bet_id {
+                    self.state
+                        .leg_parlays
+                        .load_entry_mut(&leg.bet_id)
+                        .await
+                        .expect("Failed to load leg parlays")
+                        .remove(&parlay_id)
+                        .expect("Failed to update leg parlays");
+                }
+            }
+            self.state
+                .parlays
+                .insert(&parlay_id, parlay)
+                .expect("Failed to update parlay");
+        }
+    }
+
+    /// Cancel a bet, refunding a pending challenge bond, so that every position can reclaim
+    /// its stake with `ClaimWinnings`
+    ///
+    /// The creator can only cancel a bet nobody has traded on yet. The admin can cancel any bet
+    /// whose payouts have not started, i.e. one that is not resolved for good.
+    async fn cancel_bet(&mut self, bet_id: String) -> Result<MicroBetResponse, MicroBetError> {
+        let mut bet = self.bet(&bet_id).await?;
+        let caller = self.signer()?;
+
+        let now = self.runtime.system_time();
+        ensure!(
+            bet.status != BetStatus::Cancelled && !bet.is_final_at(now),
+            MicroBetError::NotCancellable
+        );
+        if !self.is_admin(caller) {
+            ensure!(bet.creator == caller, MicroBetError::NotCreatorOrAdmin);
+            let stats = self
+                .state
+                .market_stats
+                .get(&bet_id)
+                .await
+                .expect("Failed to read market stats")
+                .unwrap_or_default();
+            ensure!(stats.volume == Amount::ZERO, MicroBetError::BetHasWagers);
+        }
+
+        let refunded_challenge = match (bet.status, &bet.challenge) {
+            (BetStatus::Disputed, Some(challenge)) => Some((challenge.challenger, challenge.bond)),
+            _ => None,
+        };
+
+        if bet.is_unresolved() {
+            self.release_open_market(bet.creator).await;
+        }
+        // The creator gets the deposit back only when withdrawing an untraded bet
+        if caller != bet.creator {
+            self.forfeit_deposit(&mut bet).await;
+        }
+
+        self.set_status(&mut bet, BetStatus::Cancelled);
+        self.state
+            .bets
+            .insert(&bet_id, bet.clone())
+            .expect("Failed to update bet");
+        self.emit(BetEvent::BetCancelled { bet });
+        self.settle_parlays(&bet_id, now).await;
+
+        if let Some((challenger, bond)) = refunded_challenge {
+            self.send_to(bond, challenger);
+        }
+        Ok(MicroBetResponse::Ok)
+    }
+
+    /// When a resolution made now stops being open to challenges
+    fn dispute_deadline(&mut self) -> Timestamp {
+        let dispute_window = self
+            .state
+            .instantiation_argument
+            .get()
+            .as_ref()
+            .map(|arg| arg.dispute_window())
+            .unwrap_or(DEFAULT_DISPUTE_WINDOW);
+        self.runtime.system_time().saturating_add(dispute_window)
+    }
+
+    /// How long arbiters have to settle a dispute before `CloseExpired` voids the bet
+    fn arbitration_window(&self) -> TimeDelta {
+        self.state
+            .instantiation_argument
+            .get()
+            .as_ref()
+            .map(|arg| arg.arbitration_window())
+            .unwrap_or(DEFAULT_ARBITRATION_WINDOW)
+    }
+
+    /// Challenge the resolution of a bet, escrowing the challenge bond until the dispute is
+    /// settled
+    async fn challenge_resolution(
+        &mut self,
+        bet_id: String,
+        proposed: Resolution,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let mut bet = self.bet(&bet_id).await?;
+        let challenger = self.signer()?;
+
+        let argument = self
+            .state
+            .instantiation_argument
+            .get()
+            .clone()
+            .unwrap_or_default();
+        ensure!(
+            argument.dispute_quorum().is_some(),
+            MicroBetError::DisputesDisabled
+        );
+
+        ensure!(
+            bet.status == BetStatus::Resolved,
+            MicroBetError::NotResolved
+        );
+        ensure!(bet.challenge.is_none(), MicroBetError::AlreadyChallenged);
+        let deadline = bet
+            .disputable_until
+            .expect("Resolution has no dispute window");
+        ensure!(
+            self.runtime.system_time() < deadline,
+            MicroBetError::DisputeWindowPassed
+        );
+        self.runtime.assert_before(deadline);
+
+        ensure!(
+            bet.resolution != Some(proposed),
+            MicroBetError::SameResolution
+        );
+        if let Resolution::Outcome(index) = proposed {
+            ensure!(
+                (index as usize) < bet.outcomes.len(),
+                MicroBetError::InvalidOutcome
+            );
+        }
+
+        let bond = argument.challenge_bond_amount();
+        self.receive_from_account(challenger, bond);
+
+        let now = self.runtime.system_time();
+        self.set_status(&mut bet, BetStatus::Disputed);
+        bet.challenge = Some(Challenge {
+            challenger,
+            bond,
+            proposed,
+            raised_at: now,
+            votes: Vec::new(),
+        });
+        self.schedule(now.saturating_add(argument.arbitration_window()), &bet_id);
+        self.state
+            .bets
+            .insert(&bet_id, bet.clone())
+            .expect("Failed to update bet");
+        self.emit(BetEvent::BetDisputed { bet });
+        Ok(MicroBetResponse::Ok)
+    }
+
+    /// Record an arbiter's vote on a disputed bet, settling the dispute once a resolution has
+    /// a majority
+    ///
+    /// If the challenge is upheld the bond is returned to the challenger, otherwise it goes to
+    /// the creator of the bet. Either way the settled resolution is final.
+    async fn settle_dispute(
+        &mut self,
+        bet_id: String,
+        resolution: Resolution,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let mut bet = self.bet(&bet_id).await?;
+        let arbiter = self.signer()?;
+
+        let argument = self
+            .state
+            .instantiation_argument
+            .get()
+            .clone()
+            .unwrap_or_default();
+        ensure!(argument.is_arbiter(arbiter), MicroBetError::NotArbiter);
+        let quorum = argument
+            .dispute_quorum()
+            .ok_or(MicroBetError::DisputesDisabled)?;
+
+        ensure!(
+            bet.status == BetStatus::Disputed,
+            MicroBetError::NotDisputed
+        );
+        if let Resolution::Outcome(index) = resolution {
+            ensure!(
+                (index as usize) < bet.outcomes.len(),
+                MicroBetError::InvalidOutcome
+            );
+        }
+
+        let challenge = bet
+            .challenge
+            .as_mut()
+            .expect("Disputed bet has no challenge");
+        challenge.votes.retain(|vote| vote.arbiter != arbiter);
+        challenge.votes.push(ArbiterVote {
+            arbiter,
+            resolution,
+        });
+        let votes = challenge
+            .votes
+            .iter()
+            .filter(|vote| vote.resolution == resolution)
+            .count();
+
+        if votes < quorum {
+            self.state
+                .bets
+                .insert(&bet_id, bet)
+                .expect("Failed to update bet");
+            return Ok(MicroBetResponse::Ok);
+        }
+
+        let challenger = challenge.challenger;
+        let bond = challenge.bond;
+        let is_overturned = bet.resolution != Some(resolution);
+        let bond_recipient = if is_overturned {
+            challenger
+        } else {
+            bet.creator
+        };
+        // A creator whose resolution is overturned loses the creation deposit to the challenger
+        let forfeited_deposit = if is_overturned {
+            std::mem::take(&mut bet.deposit)
+        } else {
+            Amount::ZERO
+        };
+
+        self.set_status(&mut bet, BetStatus::Resolved);
+        bet.resolution = Some(resolution);
+        let now = self.runtime.system_time();
+        bet.disputable_until = Some(now);
+        self.state
+            .bets
+            .insert(&bet_id, bet.clone())
+            .expect("Failed to update bet");
+        self.emit(BetEvent::BetResolved { bet });
+        self.settle_parlays(&bet_id, now).await;
+
+        self.send_to(bond, bond_recipient);
+        if forfeited_deposit > Amount::ZERO {
+            self.send_to(forfeited_deposit, challenger);
+        }
+        Ok(MicroBetResponse::Ok)
+    }
+
+    /// Ask this application's service to fetch the number deciding a bet's outcome
+    ///
+    /// The service performs the HTTP request and only returns the extracted number, so that
+    /// validators agree on the result even if the rest of the HTTP response differs.
+    fn query_oracle_value(&mut self, bet_id: &str) -> f64 {
+        let application_id = self.runtime.application_id();
+        let request = async_graphql::Request::new(
+            "query OracleValue($betId: String!) { oracleValue(betId: $betId) }",
+        )
+        .variables(async_graphql::Variables::from_json(
+            serde_json::json!({ "betId": bet_id }),
+        ));
+
+        let response = self.runtime.query_service(application_id, request);
+
+        let async_graphql::Value::Object(data) = response.data else {
+            panic!("Unexpected response from service: {:#?}", response.errors);
+        };
+        let Some(async_graphql::Value::String(value)) = data.get("oracleValue") else {
+            panic!("Failed to fetch oracle value: {:#?}", response.errors);
+        };
+        parse_decimal(value).expect("Oracle value is not a number")
+    }
+
+    /// Pay out the caller's unclaimed positions on a resolved bet
+    async fn claim_winnings(&mut self, bet_id: String) -> Result<MicroBetResponse, MicroBetError> {
+        let owner = self.signer()?;
+
+        let payout = self.collect_winnings(owner, &bet_id).await?;
+
+        self.send_to(payout, owner);
+        Ok(MicroBetResponse::Payout(payout))
+    }
+
+    /// Mark the owner's positions on a resolved bet as claimed, and return what they are owed
+    async fn collect_winnings(
+        &mut self,
+        owner: AccountOwner,
+        bet_id: &str,
+    ) -> Result<Amount, MicroBetError> {
+        let mut bet = self.bet(bet_id).await?;
+        self.check_payable(&bet)?;
+
+        let mut user_bets = self.positions(owner, bet_id).await;
+
+        // Fees come out of winnings, but not out of stakes or liquidity
+        let mut payout = Amount::ZERO;
+        let (mut protocol_fee, mut creator_fee, mut lp_fee) =
+            (Amount::ZERO, Amount::ZERO, Amount::ZERO);
+        for user_bet in user_bets.iter_mut().filter(|user_bet| !user_bet.claimed) {
+            payout.saturating_add_assign(bet.payout(user_bet));
+            let (protocol, creator, lp) = bet.fees(user_bet);
+            protocol_fee.saturating_add_assign(protocol);
+            creator_fee.saturating_add_assign(creator);
+            lp_fee.saturating_add_assign(lp);
+            user_bet.claimed = true;
+        }
+        let fees = protocol_fee
+            .saturating_add(creator_fee)
+            .saturating_add(lp_fee);
+        payout = payout.saturating_sub(fees);
+
+        // The creator also gets back the creation deposit, unless it was forfeited
+        let claims_deposit = bet.creator == owner && bet.deposit > Amount::ZERO;
+        if claims_deposit {
+            payout.saturating_add_assign(bet.deposit);
+        }
+
+        ensure!(
+            payout.saturating_add(fees) > Amount::ZERO,
+            MicroBetError::NoWinnings
+        );
+
+        if let Some(fee_recipient) = self.runtime.application_parameters().fee_recipient {
+            self.accrue_fee(fee_recipient, protocol_fee).await;
+        }
+        self.accrue_fee(bet.creator, creator_fee).await;
+
+        if claims_deposit || lp_fee > Amount::ZERO {
+            if claims_deposit {
+                bet.deposit = Amount::ZERO;
+            }
+            // Shared between the liquidity providers when they withdraw
+            bet.lp_fees.saturating_add_assign(lp_fee);
+            self.state
+                .bets
+                .insert(bet_id, bet)
+                .expect("Failed to update bet");
+        }
+        self.set_positions(owner, bet_id, user_bets).await;
+        Ok(payout)
+    }
+
+    /// Check that the payouts of a bet have started, i.e. that it is resolved and can no longer
+    /// be challenged, or cancelled
+    fn check_payable(&mut self, bet: &Bet) -> Result<(), MicroBetError> {
+        if bet.status != BetStatus::Cancelled {
+            ensure!(
+                bet.status == BetStatus::Resolved,
+                MicroBetError::NotResolved
+            );
+            ensure!(
+                bet.is_final_at(self.runtime.system_time()),
+                MicroBetError::StillDisputable
+            );
+        }
+        Ok(())
+    }
+
+    /// Provide liquidity to an open bet in exchange for liquidity provider shares
+    async fn add_liquidity(
+        &mut self,
+        bet_id: String,
+        amount: Amount,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let provider = self.signer()?;
+        ensure!(amount > Amount::ZERO, MicroBetError::ZeroAmount);
+
+        let mut bet = self.bet(&bet_id).await?;
+        ensure!(bet.status == BetStatus::Open, MicroBetError::BetNotOpen);
+        ensure!(
+            self.runtime.system_time() < bet.expires_at,
+            MicroBetError::BetExpired
+        );
+        // Also reject liquidity in blocks validated after the expiry
+        self.runtime.assert_before(bet.expires_at);
+
+        self.receive_from_account(provider, amount);
+        let (shares, returned) = bet.add_liquidity(amount);
+        self.state
+            .bets
+            .insert(&bet_id, bet.clone())
+            .expect("Failed to update bet");
+        let position = self
+            .record_liquidity(provider, &bet_id, shares, amount)
+            .await;
+
+        // The shares the market maker did not take are the provider's to trade or redeem. They
+        // cost nothing beyond the liquidity, which is what a cancellation refunds.
+        let timestamp = self.runtime.system_time();
+        for (outcome_index, shares) in returned.into_iter().enumerate() {
+            if shares > Amount::ZERO {
+                self.push_position(UserBet {
+                    bet_id: bet_id.clone(),
+                    owner: provider,
+                    outcome_index: outcome_index as u32,
+                    amount: Amount::ZERO,
+                    shares,
+                    timestamp,
+                    claimed: false,
+                })
+                .await;
+            }
+        }
+
+        self.emit(BetEvent::LiquidityAdded {
+            bet,
+            provider,
+            amount,
+        });
+        Ok(MicroBetResponse::Liquidity(position))
+    }
+
+    /// Add liquidity provider shares to a provider's position in a bet
+    async fn record_liquidity(
+        &mut self,
+        provider: AccountOwner,
+        bet_id: &str,
+        shares: Amount,
+        amount: Amount,
+    ) -> LiquidityPosition {
+        let positions = self
+            .state
+            .liquidity_positions
+            .load_entry_mut(bet_id)
+            .await
+            .expect("Failed to read liquidity positions");
+        let mut position = positions
+            .get(&provider)
+            .await
+            .expect("Failed to read liquidity positions")
+            .unwrap_or_else(|| LiquidityPosition {
+                bet_id: bet_id.to_string(),
+                provider,
+                shares: Amount::ZERO,
+                contributed: Amount::ZERO,
+                fees_claimed: Amount::ZERO,
+                withdrawn: false,
+            });
+        position.shares.saturating_add_assign(shares);
+        position.contributed.saturating_add_assign(amount);
+        positions
+            .insert(&provider, position.clone())
+            .expect("Failed to update liquidity positions");
+
+        self.state
+            .owner_liquidity
+            .load_entry_mut(&provider)
+            .await
+            .expect("Failed to read owner liquidity")
+            .insert(bet_id)
+            .expect("Failed to update owner liquidity");
+        position
+    }
+
+    /// Pay out the caller's share of the liquidity of a bet, and the liquidity provider fees
+    /// accrued to it since the last withdrawal
+    async fn withdraw_liquidity(
+        &mut self,
+        bet_id: String,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let provider = self.signer()?;
+        let bet = self.bet(&bet_id).await?;
+        self.check_payable(&bet)?;
+
+        let mut position = match self
+            .state
+            .liquidity_positions
+            .try_load_entry(&bet_id)
+            .await
+            .expect("Failed to read liquidity positions")
+        {
+            Some(positions) => positions
+                .get(&provider)
+                .await
+                .expect("Failed to read liquidity positions")
+                .ok_or(MicroBetError::NoLiquidity)?,
+            None => return Err(MicroBetError::NoLiquidity),
+        };
+
+        let mut payout = Amount::ZERO;
+        if !position.withdrawn {
+            payout = position.portion(&bet, self.liquidity_payout(&bet).await);
+            position.withdrawn = true;
+        }
+        // More fees accrue as winners claim, so providers can come back for them
+        let fees = position
+            .portion(&bet, bet.lp_fees)
+            .saturating_sub(position.fees_claimed);
+        position.fees_claimed.saturating_add_assign(fees);
+        payout.saturating_add_assign(fees);
+        ensure!(payout > Amount::ZERO, MicroBetError::NoLiquidity);
+
+        self.state
+            .liquidity_positions
+            .load_entry_mut(&bet_id)
+            .await
+            .expect("Failed to read liquidity positions")
+            .insert(&provider, position)
+            .expect("Failed to update liquidity positions");
+        self.send_to(payout, provider);
+        Ok(MicroBetResponse::Payout(payout))
+    }
+
+    /// Take away a bet's creation deposit, crediting it to the protocol's fee recipient, or
+    /// else to the admin
+    async fn forfeit_deposit(&mut self, bet: &mut Bet) {
+        let admin = self
+            .state
+            .instantiation_argument
+            .get()
+            .as_ref()
+            .and_then(|arg| arg.admin);
+        let Some(recipient) = self
+            .runtime
+            .application_parameters()
+            .fee_recipient
+            .or(admin)
+        else {
+            return;
+        };
+        let deposit = std::mem::take(&mut bet.deposit);
+        self.accrue_fee(recipient, deposit).await;
+    }
+
+    /// Free one of the creator's slots for unresolved bets
+    async fn release_open_market(&mut self, creator: AccountOwner) {
+        let open_markets = self
+            .state
+            .open_markets
+            .get(&creator)
+            .await
+            .expect("Failed to read open markets")
+            .unwrap_or_default();
+        if open_markets > 1 {
+            self.state
+                .open_markets
+                .insert(&creator, open_markets - 1)
+                .expect("Failed to update open markets");
+        } else {
+            self.state
+                .open_markets
+                .remove(&creator)
+                .expect("Failed to update open markets");
+        }
+    }
+
+    /// Credit a fee to its recipient, to be withdrawn with `WithdrawFees`
+    async fn accrue_fee(&mut self, recipient: AccountOwner, fee: Amount) {
+        if fee == Amount::ZERO {
+            return;
+        }
+        let mut accrued = self
+            .state
+            .fees
+            .get(&recipient)
+            .await
+            .expect("Failed to read fees")
+            .unwrap_or_default();
+        accrued.saturating_add_assign(fee);
+        self.state
+            .fees
+            .insert(&recipient, accrued)
+            .expect("Failed to update fees");
+    }
+
+    /// Pay out every fee accrued to the caller
+    async fn withdraw_fees(&mut self) -> Result<MicroBetResponse, MicroBetError> {
+        let recipient = self.signer()?;
+        let accrued = self
+            .state
+            .fees
+            .get(&recipient)
+            .await
+            .expect("Failed to read fees")
+            .unwrap_or_default();
+        ensure!(accrued > Amount::ZERO, MicroBetError::NoFees);
+
+        self.state
+            .fees
+            .remove(&recipient)
+            .expect("Failed to update fees");
+        self.send_to(accrued, recipient);
+        Ok(MicroBetResponse::Payout(accrued))
+    }
+
+    /// What the liquidity providers of a bet get back, in total
+    ///
+    /// When a parimutuel bet is cancelled, that is what they put in. When a market maker bet
+    /// is cancelled, that is whatever collateral is left once every position's stake is
+    /// refunded.
+    async fn liquidity_payout(&self, bet: &Bet) -> Amount {
+        if bet.status != BetStatus::Cancelled {
+            return bet.liquidity_payout();
+        }
+        if bet.pricing == PricingMode::Parimutuel {
+            return bet.liquidity;
+        }
+        let stats = self
+            .state
+            .market_stats
+            .get(&bet.id)
+            .await
+            .expect("Failed to read market stats")
+            .unwrap_or_default();
+        bet.liquidity
+            .saturating_add(bet.total_pool())
+            .saturating_sub(stats.stake)
+    }
+
+    /// Transfer tokens from the owner's account into the application's escrow account
+    fn receive_from_account(&mut self, owner: AccountOwner, amount: Amount) {
+        let target_account = Account {
+            chain_id: self.runtime.chain_id(),
+            owner: self.runtime.application_id().into(),
+        };
+        self.transfer(owner, amount, target_account);
+    }
+
+    /// Transfer tokens from the owner's account into the application's escrow account on the
+    /// hub chain
+    fn send_to_hub(&mut self, owner: AccountOwner, amount: Amount) {
+        let target_account = Account {
+            chain_id: self.runtime.application_creator_chain_id(),
+            owner: self.runtime.application_id().into(),
+        };
+        self.transfer(owner, amount, target_account);
+    }
+
+    /// Transfer tokens from the application's escrow account to the owner's account
+    fn send_to(&mut self, amount: Amount, owner: AccountOwner) {
+        let target_account = Account {
+            chain_id: self.runtime.chain_id(),
+            owner,
+        };
+        let escrow = self.runtime.application_id().into();
+        self.transfer(escrow, amount, target_account);
+    }
+
+    /// Transfer tokens through the configured fungible token application, falling back to the
+    /// chain's native token when none is configured
+    fn transfer(&mut self, owner: AccountOwner, amount: Amount, target_account: Account) {
+        match self.runtime.application_parameters().fungible_app_id {
+            Some(fungible_id) => {
+                let transfer = fungible::FungibleOperation::Transfer {
+                    owner,
+                    amount,
+                    target_account: fungible::Account {
+                        chain_id: target_account.chain_id,
+                        owner: target_account.owner,
+                    },
+                };
+                self.runtime.call_application(true, fungible_id, &transfer);
+            }
+            None => self.runtime.transfer(owner, target_account, amount),
+        }
+    }
+}
+
+/// The ID of the bet in a key of the deadline index
+fn bet_of_deadline_key(key: &[u8]) -> String {
+    let bet_id = key[size_of::<u64>()..].to_vec();
+    String::from_utf8(bet_id).expect("Invalid deadline key")
+}
+
+/// Take shares of an outcome out of unclaimed positions, oldest first, and return the stake
+/// they carried
+fn split_off_shares(
+    user_bets: &mut Vec<UserBet>,
+    outcome_index: u32,
+    shares: Amount,
+) -> Result<Amount, MicroBetError> {
+    let mut remaining = shares;
+    let mut stake = Amount::ZERO;
+    for user_bet in user_bets
+        .iter_mut()
+        .filter(|user_bet| user_bet.outcome_index == outcome_index && !user_bet.claimed)
+    {
+        if remaining == Amount::ZERO {
+            break;
+        }
+        let taken = remaining.min(user_bet.shares);
+        let cost = mul_div(user_bet.amount, taken, user_bet.shares);
+        user_bet.amount = user_bet.amount.saturating_sub(cost);
+        user_bet.shares = user_bet.shares.saturating_sub(taken);
+        stake.saturating_add_assign(cost);
+        remaining = remaining.saturating_sub(taken);
+    }
+    ensure!(remaining == Amount::ZERO, MicroBetError::InsufficientShares);
+    user_bets.retain(|user_bet| user_bet.shares > Amount::ZERO);
+    Ok(stake)
+}
+
+/// This implementation is only nonempty in the service.
+#[ComplexObject]
+impl MicroBetState {}
+
+#[cfg(test)]
+mod tests {
+    use linera_sdk::{
+        linera_base_types::{
+            Account, AccountOwner, Amount, ApplicationId, ChainId, CryptoHash, TimeDelta, Timestamp,
+        },
+        util::BlockingWait,
+        views::View,
+        Contract, ContractRuntime,
+    };
+    use micro_bet::{
+        status_key, BetStatus, GeoPoint, InstantiationArgument, MarketMetadata, MarketSort,
+        Message, MicroBetAbi, MicroBetError, MicroBetResponse, Operation, Parameters,
+        ParlayLegInput, ParlayStatus, Resolution, MAX_EXPIRED_PER_CLOSE,
+    };
+
+    use super::{MicroBetContract, MicroBetState};
+
+    const INITIAL_BALANCE: Amount = Amount::from_tokens(100);
+
+    #[test]
+    fn wager_lifecycle() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        let bet_id = create_bet(&mut contract);
+
+        place_bet(&mut contract, alice(), &bet_id, 0, Amount::from_tokens(3))
+            .expect("Wager should be accepted");
+        place_bet(&mut contract, bob(), &bet_id, 1, Amount::ONE).expect("Wager should be accepted");
+
+        let bet = contract
+            .state
+            .bets
+            .get(&bet_id)
+            .blocking_wait()
+            .expect("Failed to read bet")
+            .expect("Bet not found");
+        assert_eq!(bet.pools, vec![Amount::from_tokens(3), Amount::ONE]);
+        assert_eq!(
+            contract.runtime.owner_balance(alice()),
+            INITIAL_BALANCE.saturating_sub(Amount::from_tokens(3))
+        );
+
+        advance_time(&mut contract, TimeDelta::from_secs(61));
+        let response = execute(
+            &mut contract,
+            creator(),
+            Operation::ResolveBet {
+                bet_id: bet_id.clone(),
+                resolution: Resolution::Outcome(0),
+            },
+        );
+        assert!(matches!(response, Ok(MicroBetResponse::Ok)));
+
+        // Payouts wait until the resolution can no longer be challenged
+        let claim = Operation::ClaimWinnings {
+            bet_id: bet_id.clone(),
+        };
+        let response = execute(&mut contract, alice(), claim);
+        assert_eq!(response.unwrap_err(), MicroBetError::StillDisputable);
+
+        advance_time(&mut contract, TimeDelta::from_secs(24 * 60 * 60));
+        let claim = Operation::ClaimWinnings {
+            bet_id: bet_id.clone(),
+        };
+        let response = execute(&mut contract, alice(), claim);
+        assert!(matches!(
+            response,
+            Ok(MicroBetResponse::Payout(payout)) if payout == Amount::from_tokens(4)
+        ));
+        assert_eq!(
+            contract.runtime.owner_balance(alice()),
+            INITIAL_BALANCE.saturating_add(Amount::ONE)
+        );
+
+        let claim = Operation::ClaimWinnings { bet_id };
+        let response = execute(&mut contract, bob(), claim);
+        assert_eq!(response.unwrap_err(), MicroBetError::NoWinnings);
+    }
+
+    #[test]
+    fn only_creator_or_admin_can_resolve() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        let bet_id = create_bet(&mut contract);
+
+        let resolve = Operation::ResolveBet {
+            bet_id,
+            resolution: Resolution::Outcome(0),
+        };
+        let response = execute(&mut contract, bob(), resolve);
+        assert_eq!(response.unwrap_err(), MicroBetError::NotCreatorOrAdmin);
+    }
+
+    #[test]
+    fn unsigned_operations_are_rejected() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        let bet_id = create_bet(&mut contract);
+
+        contract.runtime.set_authenticated_signer(None);
+        let operation = Operation::PlaceBet {
+            bet_id,
+            outcome_index: 0,
+            amount: Amount::ONE,
+        };
+        let response = contract.execute_operation(operation).blocking_wait();
+        assert_eq!(response.unwrap_err(), MicroBetError::NotAuthenticated);
+    }
+
+    #[test]
+    fn wagers_are_rejected_after_expiry() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        let bet_id = create_bet(&mut contract);
+
+        advance_time(&mut contract, TimeDelta::from_secs(60));
+        let response = place_bet(&mut contract, alice(), &bet_id, 0, Amount::ONE);
+        assert_eq!(response.unwrap_err(), MicroBetError::BetExpired);
+        assert_eq!(contract.runtime.owner_balance(alice()), INITIAL_BALANCE);
+    }
+
+    #[test]
+    fn overlong_bets_are_rejected() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        let operation = Operation::CreateBet {
+            question: "Will it rain in a thousand years?".to_string(),
+            duration_seconds: u64::MAX,
+            outcomes: None,
+            liquidity: None,
+            seed: None,
+            oracle: None,
+            creator_fee_bps: None,
+            lp_fee_bps: None,
+            metadata: None,
+        };
+        let response = execute(&mut contract, creator(), operation);
+        assert!(matches!(response, Err(MicroBetError::InvalidBet(_))));
+    }
+
+    #[test]
+    fn due_bets_are_closed_then_voided() {
+        let mut contract = create_and_instantiate(InstantiationArgument {
+            resolution_window_seconds: Some(60),
+            ..InstantiationArgument::default()
+        });
+        let bet_ids = (0..=MAX_EXPIRED_PER_CLOSE)
+            .map(|_| create_bet(&mut contract))
+            .collect::<Vec<_>>();
+
+        execute(&mut contract, alice(), Operation::CloseExpired).expect("Anyone can close");
+        assert!(bet_ids
+            .iter()
+            .all(|bet_id| bet_status(&contract, bet_id) == BetStatus::Open));
+
+        // Only a limited number of due bets are looked at per request
+        advance_time(&mut contract, TimeDelta::from_secs(60));
+        execute(&mut contract, alice(), Operation::CloseExpired).expect("Anyone can close");
+        let closed = bet_ids
+            .iter()
+            .filter(|bet_id| bet_status(&contract, bet_id) == BetStatus::Closed)
+            .count();
+        assert_eq!(closed, MAX_EXPIRED_PER_CLOSE);
+        execute(&mut contract, alice(), Operation::CloseExpired).expect("Anyone can close");
+        assert!(bet_ids
+            .iter()
+            .all(|bet_id| bet_status(&contract, bet_id) == BetStatus::Closed));
+
+        advance_time(&mut contract, TimeDelta::from_secs(60));
+        execute(&mut contract, alice(), Operation::CloseExpired).expect("Anyone can close");
+        execute(&mut contract, alice(), Operation::CloseExpired).expect("Anyone can close");
+        assert!(bet_ids
+            .iter()
+            .all(|bet_id| bet_status(&contract, bet_id) == BetStatus::Resolved));
+        let remaining = contract
+            .state
+            .deadlines
+            .keys()
+            .blocking_wait()
+            .expect("Failed to read deadlines");
+        assert!(remaining.is_empty());
+    }
+
+    #[test]
+    fn bets_can_have_a_location() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        let mut create = |location| {
+            let operation = Operation::CreateBet {
+                question: "Will it snow in Zurich tomorrow?".to_string(),
+                duration_seconds: 60,
+                outcomes: None,
+                liquidity: None,
+                seed: None,
+                oracle: None,
+                creator_fee_bps: None,
+                lp_fee_bps: None,
+                metadata: Some(MarketMetadata {
+                    location: Some(location),
+                    ..MarketMetadata::default()
+                }),
+            };
+            execute(&mut contract, creator(), operation)
+        };
+
+        let zurich = GeoPoint {
+            latitude_micros: 47_376_887,
+            longitude_micros: 8_541_694,
+        };
+        let bet_id = match create(zurich) {
+            Ok(MicroBetResponse::BetCreated(bet_id)) => bet_id,
+            response => panic!("Unexpected response: {response:?}"),
+        };
+        let off_the_map = GeoPoint {
+            latitude_micros: 91_000_000,
+            longitude_micros: 0,
+        };
+        assert!(matches!(
+            create(off_the_map),
+            Err(MicroBetError::InvalidBet(_))
+        ));
+
+        let bet = contract
+            .state
+            .bets
+            .get(&bet_id)
+            .blocking_wait()
+            .expect("Failed to read bet")
+            .expect("Bet not found");
+        let location = bet.metadata.location.expect("Location not stored");
+        assert_eq!(location.latitude_micros, zurich.latitude_micros);
+        assert_eq!(location.longitude_micros, zurich.longitude_micros);
+    }
+
+    #[test]
+    fn markets_are_indexed_in_every_order() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        let first = create_bet(&mut contract);
+        advance_time(&mut contract, TimeDelta::from_secs(1));
+        let second = create_bet(&mut contract);
+        place_bet(&mut contract, alice(), &second, 0, Amount::ONE).expect("Wager is valid");
+
+        let indexes = [
+            (&contract.state.created_markets, MarketSort::Created),
+            (&contract.state.expiry_markets, MarketSort::Expiry),
+            (&contract.state.volume_markets, MarketSort::Volume),
+        ];
+        for (index, order) in indexes {
+            let bet_ids = index
+                .keys()
+                .blocking_wait()
+                .expect("Failed to read market index")
+                .iter()
+                .map(|key| order.bet_of_key(key))
+                .collect::<Vec<_>>();
+            let expected = match order {
+                MarketSort::Created | MarketSort::Expiry => [first.clone(), second.clone()],
+                MarketSort::Volume => [second.clone(), first.clone()],
+            };
+            assert_eq!(bet_ids, expected, "{order:?}");
+        }
+    }
+
+    #[test]
+    fn markets_are_indexed_by_status() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        let open = create_bet(&mut contract);
+        let resolved = create_bet(&mut contract);
+        resolve(&mut contract, &resolved, 0);
+
+        let keys = contract
+            .state
+            .status_markets
+            .keys()
+            .blocking_wait()
+            .expect("Failed to read status index");
+        assert_eq!(
+            keys,
+            [
+                status_key(BetStatus::Open, &open),
+                status_key(BetStatus::Resolved, &resolved),
+            ]
+        );
+    }
+
+    #[test]
+    fn parlays_settle_when_their_legs_are_final() {
+        let mut contract = create_and_instantiate(InstantiationArgument {
+            admin: Some(creator()),
+            dispute_window_seconds: Some(60),
+            ..InstantiationArgument::default()
+        });
+        let first = create_bet(&mut contract);
+        let second = create_bet(&mut contract);
+        for bet_id in [&first, &second] {
+            place_bet(&mut contract, alice(), bet_id, 0, Amount::ONE).expect("Wager is valid");
+            place_bet(&mut contract, bob(), bet_id, 1, Amount::ONE).expect("Wager is valid");
+        }
+        let fund = Operation::FundParlayReserve {
+            amount: Amount::from_tokens(10),
+        };
+        execute(&mut contract, creator(), fund).expect("Anyone can fund the reserve");
+        let winning = place_parlay(&mut contract, alice(), [(&first, 0), (&second, 0)]);
+        let losing = place_parlay(&mut contract, bob(), [(&first, 1), (&second, 0)]);
+
+        // A resolution only counts once it can no longer be challenged
+        resolve(&mut contract, &first, 0);
+        assert_eq!(parlay_status(&contract, winning), ParlayStatus::Open);
+        assert_eq!(parlay_status(&contract, losing), ParlayStatus::Open);
+        advance_time(&mut contract, TimeDelta::from_secs(60));
+        execute(&mut contract, bob(), Operation::CloseExpired).expect("Anyone can close");
+        assert_eq!(parlay_status(&contract, winning), ParlayStatus::Open);
+        assert_eq!(parlay_status(&contract, losing), ParlayStatus::Lost);
+
+        // Cancelling the last leg settles the parlay right away
+        execute(
+            &mut contract,
+            creator(),
+            Operation::CancelBet {
+                bet_id: second.clone(),
+            },
+        )
+        .expect("The admin can cancel an unresolved bet");
+        assert_eq!(parlay_status(&contract, winning), ParlayStatus::Won);
+        assert_eq!(
+            contract.runtime.owner_balance(alice()),
+            INITIAL_BALANCE.saturating_sub(Amount::ONE)
+        );
+    }
+
+    #[test]
+    fn unsettled_disputes_are_voided() {
+        let mut contract = create_and_instantiate(InstantiationArgument {
+            admin: Some(creator()),
+            arbitration_window_seconds: Some(60),
+            ..InstantiationArgument::default()
+        });
+        let bet_id = create_bet(&mut contract);
+        place_bet(&mut contract, alice(), &bet_id, 0, Amount::ONE).expect("Wager is valid");
+        place_bet(&mut contract, bob(), &bet_id, 1, Amount::ONE).expect("Wager is valid");
+        resolve(&mut contract, &bet_id, 0);
+        let challenge = Operation::ChallengeResolution {
+            bet_id: bet_id.clone(),
+            proposed: Resolution::Outcome(1),
+        };
+        execute(&mut contract, bob(), challenge).expect("The loser can challenge");
+
+        execute(&mut contract, alice(), Operation::CloseExpired).expect("Anyone can close");
+        assert_eq!(bet_status(&contract, &bet_id), BetStatus::Disputed);
+
+        // Nobody settles the dispute, so the bet is voided and the bond returned
+        advance_time(&mut contract, TimeDelta::from_secs(60));
+        execute(&mut contract, alice(), Operation::CloseExpired).expect("Anyone can close");
+        assert_eq!(bet_status(&contract, &bet_id), BetStatus::Resolved);
+        assert_eq!(
+            contract.runtime.owner_balance(bob()),
+            INITIAL_BALANCE.saturating_sub(Amount::ONE)
+        );
+        for owner in [alice(), bob()] {
+            let claim = Operation::ClaimWinnings {
+                bet_id: bet_id.clone(),
+            };
+            execute(&mut contract, owner, claim).expect("Stakes are refunded");
+            assert_eq!(contract.runtime.owner_balance(owner), INITIAL_BALANCE);
+        }
+    }
+
+    #[test]
+    fn stake_and_exposure_limits() {
+        let mut contract = create_and_instantiate(InstantiationArgument {
+            min_stake: Some(Amount::ONE),
+            max_exposure: Some(Amount::from_tokens(5)),
+            ..InstantiationArgument::default()
+        });
+        let bet_id = create_bet(&mut contract);
+
+        let response = place_bet(&mut contract, alice(), &bet_id, 0, Amount::from_millis(500));
+        assert_eq!(
+            response.unwrap_err(),
+            MicroBetError::StakeTooSmall(Amount::ONE)
+        );
+
+        place_bet(&mut contract, alice(), &bet_id, 0, Amount::from_tokens(3))
+            .expect("Wager should be accepted");
+        let response = place_bet(&mut contract, alice(), &bet_id, 1, Amount::from_tokens(3));
+        assert_eq!(
+            response.unwrap_err(),
+            MicroBetError::ExposureExceeded(Amount::from_tokens(5))
+        );
+    }
+
+    #[test]
+    fn fees_are_only_taken_from_net_winnings() {
+        let mut contract = create_and_instantiate(InstantiationArgument {
+            dispute_window_seconds: Some(0),
+            ..InstantiationArgument::default()
+        });
+        let mut create = || {
+            let operation = Operation::CreateBet {
+                question: "Will it rain tomorrow?".to_string(),
+                duration_seconds: 60,
+                outcomes: None,
+                liquidity: None,
+                seed: None,
+                oracle: None,
+                creator_fee_bps: Some(1_000),
+                lp_fee_bps: None,
+                metadata: None,
+            };
+            match execute(&mut contract, creator(), operation) {
+                Ok(MicroBetResponse::BetCreated(bet_id)) => bet_id,
+                response => panic!("Unexpected response: {response:?}"),
+            }
+        };
+        let contested = create();
+        let unbacked = create();
+        place_bet(
+            &mut contract,
+            alice(),
+            &contested,
+            0,
+            Amount::from_tokens(3),
+        )
+        .expect("Wager is valid");
+        place_bet(&mut contract, bob(), &contested, 1, Amount::ONE).expect("Wager is valid");
+        place_bet(&mut contract, alice(), &unbacked, 1, Amount::ONE).expect("Wager is valid");
+        resolve(&mut contract, &contested, 0);
+        resolve(&mut contract, &unbacked, 0);
+
+        // The 10% creator fee is taken out of the one token won, not out of the stake
+        let claim = Operation::ClaimWinnings { bet_id: contested };
+        let response = execute(&mut contract, alice(), claim);
+        assert!(matches!(
+            response,
+            Ok(MicroBetResponse::Payout(payout)) if payout == Amount::from_millis(3_900)
+        ));
+
+        // Nobody backed the winning outcome, so the stake is refunded in full
+        let claim = Operation::ClaimWinnings { bet_id: unbacked };
+        let response = execute(&mut contract, alice(), claim);
+        assert!(matches!(
+            response,
+            Ok(MicroBetResponse::Payout(payout)) if payout == Amount::ONE
+        ));
+
+        let creator_fees = contract
+            .state
+            .fees
+            .get(&creator())
+            .blocking_wait()
+            .expect("Failed to read fees");
+        assert_eq!(creator_fees, Some(Amount::from_millis(100)));
+    }
+
+    #[test]
+    fn liquidity_seeding_and_withdrawal() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        let operation = Operation::CreateBet {
+            question: "Will it rain tomorrow?".to_string(),
+            duration_seconds: 60,
+            outcomes: None,
+            liquidity: None,
+            seed: Some(Amount::from_tokens(2)),
+            oracle: None,
+            creator_fee_bps: None,
+            lp_fee_bps: Some(100),
+            metadata: None,
+        };
+        let bet_id = match execute(&mut contract, creator(), operation) {
+            Ok(MicroBetResponse::BetCreated(bet_id)) => bet_id,
+            response => panic!("Unexpected response: {response:?}"),
+        };
+
+        let amount = Amount::from_tokens(2);
+        let add = Operation::AddLiquidity {
+            bet_id: bet_id.clone(),
+            amount,
+        };
+        let response = execute(&mut contract, bob(), add);
+        assert!(matches!(
+            response,
+            Ok(MicroBetResponse::Liquidity(position)) if position.shares == Amount::from_tokens(2)
+        ));
+        place_bet(&mut contract, alice(), &bet_id, 0, Amount::from_tokens(3))
+            .expect("Wager should be accepted");
+
+        let bet = contract
+            .state
+            .bets
+            .get(&bet_id)
+            .blocking_wait()
+            .expect("Failed to read bet")
+            .expect("Bet not found");
+        assert_eq!(
+            bet.pools,
+            vec![Amount::from_tokens(5), Amount::from_tokens(2)]
+        );
+        assert_eq!(bet.lp_shares, Amount::from_tokens(4));
+
+        let withdraw = Operation::WithdrawLiquidity {
+            bet_id: bet_id.clone(),
+        };
+        let response = execute(&mut contract, creator(), withdraw);
+        assert_eq!(response.unwrap_err(), MicroBetError::NotResolved);
+
+        let resolve = Operation::ResolveBet {
+            bet_id: bet_id.clone(),
+            resolution: Resolution::Outcome(0),
+        };
+        execute(&mut contract, creator(), resolve).expect("Bet should be resolved");
+        advance_time(&mut contract, TimeDelta::from_secs(24 * 60 * 60));
+
+        // Alice wins a share of the seeded NO pool, minus the 1% liquidity provider fee on
+        // her 1.2 tokens of winnings
+        let claim = Operation::ClaimWinnings {
+            bet_id: bet_id.clone(),
+        };
+        let response = execute(&mut contract, alice(), claim);
+        assert!(matches!(
+            response,
+            Ok(MicroBetResponse::Payout(payout)) if payout == Amount::from_millis(4_188)
+        ));
+
+        // The seeded YES pool is worth 2.8 tokens, split evenly with the fee
+        for provider in [creator(), bob()] {
+            let withdraw = Operation::WithdrawLiquidity {
+                bet_id: bet_id.clone(),
+            };
+            let response = execute(&mut contract, provider, withdraw);
+            assert!(matches!(
+                response,
+                Ok(MicroBetResponse::Payout(payout)) if payout == Amount::from_millis(1_406)
+            ));
+        }
+        let withdraw = Operation::WithdrawLiquidity { bet_id };
+        let response = execute(&mut contract, bob(), withdraw);
+        assert_eq!(response.unwrap_err(), MicroBetError::NoLiquidity);
+        assert_eq!(
+            contract.runtime.owner_balance(bob()),
+            INITIAL_BALANCE.saturating_sub(Amount::from_millis(594))
+        );
+    }
+
+    #[test]
+    fn remote_wager_is_forwarded_to_hub() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        contract.runtime.set_chain_id(user_chain());
+
+        let response = place_bet(&mut contract, alice(), "bet_1", 0, Amount::ONE);
+        assert!(matches!(response, Ok(MicroBetResponse::Forwarded)));
+        assert_eq!(
+            contract.runtime.owner_balance(alice()),
+            INITIAL_BALANCE.saturating_sub(Amount::ONE)
+        );
+        // The stake goes along to the hub's escrow account
+        let hub_escrow = Account {
+            chain_id: hub_chain(),
+            owner: contract.runtime.application_id().into(),
+        };
+        assert_eq!(
+            contract.runtime.outgoing_transfers().get(&hub_escrow),
+            Some(&Amount::ONE)
+        );
+
+        let requests = contract.runtime.created_send_message_requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].destination, hub_chain());
+        assert!(requests[0].authenticated && requests[0].is_tracked);
+        assert!(matches!(
+            &requests[0].message,
+            Message::PlaceBet { owner, bet_id, .. } if *owner == alice() && bet_id == "bet_1"
+        ));
+    }
+
+    #[test]
+    fn bounced_wager_is_ignored() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        contract.runtime.set_chain_id(user_chain());
+        place_bet(&mut contract, alice(), "bet_404", 0, Amount::ONE)
+            .expect("Wager should be forwarded");
+
+        // The stake bounces back by itself, so the user chain has nothing left to refund
+        contract.runtime.set_message_is_bouncing(Some(true));
+        contract
+            .execute_message(Message::PlaceBet {
+                owner: alice(),
+                bet_id: "bet_404".to_string(),
+                outcome_index: 0,
+                amount: Amount::ONE,
+            })
+            .blocking_wait();
+        assert_eq!(
+            contract.runtime.owner_balance(alice()),
+            INITIAL_BALANCE.saturating_sub(Amount::ONE)
+        );
+        let escrow = contract.runtime.application_id().into();
+        assert_eq!(contract.runtime.owner_balance(escrow), Amount::ZERO);
+        assert_eq!(contract.runtime.created_send_message_requests().len(), 1);
+    }
+
+    #[test]
+    fn bounced_claim_is_ignored() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        contract.runtime.set_chain_id(user_chain());
+        let claim = Operation::ClaimWinnings {
+            bet_id: "bet_404".to_string(),
+        };
+        let response = execute(&mut contract, alice(), claim);
+        assert!(matches!(response, Ok(MicroBetResponse::Forwarded)));
+
+        contract.runtime.set_message_is_bouncing(Some(true));
+        contract
+            .execute_message(Message::ClaimWinnings {
+                owner: alice(),
+                bet_id: "bet_404".to_string(),
+            })
+            .blocking_wait();
+        assert_eq!(contract.runtime.owner_balance(alice()), INITIAL_BALANCE);
+        assert_eq!(contract.runtime.created_send_message_requests().len(), 1);
+    }
+
+    #[test]
+    fn operations_other_than_trades_are_hub_only() {
+        let mut contract = create_and_instantiate(InstantiationArgument::default());
+        contract.runtime.set_chain_id(user_chain());
+
+        let close = Operation::CloseBet {
+            bet_id: "bet_1".to_string(),
+        };
+        let response = execute(&mut contract, creator(), close);
+        assert_eq!(response.unwrap_err(), MicroBetError::HubOnly);
+    }
+
+    fn creator() -> AccountOwner {
+        AccountOwner::from(CryptoHash::test_hash("creator"))
+    }
+
+    fn alice() -> AccountOwner {
+        AccountOwner::from(CryptoHash::test_hash("alice"))
+    }
+
+    fn bob() -> AccountOwner {
+        AccountOwner::from(CryptoHash::test_hash("bob"))
+    }
+
+    fn hub_chain() -> ChainId {
+        ChainId(CryptoHash::test_hash("hub"))
+    }
+
+    fn user_chain() -> ChainId {
+        ChainId(CryptoHash::test_hash("user"))
+    }
+
+    /// Creates a contract on the hub chain with some tokens for every test account
+    fn create_and_instantiate(argument: InstantiationArgument) -> MicroBetContract {
+        let application_id =
+            ApplicationId::new(CryptoHash::test_hash("micro-bet")).with_abi::<MicroBetAbi>();
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(Parameters::default())
+            .with_application_id(application_id)
+            .with_chain_id(hub_chain())
+            .with_application_creator_chain_id(hub_chain())
+            .with_system_time(Timestamp::from(0))
+            .with_authenticated_signer(creator())
+            .with_owner_balances([
+                (creator(), INITIAL_BALANCE),
+                (alice(), INITIAL_BALANCE),
+                (bob(), INITIAL_BALANCE),
+                (application_id.into(), Amount::ZERO),
+            ]);
+        let mut contract = MicroBetContract {
+            state: MicroBetState::load(runtime.root_view_storage_context())
+                .blocking_wait()
+                .expect("Failed to read from mock key value store"),
+            runtime,
+        };
+
+        contract.instantiate(argument).blocking_wait();
+
+        contract
+    }
+
+    /// Creates a YES/NO bet expiring after a minute, returning its ID
+    fn create_bet(contract: &mut MicroBetContract) -> String {
+        let operation = Operation::CreateBet {
+            question: "Will it rain tomorrow?".to_string(),
+            duration_seconds: 60,
+            outcomes: None,
+            liquidity: None,
+            seed: None,
+            oracle: None,
+            creator_fee_bps: None,
+            lp_fee_bps: None,
+            metadata: None,
+        };
+        match execute(contract, creator(), operation) {
+            Ok(MicroBetResponse::BetCreated(bet_id)) => bet_id,
+            response => panic!("Unexpected response: {response:?}"),
+        }
+    }
+
+    fn place_bet(
+        contract: &mut MicroBetContract,
+        owner: AccountOwner,
+        bet_id: &str,
+        outcome_index: u32,
+        amount: Amount,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        let operation = Operation::PlaceBet {
+            bet_id: bet_id.to_string(),
+            outcome_index,
+            amount,
+        };
+        execute(contract, owner, operation)
+    }
+
+    /// Places a parlay of `stake` one token, returning its ID
+    fn place_parlay<'a>(
+        contract: &mut MicroBetContract,
+        owner: AccountOwner,
+        legs: impl IntoIterator<Item = (&'a String, u32)>,
+    ) -> u64 {
+        let legs = legs
+            .into_iter()
+            .map(|(bet_id, outcome_index)| ParlayLegInput {
+                bet_id: bet_id.clone(),
+                outcome_index,
+            })
+            .collect();
+        let operation = Operation::PlaceParlay {
+            legs,
+            stake: Amount::ONE,
+        };
+        match execute(contract, owner, operation) {
+            Ok(MicroBetResponse::Parlay(parlay)) => parlay.id,
+            response => panic!("Unexpected response: {response:?}"),
+        }
+    }
+
+    fn resolve(contract: &mut MicroBetContract, bet_id: &str, outcome_index: u32) {
+        let operation = Operation::ResolveBet {
+            bet_id: bet_id.to_string(),
+            resolution: Resolution::Outcome(outcome_index),
+        };
+        execute(contract, creator(), operation).expect("The creator can resolve");
+    }
+
+    fn parlay_status(contract: &MicroBetContract, parlay_id: u64) -> ParlayStatus {
+        contract
+            .state
+            .parlays
+            .get(&parlay_id)
+            .blocking_wait()
+            .expect("Failed to read parlay")
+            .expect("Parlay not found")
+            .status
+    }
+
+    fn bet_status(contract: &MicroBetContract, bet_id: &str) -> BetStatus {
+        contract
+            .state
+            .bets
+            .get(bet_id)
+            .blocking_wait()
+            .expect("Failed to read bet")
+            .expect("Bet not found")
+            .status
+    }
+
+    fn execute(
+        contract: &mut MicroBetContract,
+        signer: AccountOwner,
+        operation: Operation,
+    ) -> Result<MicroBetResponse, MicroBetError> {
+        contract.runtime.set_authenticated_signer(signer);
+        contract.execute_operation(operation).blocking_wait()
+    }
+
+    fn advance_time(contract: &mut MicroBetContract, delta: TimeDelta) {
+        let now = contract.runtime.system_time();
+        contract.runtime.set_system_time(now.saturating_add(delta));
     }
 }
@@ -1,18 +1,68 @@
 // StormCast Micro-Bet Application ABI
 // A prediction market for environmental events on Linera
 
-use async_graphql::{Request, Response, SimpleObject, InputObject};
+use async_graphql::{scalar, ComplexObject, Context, InputObject, Request, Response, SimpleObject};
+use fungible::FungibleTokenAbi;
 use linera_sdk::{
     graphql::GraphQLMutationRoot,
-    linera_base_types::{AccountOwner, Amount, ContractAbi, ServiceAbi, Timestamp},
+    linera_base_types::{
+        AccountOwner, Amount, ApplicationId, ContractAbi, ServiceAbi, StreamName, TimeDelta,
+        Timestamp,
+    },
 };
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub mod market_maker;
+
+/// The outcome labels of a binary market, the YES outcome being at index 0
+pub const BINARY_OUTCOMES: [&str; 2] = ["Yes", "No"];
+
+/// The maximum number of outcomes a categorical market can have
+pub const MAX_OUTCOMES: usize = 32;
+
+/// The highest protocol fee, creator fee and liquidity provider fee, in basis points of net
+/// winnings
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// The maximum number of legs in a parlay
+pub const MAX_PARLAY_LEGS: usize = 8;
+
+/// The maximum number of tags on a bet
+pub const MAX_TAGS: usize = 8;
+
+/// The maximum number of due bets a single `CloseExpired` looks at
+pub const MAX_EXPIRED_PER_CLOSE: usize = 100;
+
+/// The maximum length of a category, region or tag, in bytes
+pub const MAX_LABEL_LENGTH: usize = 64;
+
+/// The maximum length of a source URL or of resolution criteria, in bytes
+pub const MAX_TEXT_LENGTH: usize = 2_048;
+
+/// The longest a bet can stay open for wagers
+pub const MAX_BET_DURATION: TimeDelta = TimeDelta::from_secs(366 * 24 * 60 * 60);
+
+/// How long after expiring a bet can still be resolved, unless configured otherwise
+pub const DEFAULT_RESOLUTION_WINDOW: TimeDelta = TimeDelta::from_secs(7 * 24 * 60 * 60);
+
+/// How long after being resolved a bet can be challenged, unless configured otherwise
+pub const DEFAULT_DISPUTE_WINDOW: TimeDelta = TimeDelta::from_secs(24 * 60 * 60);
+
+/// How long arbiters have to settle a dispute before the bet is voided, unless configured
+/// otherwise
+pub const DEFAULT_ARBITRATION_WINDOW: TimeDelta = TimeDelta::from_secs(7 * 24 * 60 * 60);
+
+/// The bond posted to challenge a resolution, unless configured otherwise
+pub const DEFAULT_CHALLENGE_BOND: Amount = Amount::ONE;
 
 pub struct MicroBetAbi;
 
 impl ContractAbi for MicroBetAbi {
     type Operation = Operation;
-    type Response = ();
+    type Response = Result<MicroBetResponse, MicroBetError>;
 }
 
 impl ServiceAbi for MicroBetAbi {
@@ -20,11 +70,124 @@ impl ServiceAbi for MicroBetAbi {
     type QueryResponse = Response;
 }
 
-/// The instantiation argument (empty for this contract)
+/// The application parameters
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct Parameters {
+    /// The fungible token application wagers are settled in, or `None` to settle wagers in the
+    /// chain's native token
+    #[serde(default)]
+    pub fungible_app_id: Option<ApplicationId<FungibleTokenAbi>>,
+    /// The fee taken out of net winnings for the protocol, in basis points
+    #[serde(default)]
+    pub protocol_fee_bps: u16,
+    /// Who the protocol fee accrues to, no protocol fee being taken if unset
+    #[serde(default)]
+    pub fee_recipient: Option<AccountOwner>,
+}
+
+/// The instantiation argument
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SimpleObject)]
 pub struct InstantiationArgument {
     /// Optional owner who can resolve bets
     pub admin: Option<AccountOwner>,
+    /// How many seconds after expiring a bet can still be resolved before it is voided,
+    /// `DEFAULT_RESOLUTION_WINDOW` if unset
+    #[serde(default)]
+    pub resolution_window_seconds: Option<u64>,
+    /// How many seconds after being resolved a bet can be challenged, `DEFAULT_DISPUTE_WINDOW`
+    /// if unset
+    #[serde(default)]
+    pub dispute_window_seconds: Option<u64>,
+    /// The bond posted to challenge a resolution, `DEFAULT_CHALLENGE_BOND` if unset
+    #[serde(default)]
+    pub challenge_bond: Option<Amount>,
+    /// How many seconds after a challenge the arbiters have to settle it before the bet is
+    /// voided, `DEFAULT_ARBITRATION_WINDOW` if unset
+    #[serde(default)]
+    pub arbitration_window_seconds: Option<u64>,
+    /// Committee settling challenged resolutions by majority vote, the admin settling them
+    /// alone if empty
+    #[serde(default)]
+    pub arbiters: Vec<AccountOwner>,
+    /// The smallest amount a single wager, purchase, order or parlay may stake
+    #[serde(default)]
+    pub min_stake: Option<Amount>,
+    /// The largest amount a single wager, purchase, order or parlay may stake
+    #[serde(default)]
+    pub max_stake: Option<Amount>,
+    /// The largest total stake one owner may hold in the positions of one market
+    #[serde(default)]
+    pub max_exposure: Option<Amount>,
+    /// How many unresolved bets one creator may have at a time
+    #[serde(default)]
+    pub max_open_markets: Option<u32>,
+    /// The deposit posted with every new bet, returned to the creator once the bet is
+    /// resolved for good and forfeited if the bet is abandoned or its resolution overturned
+    #[serde(default)]
+    pub creation_deposit: Option<Amount>,
+}
+
+impl InstantiationArgument {
+    /// How long after expiring a bet can still be resolved
+    pub fn resolution_window(&self) -> TimeDelta {
+        self.resolution_window_seconds
+            .map(TimeDelta::from_secs)
+            .unwrap_or(DEFAULT_RESOLUTION_WINDOW)
+    }
+
+    /// How long after being resolved a bet can be challenged
+    pub fn dispute_window(&self) -> TimeDelta {
+        self.dispute_window_seconds
+            .map(TimeDelta::from_secs)
+            .unwrap_or(DEFAULT_DISPUTE_WINDOW)
+    }
+
+    /// How long after a challenge the arbiters have to settle it
+    pub fn arbitration_window(&self) -> TimeDelta {
+        self.arbitration_window_seconds
+            .map(TimeDelta::from_secs)
+            .unwrap_or(DEFAULT_ARBITRATION_WINDOW)
+    }
+
+    /// The bond posted to challenge a resolution
+    pub fn challenge_bond_amount(&self) -> Amount {
+        self.challenge_bond.unwrap_or(DEFAULT_CHALLENGE_BOND)
+    }
+
+    /// The number of votes needed to settle a dispute, or `None` if disputes cannot be settled
+    pub fn dispute_quorum(&self) -> Option<usize> {
+        if !self.arbiters.is_empty() {
+            Some(self.arbiters.len() / 2 + 1)
+        } else if self.admin.is_some() {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    /// Check that a single stake is within the configured bounds
+    pub fn check_stake(&self, amount: Amount) -> Result<(), MicroBetError> {
+        if let Some(min_stake) = self.min_stake {
+            if amount < min_stake {
+                return Err(MicroBetError::StakeTooSmall(min_stake));
+            }
+        }
+        if let Some(max_stake) = self.max_stake {
+            if amount > max_stake {
+                return Err(MicroBetError::StakeTooLarge(max_stake));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `owner` may vote on disputes
+    pub fn is_arbiter(&self, owner: AccountOwner) -> bool {
+        if self.arbiters.is_empty() {
+            self.admin == Some(owner)
+        } else {
+            self.arbiters.contains(&owner)
+        }
+    }
 }
 
 impl std::fmt::Display for InstantiationArgument {
@@ -39,29 +202,385 @@ impl std::fmt::Display for InstantiationArgument {
 
 /// A prediction market bet
 #[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+#[graphql(complex)]
 pub struct Bet {
     /// Unique identifier for the bet
     pub id: String,
     /// The question being bet on
     pub question: String,
-    /// Total amount bet on YES
-    pub yes_pool: Amount,
-    /// Total amount bet on NO
-    pub no_pool: Amount,
+    /// Whether this is a YES/NO or a multi-outcome market
+    pub kind: MarketKind,
+    /// The labels of the possible outcomes
+    pub outcomes: Vec<String>,
+    /// Total amount bet on each outcome, indexed like `outcomes`
+    pub pools: Vec<Amount>,
+    /// How wagers on this bet are priced
+    pub pricing: PricingMode,
+    /// The market maker's share reserves for each outcome, empty for parimutuel bets
+    pub reserves: Vec<Amount>,
+    /// The collateral liquidity providers funded the bet with
+    pub liquidity: Amount,
+    /// The part of each pool of a parimutuel bet seeded by liquidity providers, indexed like
+    /// `outcomes`
+    pub seeded_pools: Vec<Amount>,
+    /// The liquidity provider shares issued, which divide the liquidity between providers
+    pub lp_shares: Amount,
+    /// The fee taken out of net winnings for the liquidity providers, in basis points
+    pub lp_fee_bps: u16,
+    /// The fees accrued to the liquidity providers so far
+    pub lp_fees: Amount,
     /// Current status of the bet
     pub status: BetStatus,
     /// The creator of the bet
     pub creator: AccountOwner,
-    /// The resolution (true = YES won, false = NO won)
-    pub resolution: Option<bool>,
+    /// The protocol fee taken out of net winnings, in basis points
+    pub protocol_fee_bps: u16,
+    /// The fee taken out of net winnings for the creator, in basis points
+    pub creator_fee_bps: u16,
+    /// The resolution, once the bet is resolved
+    pub resolution: Option<Resolution>,
+    /// Where the outcome is fetched from by `ResolveFromOracle`, if anywhere
+    pub oracle: Option<OracleSource>,
     /// When the bet was created
     pub created_at: Timestamp,
     /// When the bet expires
     pub expires_at: Timestamp,
+    /// When the bet must be resolved by, after which it is voided and every wager refunded
+    pub resolve_by: Timestamp,
+    /// Until when the resolution can be challenged, once the bet is resolved
+    pub disputable_until: Option<Timestamp>,
+    /// The challenge raised against the resolution, if any
+    pub challenge: Option<Challenge>,
+    /// What the bet is about, for browsing
+    pub metadata: MarketMetadata,
+    /// The creation deposit held until the bet is resolved for good
+    pub deposit: Amount,
+}
+
+#[ComplexObject]
+impl Bet {
+    /// The status of the bet at the time of the query, open bets past their expiry being
+    /// reported as closed
+    async fn current_status(&self, ctx: &Context<'_>) -> BetStatus {
+        match ctx.data::<Timestamp>() {
+            Ok(now) => self.status_at(*now),
+            Err(_) => self.status,
+        }
+    }
+
+    /// Whether the resolution can no longer be challenged, so that payouts can be claimed
+    async fn is_final(&self, ctx: &Context<'_>) -> bool {
+        match ctx.data::<Timestamp>() {
+            Ok(now) => self.is_final_at(*now),
+            Err(_) => false,
+        }
+    }
+}
+
+impl Bet {
+    /// The status of the bet at the given time, open bets past their expiry being closed
+    pub fn status_at(&self, now: Timestamp) -> BetStatus {
+        if self.status == BetStatus::Open && now >= self.expires_at {
+            BetStatus::Closed
+        } else {
+            self.status
+        }
+    }
+
+    /// Whether the bet is resolved and its resolution can no longer be challenged at the given
+    /// time
+    pub fn is_final_at(&self, now: Timestamp) -> bool {
+        self.status == BetStatus::Resolved
+            && self
+                .disputable_until
+                .is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Whether the bet is closed or open and can still be resolved
+    pub fn is_unresolved(&self) -> bool {
+        self.status == BetStatus::Open || self.status == BetStatus::Closed
+    }
+
+    /// Total amount wagered on all outcomes
+    pub fn total_pool(&self) -> Amount {
+        self.pools.iter().sum()
+    }
+
+    /// The amount owed to a position once the bet is resolved
+    ///
+    /// In parimutuel bets, winners get their stake back plus a share of the losing pools
+    /// proportional to their stake, and if the bet is invalid or nobody backed the winning
+    /// outcome, every position is refunded. In market maker bets, each share of the winning
+    /// outcome is worth one token, and if the bet is invalid every share is worth the same.
+    /// If the bet was cancelled, every position gets back exactly what it staked.
+    pub fn payout(&self, user_bet: &UserBet) -> Amount {
+        if self.status == BetStatus::Cancelled {
+            return user_bet.amount;
+        }
+        if self.pricing == PricingMode::MarketMaker {
+            return self.share_value(user_bet.outcome_index, user_bet.shares);
+        }
+        self.wager_value(user_bet.outcome_index, user_bet.amount)
+    }
+
+    /// The value of `amount` wagered on an outcome of a resolved parimutuel bet
+    fn wager_value(&self, outcome_index: u32, amount: Amount) -> Amount {
+        let winning_index = match self.resolution {
+            None => return Amount::ZERO,
+            Some(Resolution::Invalid) => return amount,
+            Some(Resolution::Outcome(index)) => index,
+        };
+        let winning_pool = self.pools[winning_index as usize];
+        if winning_pool == Amount::ZERO {
+            return amount;
+        }
+        if outcome_index != winning_index {
+            return Amount::ZERO;
+        }
+        let losing_pool = self.total_pool().saturating_sub(winning_pool);
+        let winnings = mul_div(losing_pool, amount, winning_pool);
+        amount.saturating_add(winnings)
+    }
+
+    /// The protocol fee, the creator fee and the liquidity providers' fee taken out of the
+    /// payout of a position
+    ///
+    /// Fees are only taken out of net winnings, i.e. what the position is paid beyond its
+    /// stake, when the bet was resolved with a winning outcome that somebody backed, never out
+    /// of refunds. The liquidity providers' fee is only taken if the bet has liquidity
+    /// providers.
+    pub fn fees(&self, user_bet: &UserBet) -> (Amount, Amount, Amount) {
+        let no_fees = (Amount::ZERO, Amount::ZERO, Amount::ZERO);
+        let Some(Resolution::Outcome(winning_index)) = self.resolution else {
+            return no_fees;
+        };
+        if self.status != BetStatus::Resolved {
+            return no_fees;
+        }
+        if self.pricing == PricingMode::Parimutuel
+            && self.pools[winning_index as usize] == Amount::ZERO
+        {
+            // Nobody backed the winning outcome, so every stake is refunded
+            return no_fees;
+        }
+        let winnings = self.payout(user_bet).saturating_sub(user_bet.amount);
+        let fee = |bps: u16| {
+            mul_div(
+                winnings,
+                Amount::from_attos(bps.into()),
+                Amount::from_attos(10_000),
+            )
+        };
+        let lp_fee = if self.lp_shares == Amount::ZERO {
+            Amount::ZERO
+        } else {
+            fee(self.lp_fee_bps)
+        };
+        (
+            fee(self.protocol_fee_bps),
+            fee(self.creator_fee_bps),
+            lp_fee,
+        )
+    }
+
+    /// The decimal odds of an outcome, i.e. what one token backing it would pay back, or `None`
+    /// if the outcome cannot be priced yet
+    ///
+    /// In parimutuel bets this is the total pool over the outcome's pool; in market maker
+    /// bets it is the inverse of the outcome's price.
+    pub fn odds(&self, outcome_index: u32) -> Option<Amount> {
+        let index = outcome_index as usize;
+        let (numerator, denominator) = match self.pricing {
+            PricingMode::Parimutuel => (self.total_pool(), *self.pools.get(index)?),
+            PricingMode::MarketMaker => {
+                if index >= self.reserves.len() {
+                    return None;
+                }
+                (Amount::ONE, market_maker::price(&self.reserves, index))
+            }
+        };
+        if denominator == Amount::ZERO {
+            return None;
+        }
+        Some(mul_div(Amount::ONE, numerator, denominator))
+    }
+
+    /// The amount owed to the liquidity providers once the bet is resolved: the value of the
+    /// shares left in the market maker's reserves, or of the seeded part of the pools
+    pub fn liquidity_payout(&self) -> Amount {
+        let values = match self.pricing {
+            PricingMode::MarketMaker => self
+                .reserves
+                .iter()
+                .enumerate()
+                .map(|(index, shares)| self.share_value(index as u32, *shares))
+                .collect::<Vec<_>>(),
+            PricingMode::Parimutuel => self
+                .seeded_pools
+                .iter()
+                .enumerate()
+                .map(|(index, seed)| self.wager_value(index as u32, *seed))
+                .collect(),
+        };
+        values.iter().sum()
+    }
+
+    /// Adds `amount` of collateral from a liquidity provider, returning the liquidity provider
+    /// shares it is worth and the outcome shares left over for the provider
+    ///
+    /// Parimutuel bets split the collateral evenly between their pools. Market maker bets add
+    /// it to their reserves without moving prices, which leaves over some shares of every
+    /// outcome but the most likely one.
+    pub fn add_liquidity(&mut self, amount: Amount) -> (Amount, Vec<Amount>) {
+        let (shares, returned) = match self.pricing {
+            PricingMode::Parimutuel => {
+                let count = self.pools.len() as u128;
+                let seed = amount.saturating_div(count);
+                // Rounding leftovers go to the first pool
+                let leftover = amount.saturating_sub(seed.saturating_mul(count));
+                for (index, (pool, seeded)) in self
+                    .pools
+                    .iter_mut()
+                    .zip(&mut self.seeded_pools)
+                    .enumerate()
+                {
+                    let seed = if index == 0 {
+                        seed.saturating_add(leftover)
+                    } else {
+                        seed
+                    };
+                    pool.saturating_add_assign(seed);
+                    seeded.saturating_add_assign(seed);
+                }
+                // Seeds are spread the same way whenever they are added, so every token of
+                // them is worth the same
+                (amount, Vec::new())
+            }
+            PricingMode::MarketMaker => {
+                let largest = self.reserves.iter().copied().max().unwrap_or(Amount::ZERO);
+                let shares = if self.lp_shares == Amount::ZERO || largest == Amount::ZERO {
+                    amount
+                } else {
+                    mul_div(amount, self.lp_shares, largest)
+                };
+                let (reserves, returned) = market_maker::add_liquidity(&self.reserves, amount);
+                self.reserves = reserves;
+                (shares, returned)
+            }
+        };
+        self.liquidity.saturating_add_assign(amount);
+        self.lp_shares.saturating_add_assign(shares);
+        (shares, returned)
+    }
+
+    /// The value of `shares` of an outcome of a resolved market maker bet
+    fn share_value(&self, outcome_index: u32, shares: Amount) -> Amount {
+        match self.resolution {
+            None => Amount::ZERO,
+            Some(Resolution::Invalid) => shares.saturating_div(self.outcomes.len() as u128),
+            Some(Resolution::Outcome(index)) if index == outcome_index => shares,
+            Some(Resolution::Outcome(_)) => Amount::ZERO,
+        }
+    }
+
+    /// Quotes a trade of `amount` tokens against the market maker, or `None` if this is not a
+    /// market maker bet or the trade cannot be filled
+    pub fn quote(&self, outcome_index: u32, side: TradeSide, amount: Amount) -> Option<Quote> {
+        let index = outcome_index as usize;
+        if self.pricing != PricingMode::MarketMaker || index >= self.reserves.len() {
+            return None;
+        }
+        if amount == Amount::ZERO {
+            return None;
+        }
+        let (shares, new_reserves) = match side {
+            TradeSide::Buy => market_maker::buy(&self.reserves, index, amount),
+            TradeSide::Sell => market_maker::sell(&self.reserves, index, amount)?,
+        };
+        if shares == Amount::ZERO {
+            return None;
+        }
+        let price = market_maker::price(&self.reserves, index);
+        let average_price = mul_div(amount, Amount::ONE, shares);
+        let slippage = match side {
+            TradeSide::Buy => average_price.saturating_sub(price),
+            TradeSide::Sell => price.saturating_sub(average_price),
+        };
+        let slippage_bps = if price == Amount::ZERO {
+            0
+        } else {
+            mul_div(slippage, Amount::from_attos(10_000), price).to_attos() as u64
+        };
+        Some(Quote {
+            outcome_index,
+            side,
+            amount,
+            shares,
+            price,
+            average_price,
+            price_after: market_maker::price(&new_reserves, index),
+            slippage_bps,
+        })
+    }
+}
+
+/// How wagers on a bet are priced
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, async_graphql::Enum,
+)]
+pub enum PricingMode {
+    /// Wagers go into per-outcome pools, and the losing pools are shared among winners
+    #[default]
+    Parimutuel,
+    /// Outcome shares are bought from and sold to a constant-product market maker
+    MarketMaker,
+}
+
+/// The direction of a trade against a market maker or on an order book
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum TradeSide {
+    /// Spend tokens on outcome shares
+    Buy,
+    /// Sell outcome shares for tokens
+    Sell,
+}
+
+/// The price of a trade against a market maker
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Quote {
+    /// The outcome being traded
+    pub outcome_index: u32,
+    /// Whether shares are bought or sold
+    pub side: TradeSide,
+    /// Tokens spent when buying, or received when selling
+    pub amount: Amount,
+    /// Shares received when buying, or given up when selling
+    pub shares: Amount,
+    /// The marginal price of a share before the trade
+    pub price: Amount,
+    /// The average price paid or received per share
+    pub average_price: Amount,
+    /// The marginal price of a share after the trade
+    pub price_after: Amount,
+    /// How much worse the average price is than the marginal price, in basis points
+    pub slippage_bps: u64,
+}
+
+/// Computes `amount * numerator / denominator`, rounding down, without intermediate overflow
+pub fn mul_div(amount: Amount, numerator: Amount, denominator: Amount) -> Amount {
+    let product = BigUint::from(u128::from(amount)) * BigUint::from(u128::from(numerator));
+    let quotient = product / BigUint::from(u128::from(denominator));
+    Amount::from_attos(
+        quotient
+            .to_u128()
+            .expect("Result does not fit into an amount"),
+    )
 }
 
 /// Status of a bet
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, async_graphql::Enum)]
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, async_graphql::Enum,
+)]
 pub enum BetStatus {
     /// Bet is open for new wagers
     #[default]
@@ -70,6 +589,163 @@ pub enum BetStatus {
     Closed,
     /// Bet has been resolved
     Resolved,
+    /// The resolution has been challenged and awaits the arbiters' decision
+    Disputed,
+    /// Bet was cancelled, every stake is refunded
+    Cancelled,
+}
+
+/// The kind of question a bet is about
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, async_graphql::Enum,
+)]
+pub enum MarketKind {
+    /// A YES/NO question, with outcomes `BINARY_OUTCOMES`
+    #[default]
+    Binary,
+    /// A question with several mutually exclusive outcomes
+    Categorical,
+}
+
+/// How a bet was resolved
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Resolution {
+    /// The outcome with the given index won
+    Outcome(u32),
+    /// The question could not be answered, every wager is refunded
+    Invalid,
+}
+
+scalar!(Resolution);
+
+/// A challenge against the resolution of a bet
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Challenge {
+    /// Who posted the bond
+    pub challenger: AccountOwner,
+    /// The bond, returned if the resolution is overturned and paid to the creator otherwise
+    pub bond: Amount,
+    /// The resolution the challenger claims is correct
+    pub proposed: Resolution,
+    /// When the challenge was raised
+    pub raised_at: Timestamp,
+    /// The votes cast by arbiters so far
+    pub votes: Vec<ArbiterVote>,
+}
+
+/// The resolution an arbiter voted for in a dispute
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct ArbiterVote {
+    pub arbiter: AccountOwner,
+    pub resolution: Resolution,
+}
+
+/// Where the outcome of a bet is fetched from when it is resolved by an oracle
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct OracleSource {
+    /// The URL queried with an HTTP GET request, which must return JSON
+    pub url: String,
+    /// The dot-separated path of the deciding number in the JSON response, with array
+    /// indices as path segments, e.g. `daily.precipitation_sum.0`
+    pub json_path: String,
+    /// How the deciding number selects the winning outcome
+    pub rule: OracleRule,
+}
+
+scalar!(OracleSource);
+
+/// How the number fetched by an oracle selects the winning outcome
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum OracleRule {
+    /// For binary bets: YES wins if the number compares to `threshold` as given, NO otherwise
+    Threshold {
+        comparison: Comparison,
+        threshold: String,
+    },
+    /// For categorical bets: one ascending decimal bound between each pair of consecutive
+    /// outcomes, outcome `i` covering numbers from bound `i - 1` (inclusive) to bound `i`
+    /// (exclusive)
+    Ranges { bounds: Vec<String> },
+}
+
+/// A comparison between the number fetched by an oracle and a threshold
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Comparison {
+    /// The number is strictly above the threshold
+    GreaterThan,
+    /// The number is at or above the threshold
+    GreaterOrEqual,
+    /// The number is strictly below the threshold
+    LessThan,
+    /// The number is at or below the threshold
+    LessOrEqual,
+}
+
+impl Comparison {
+    /// Whether `value` compares to `threshold` this way
+    pub fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::GreaterThan => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+            Comparison::LessThan => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+        }
+    }
+}
+
+impl OracleSource {
+    /// Whether this source can resolve a bet of the given kind and number of outcomes
+    pub fn is_valid_for(&self, kind: MarketKind, outcome_count: usize) -> bool {
+        let url_is_valid = self.url.starts_with("https://") || self.url.starts_with("http://");
+        let rule_is_valid = match (&self.rule, kind) {
+            (OracleRule::Threshold { threshold, .. }, MarketKind::Binary) => {
+                parse_decimal(threshold).is_some()
+            }
+            (OracleRule::Ranges { bounds }, MarketKind::Categorical) => {
+                let bounds = bounds
+                    .iter()
+                    .map(|bound| parse_decimal(bound))
+                    .collect::<Option<Vec<_>>>();
+                bounds.is_some_and(|bounds| {
+                    bounds.len() + 1 == outcome_count
+                        && bounds.windows(2).all(|pair| pair[0] < pair[1])
+                })
+            }
+            _ => false,
+        };
+        url_is_valid && !self.json_path.is_empty() && rule_is_valid
+    }
+
+    /// The index of the outcome selected by the fetched `value`
+    pub fn outcome_index(&self, value: f64) -> u32 {
+        match &self.rule {
+            OracleRule::Threshold {
+                comparison,
+                threshold,
+            } => {
+                let threshold = parse_decimal(threshold).expect("Invalid oracle threshold");
+                if comparison.holds(value, threshold) {
+                    0
+                } else {
+                    1
+                }
+            }
+            OracleRule::Ranges { bounds } => bounds
+                .iter()
+                .filter_map(|bound| parse_decimal(bound))
+                .filter(|bound| value >= *bound)
+                .count() as u32,
+        }
+    }
+}
+
+/// Parses a finite decimal number
+pub fn parse_decimal(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
 }
 
 /// A user's position in a bet
@@ -79,12 +755,317 @@ pub struct UserBet {
     pub bet_id: String,
     /// The user who placed the bet
     pub owner: AccountOwner,
-    /// true = YES, false = NO
-    pub side: bool,
+    /// The index of the outcome backed by this position
+    pub outcome_index: u32,
     /// Amount wagered
     pub amount: Amount,
+    /// Outcome shares held, equal to the amount wagered in parimutuel bets
+    pub shares: Amount,
     /// When the bet was placed
     pub timestamp: Timestamp,
+    /// Whether the payout for this position has been claimed
+    pub claimed: bool,
+}
+
+/// A limit order on the order book of a market outcome
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct BookOrder {
+    /// Unique identifier for the order
+    pub id: u64,
+    /// The bet whose shares are traded
+    pub bet_id: String,
+    /// The outcome whose shares are traded
+    pub outcome_index: u32,
+    /// Who placed the order
+    pub owner: AccountOwner,
+    /// Whether the order buys or sells shares
+    pub side: TradeSide,
+    /// The limit price, in tokens per share
+    pub price: Amount,
+    /// The shares left to trade
+    pub shares: Amount,
+    /// What the order holds until it is filled: the tokens paying for the remaining shares of
+    /// a buy order, or the stake of the remaining shares of a sell order
+    pub locked: Amount,
+    /// When the order was placed
+    pub created_at: Timestamp,
+}
+
+/// Descriptive information about a bet, used to browse and search markets
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SimpleObject, InputObject)]
+#[graphql(input_name = "MarketMetadataInput")]
+pub struct MarketMetadata {
+    /// The category of the market, e.g. "weather" or "sports"
+    pub category: Option<String>,
+    /// The region the question is about, e.g. a country or city name
+    pub region: Option<String>,
+    /// The coordinates the question is about, if it concerns a specific place
+    pub location: Option<GeoPoint>,
+    /// Where the outcome will be looked up
+    pub source_url: Option<String>,
+    /// How the outcome will be decided, in plain words
+    pub criteria: Option<String>,
+    /// Free-form labels for searching
+    #[graphql(default)]
+    pub tags: Vec<String>,
+}
+
+impl MarketMetadata {
+    /// The metadata with labels trimmed and duplicate tags removed, or why it is invalid
+    pub fn normalized(mut self) -> Result<Self, String> {
+        let check_label = |label: &mut String, name: &str| {
+            *label = label.trim().to_string();
+            if label.is_empty() || label.len() > MAX_LABEL_LENGTH {
+                return Err(format!(
+                    "The {name} must have between 1 and {MAX_LABEL_LENGTH} bytes"
+                ));
+            }
+            Ok(())
+        };
+        if let Some(category) = &mut self.category {
+            check_label(category, "category")?;
+        }
+        if let Some(region) = &mut self.region {
+            check_label(region, "region")?;
+        }
+        for tag in &mut self.tags {
+            check_label(tag, "tag")?;
+        }
+        let mut keys = Vec::new();
+        self.tags.retain(|tag| {
+            let key = label_key(tag);
+            let is_new = !keys.contains(&key);
+            keys.push(key);
+            is_new
+        });
+        if self.tags.len() > MAX_TAGS {
+            return Err(format!("A bet can have at most {MAX_TAGS} tags"));
+        }
+
+        if self.location.is_some_and(|location| !location.is_valid()) {
+            return Err("Invalid coordinates".to_string());
+        }
+        if let Some(source_url) = &self.source_url {
+            if !(source_url.starts_with("https://") || source_url.starts_with("http://"))
+                || source_url.len() > MAX_TEXT_LENGTH
+            {
+                return Err("The source must be an HTTP URL".to_string());
+            }
+        }
+        if self
+            .criteria
+            .as_ref()
+            .is_some_and(|criteria| criteria.len() > MAX_TEXT_LENGTH)
+        {
+            return Err("The resolution criteria are too long".to_string());
+        }
+        Ok(self)
+    }
+}
+
+/// The index key of a category, region or tag, so that lookups ignore case and padding
+pub fn label_key(label: &str) -> String {
+    label.trim().to_lowercase()
+}
+
+/// A position on Earth, in millionths of a degree so that it can be serialized with BCS
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, SimpleObject, InputObject)]
+#[graphql(input_name = "GeoPointInput")]
+pub struct GeoPoint {
+    pub latitude_micros: i32,
+    pub longitude_micros: i32,
+}
+
+impl GeoPoint {
+    /// Whether the coordinates are within range
+    pub fn is_valid(&self) -> bool {
+        (-90_000_000..=90_000_000).contains(&self.latitude_micros)
+            && (-180_000_000..=180_000_000).contains(&self.longitude_micros)
+    }
+}
+
+/// How the results of a market search are ordered
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, async_graphql::Enum,
+)]
+pub enum MarketSort {
+    /// Oldest first
+    #[default]
+    Created,
+    /// Highest traded volume first
+    Volume,
+    /// Soonest expiry first
+    Expiry,
+}
+
+impl MarketSort {
+    /// The key of a bet in the index of this order
+    pub fn index_key(self, bet: &Bet, volume: Amount) -> Vec<u8> {
+        match self {
+            MarketSort::Created => time_key(bet.created_at, &bet.id),
+            MarketSort::Expiry => time_key(bet.expires_at, &bet.id),
+            MarketSort::Volume => volume_key(volume, &bet.id),
+        }
+    }
+
+    /// The ID of the bet in a key of the index of this order
+    pub fn bet_of_key(self, key: &[u8]) -> String {
+        let sort_key_len = match self {
+            MarketSort::Created | MarketSort::Expiry => size_of::<u64>(),
+            MarketSort::Volume => size_of::<u128>(),
+        };
+        String::from_utf8(key[sort_key_len..].to_vec()).expect("Invalid market index key")
+    }
+}
+
+/// The key of a bet in an index sorted by time: the time in big-endian order, so that keys
+/// sort by it, followed by the bet ID to break ties
+pub fn time_key(time: Timestamp, bet_id: &str) -> Vec<u8> {
+    [&time.micros().to_be_bytes()[..], bet_id.as_bytes()].concat()
+}
+
+/// The key of a bet in the index sorted by volume: the complement of the volume in big-endian
+/// order, so that the highest volumes sort first, followed by the bet ID to break ties
+pub fn volume_key(volume: Amount, bet_id: &str) -> Vec<u8> {
+    let complement = u128::MAX - u128::from(volume);
+    [&complement.to_be_bytes()[..], bet_id.as_bytes()].concat()
+}
+
+/// The key of a bet in the index by status: the recorded status, followed by the bet ID
+pub fn status_key(status: BetStatus, bet_id: &str) -> Vec<u8> {
+    [&[status as u8][..], bet_id.as_bytes()].concat()
+}
+
+/// The ID of the bet in a key of the index by status
+pub fn bet_of_status_key(key: &[u8]) -> String {
+    String::from_utf8(key[1..].to_vec()).expect("Invalid status index key")
+}
+
+/// A combined position on several bets, paying only if every leg wins
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Parlay {
+    /// Unique identifier for the parlay
+    pub id: u64,
+    /// Who placed the parlay
+    pub owner: AccountOwner,
+    /// The predictions combined in the parlay
+    pub legs: Vec<ParlayLeg>,
+    /// Amount wagered
+    pub stake: Amount,
+    /// What the parlay pays if every leg wins, the stake times the odds of all legs
+    pub payout: Amount,
+    /// Whether the parlay is still open, and how it was settled otherwise
+    pub status: ParlayStatus,
+    /// What was paid out when the parlay was settled
+    pub paid: Amount,
+    /// When the parlay was placed
+    pub created_at: Timestamp,
+}
+
+/// One prediction in a parlay
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct ParlayLeg {
+    pub bet_id: String,
+    pub outcome_index: u32,
+    /// The decimal odds of the outcome when the parlay was placed
+    pub odds: Amount,
+}
+
+/// Input for one prediction in a parlay
+#[derive(Clone, Debug, Deserialize, Serialize, InputObject)]
+pub struct ParlayLegInput {
+    pub bet_id: String,
+    pub outcome_index: u32,
+}
+
+/// How a parlay was settled
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, async_graphql::Enum,
+)]
+pub enum ParlayStatus {
+    /// Some legs are not resolved for good yet
+    #[default]
+    Open,
+    /// Every leg won, or was voided
+    Won,
+    /// A leg lost
+    Lost,
+    /// Every leg was voided, the stake is refunded
+    Void,
+}
+
+/// Aggregates over the positions in a market
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SimpleObject)]
+pub struct MarketStats {
+    /// How many owners hold positions
+    pub bettors: u64,
+    /// How many positions are held
+    pub positions: u64,
+    /// Total amount wagered, bought and sold
+    pub volume: Amount,
+    /// Total stake of the positions held, including shares offered on the order book, net of
+    /// the cost of shares sold
+    pub stake: Amount,
+}
+
+/// The share of a liquidity provider in the liquidity of a bet
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct LiquidityPosition {
+    /// The bet the liquidity was provided to
+    pub bet_id: String,
+    /// Who provided the liquidity
+    pub provider: AccountOwner,
+    /// The provider's liquidity provider shares
+    pub shares: Amount,
+    /// The collateral the provider put in
+    pub contributed: Amount,
+    /// The liquidity provider fees already paid to the provider
+    pub fees_claimed: Amount,
+    /// Whether the provider has withdrawn their share of the liquidity
+    pub withdrawn: bool,
+}
+
+impl LiquidityPosition {
+    /// The provider's part of `amount`, in proportion to their shares of the bet's liquidity
+    pub fn portion(&self, bet: &Bet, amount: Amount) -> Amount {
+        if bet.lp_shares == Amount::ZERO {
+            return Amount::ZERO;
+        }
+        mul_div(amount, self.shares, bet.lp_shares)
+    }
+}
+
+/// A snapshot of the prices and activity of a market, as pushed to live subscribers
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct MarketOdds {
+    /// The bet the snapshot is of
+    pub bet_id: String,
+    /// The status of the bet at the time of the snapshot
+    pub status: BetStatus,
+    /// Total amount bet on each outcome
+    pub pools: Vec<Amount>,
+    /// The decimal odds of each outcome, or `None` while an outcome cannot be priced
+    pub odds: Vec<Option<Amount>>,
+    /// Total amount wagered, bought and sold
+    pub volume: Amount,
+    /// How many owners hold positions
+    pub bettors: u64,
+}
+
+impl MarketOdds {
+    /// Takes a snapshot of a bet and its statistics at the given time
+    pub fn new(bet: &Bet, stats: &MarketStats, now: Timestamp) -> Self {
+        MarketOdds {
+            bet_id: bet.id.clone(),
+            status: bet.status_at(now),
+            pools: bet.pools.clone(),
+            odds: (0..bet.outcomes.len() as u32)
+                .map(|index| bet.odds(index))
+                .collect(),
+            volume: stats.volume,
+            bettors: stats.bettors,
+        }
+    }
 }
 
 /// Input for creating a new bet
@@ -92,44 +1073,361 @@ pub struct UserBet {
 pub struct CreateBetInput {
     pub question: String,
     pub duration_seconds: u64,
+    pub outcomes: Option<Vec<String>>,
+    pub liquidity: Option<Amount>,
+    pub seed: Option<Amount>,
+    pub oracle: Option<OracleSource>,
+    pub creator_fee_bps: Option<u16>,
+    pub lp_fee_bps: Option<u16>,
+    pub metadata: Option<MarketMetadata>,
 }
 
 /// Input for placing a bet
 #[derive(Clone, Debug, Deserialize, Serialize, InputObject)]
 pub struct PlaceBetInput {
     pub bet_id: String,
-    pub side: bool,
+    pub outcome_index: u32,
     pub amount: Amount,
 }
 
 /// Operations that can be executed by the application
 #[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
 pub enum Operation {
-    /// Create a new prediction market
+    /// Create a new prediction market, a YES/NO one unless outcome labels are given
+    ///
+    /// If `liquidity` is set, the creator funds a market maker with it and shares are traded
+    /// with `BuyShares` and `SellShares` instead of `PlaceBet`. Otherwise, if `seed` is set,
+    /// the creator seeds the pools with it, split evenly. Either way the creator becomes the
+    /// first liquidity provider. If `oracle` is set, the bet is resolved from that source with
+    /// `ResolveFromOracle`. If `creator_fee_bps` or `lp_fee_bps` is set, that share of net
+    /// winnings accrues to the creator or to the liquidity providers. The `metadata` is indexed
+    /// so that the market can be found by category, region and tags. Wagers are taken for
+    /// `duration_seconds`, at most `MAX_BET_DURATION`.
     CreateBet {
         question: String,
         duration_seconds: u64,
+        outcomes: Option<Vec<String>>,
+        liquidity: Option<Amount>,
+        seed: Option<Amount>,
+        oracle: Option<OracleSource>,
+        creator_fee_bps: Option<u16>,
+        lp_fee_bps: Option<u16>,
+        metadata: Option<MarketMetadata>,
     },
-    /// Place a bet on an existing market
+    /// Place a bet on one of the outcomes of an existing market
     PlaceBet {
         bet_id: String,
-        side: bool,
+        outcome_index: u32,
         amount: Amount,
     },
-    /// Close a bet (stop accepting new wagers)
-    CloseBet {
+    /// Buy shares of an outcome from the market maker of a bet, spending `amount`
+    BuyShares {
         bet_id: String,
+        outcome_index: u32,
+        amount: Amount,
+        min_shares: Amount,
     },
-    /// Resolve a bet with the outcome
+    /// Sell shares of an outcome to the market maker of a bet, receiving `amount`
+    SellShares {
+        bet_id: String,
+        outcome_index: u32,
+        amount: Amount,
+        max_shares: Amount,
+    },
+    /// Close a bet (stop accepting new wagers)
+    CloseBet { bet_id: String },
+    /// Resolve a bet with the winning outcome, or as invalid
     ResolveBet {
         bet_id: String,
-        outcome: bool,
+        resolution: Resolution,
+    },
+    /// Close the expired bets, void the bets past their resolution deadline or left disputed
+    /// past their arbitration window, and settle the parlays on bets whose dispute window
+    /// ended, which anyone can request
+    ///
+    /// At most `MAX_EXPIRED_PER_CLOSE` bets are looked at, earliest deadline first, so more may
+    /// be left for the next request.
+    CloseExpired,
+    /// Resolve an expired bet from its oracle source, which anyone can request
+    ResolveFromOracle { bet_id: String },
+    /// Give shares of the caller's positions on an outcome to another owner
+    TransferPosition {
+        bet_id: String,
+        outcome_index: u32,
+        shares: Amount,
+        recipient: AccountOwner,
+    },
+    /// Buy or sell shares of an outcome at `price` tokens per share or better, matching the
+    /// order against the order book and keeping what is not filled on it
+    PlaceOrder {
+        bet_id: String,
+        outcome_index: u32,
+        side: TradeSide,
+        shares: Amount,
+        price: Amount,
+    },
+    /// Cancel one of the caller's orders, giving back the tokens or shares it holds
+    CancelOrder { order_id: u64 },
+    /// Combine predictions on several bets into one position, priced at the current odds of
+    /// every leg and paying only if all of them win
+    ///
+    /// Legs on voided or cancelled bets are left out of the payout. A parlay is settled and paid
+    /// as soon as every leg is resolved for good, or one of them loses for good.
+    PlaceParlay {
+        legs: Vec<ParlayLegInput>,
+        stake: Amount,
     },
+    /// Add funds to the reserve backing the potential winnings of parlays
+    FundParlayReserve { amount: Amount },
+    /// Cancel a bet so that every position can reclaim its stake, which its creator can do
+    /// before any wager and the admin at any time before payouts start
+    CancelBet { bet_id: String },
+    /// Challenge the resolution of a bet during its dispute window, posting the challenge bond
+    ///
+    /// If the arbiters do not settle the dispute within the arbitration window, `CloseExpired`
+    /// voids the bet and returns the bond.
+    ChallengeResolution {
+        bet_id: String,
+        proposed: Resolution,
+    },
+    /// Vote on the correct resolution of a disputed bet, as the admin or one of the arbiters
+    SettleDispute {
+        bet_id: String,
+        resolution: Resolution,
+    },
+    /// Claim the payout of the caller's positions on a resolved bet, once its dispute window
+    /// has passed, or their stake back on a cancelled bet
+    ClaimWinnings { bet_id: String },
+    /// Provide liquidity to an open bet, seeding its pools or its market maker with `amount`
+    /// in exchange for liquidity provider shares
+    ///
+    /// For market maker bets, the outcome shares left over to keep prices unchanged are added
+    /// to the caller's positions.
+    AddLiquidity { bet_id: String, amount: Amount },
+    /// Withdraw the caller's share of the liquidity of a bet once it is resolved for good or
+    /// cancelled, with the liquidity provider fees accrued since the last withdrawal
+    WithdrawLiquidity { bet_id: String },
+    /// Withdraw the fees accrued to the caller
+    WithdrawFees,
+    /// Mirror a market on this chain by following its event stream on the hub chain
+    Subscribe { bet_id: String },
+    /// Stop following the event stream of a market
+    Unsubscribe { bet_id: String },
+}
+
+/// The result of a successful operation
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum MicroBetResponse {
+    /// The operation succeeded and has nothing to report
+    Ok,
+    /// A bet was created with the given ID
+    BetCreated(String),
+    /// A wager was placed or shares were bought, opening this position
+    Position(UserBet),
+    /// Tokens were paid out to the caller
+    Payout(Amount),
+    /// The operation was forwarded to the hub chain, which will send back the outcome
+    Forwarded,
+    /// An order was placed and `filled` of its shares were traded right away
+    OrderPlaced { order_id: u64, filled: Amount },
+    /// A parlay was placed
+    Parlay(Parlay),
+    /// Liquidity was provided, leaving the provider with this position
+    Liquidity(LiquidityPosition),
+}
+
+/// Why an operation was rejected
+#[derive(Clone, Debug, Deserialize, Error, PartialEq, Eq, Serialize)]
+pub enum MicroBetError {
+    #[error("Operation must be authenticated")]
+    NotAuthenticated,
+    #[error("This operation can only be executed on the hub chain")]
+    HubOnly,
+    #[error("The hub chain already holds every market")]
+    AlreadyOnHub,
+    #[error("Bet {0} not found")]
+    BetNotFound(String),
+    #[error("Invalid bet: {0}")]
+    InvalidBet(String),
+    #[error("Invalid outcome index")]
+    InvalidOutcome,
+    #[error("Amount must be positive")]
+    ZeroAmount,
+    #[error("Bet is not open")]
+    BetNotOpen,
+    #[error("Bet has expired")]
+    BetExpired,
+    #[error("Bet has not expired yet")]
+    BetNotExpired,
+    #[error("Shares of market maker bets are traded with BuyShares and SellShares")]
+    MarketMakerBet,
+    #[error("Bet does not have a market maker")]
+    NoMarketMaker,
+    #[error("Trade amount is too small")]
+    TradeTooSmall,
+    #[error("Price moved beyond the slippage limit")]
+    SlippageExceeded,
+    #[error("Not enough liquidity for this trade")]
+    InsufficientLiquidity,
+    #[error("Not enough shares to sell")]
+    InsufficientShares,
+    #[error("Shares of resolved or cancelled bets are no longer traded")]
+    TradingClosed,
+    #[error("Order {0} not found")]
+    OrderNotFound(u64),
+    #[error("Only the owner of an order can cancel it")]
+    NotOrderOwner,
+    #[error("Only the creator or the admin can do this")]
+    NotCreatorOrAdmin,
+    #[error("Bets with wagers can only be cancelled by the admin")]
+    BetHasWagers,
+    #[error("Bet can no longer be cancelled")]
+    NotCancellable,
+    #[error("Bets with an oracle source are resolved with ResolveFromOracle")]
+    OracleBet,
+    #[error("Bet has no oracle source")]
+    NoOracle,
+    #[error("Bet has already been resolved")]
+    AlreadyResolved,
+    #[error("Bet has not been resolved")]
+    NotResolved,
+    #[error("Resolutions cannot be disputed without an admin or arbiters")]
+    DisputesDisabled,
+    #[error("Resolution has already been challenged")]
+    AlreadyChallenged,
+    #[error("Dispute window has passed")]
+    DisputeWindowPassed,
+    #[error("Proposed resolution is the current one")]
+    SameResolution,
+    #[error("Only the admin or an arbiter can settle disputes")]
+    NotArbiter,
+    #[error("Bet is not disputed")]
+    NotDisputed,
+    #[error("Resolution can still be disputed")]
+    StillDisputable,
+    #[error("No winnings to claim")]
+    NoWinnings,
+    #[error("No fees to withdraw")]
+    NoFees,
+    #[error("No liquidity to withdraw")]
+    NoLiquidity,
+    #[error("Invalid parlay: {0}")]
+    InvalidParlay(String),
+    #[error("Odds of bet {0} cannot be computed before anyone backs the outcome")]
+    UnpricedLeg(String),
+    #[error("The parlay reserve cannot cover this payout")]
+    InsufficientReserve,
+    #[error("The stake must be at least {0}")]
+    StakeTooSmall(Amount),
+    #[error("The stake must be at most {0}")]
+    StakeTooLarge(Amount),
+    #[error("The total stake in one market must be at most {0}")]
+    ExposureExceeded(Amount),
+    #[error("A creator can have at most {0} unresolved bets")]
+    TooManyOpenMarkets(u32),
 }
 
 /// Messages for cross-chain communication
+///
+/// Markets live on the hub chain, the chain that created the application. Wagers and trades
+/// submitted on a user chain are forwarded to the hub together with their stake, which the hub
+/// escrows. It answers with a position receipt and pays out through cross-chain transfers.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum Message {
     /// Sync a bet to another chain
     SyncBet { bet: Bet },
+    /// Place a wager whose stake was sent along to the hub (user chain to hub)
+    PlaceBet {
+        owner: AccountOwner,
+        bet_id: String,
+        outcome_index: u32,
+        amount: Amount,
+    },
+    /// Buy market maker shares with collateral sent along to the hub (user chain to hub)
+    BuyShares {
+        owner: AccountOwner,
+        bet_id: String,
+        outcome_index: u32,
+        amount: Amount,
+        min_shares: Amount,
+    },
+    /// Sell market maker shares, paying the proceeds to the sending chain (user chain to hub)
+    SellShares {
+        owner: AccountOwner,
+        bet_id: String,
+        outcome_index: u32,
+        amount: Amount,
+        max_shares: Amount,
+    },
+    /// Claim winnings, paying them to the sending chain (user chain to hub)
+    ClaimWinnings { owner: AccountOwner, bet_id: String },
+    /// Confirm a position recorded on the hub (hub to user chain)
+    PositionReceipt { user_bet: UserBet },
+    /// Replace the mirrored positions of an owner on a bet (hub to user chain)
+    SyncPositions {
+        owner: AccountOwner,
+        bet_id: String,
+        positions: Vec<UserBet>,
+    },
+}
+
+/// Events published by the hub chain on the stream of each market
+///
+/// Every event carries the bet as it is after the change, so that subscribers can mirror it
+/// without replaying the market logic.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum BetEvent {
+    /// A market was created
+    BetCreated { bet: Bet },
+    /// A wager was placed, or shares were bought from the market maker
+    WagerPlaced { bet: Bet, user_bet: UserBet },
+    /// Liquidity was provided to a market
+    LiquidityAdded {
+        bet: Bet,
+        provider: AccountOwner,
+        amount: Amount,
+    },
+    /// Shares were sold back to the market maker
+    SharesSold {
+        bet: Bet,
+        owner: AccountOwner,
+        outcome_index: u32,
+        amount: Amount,
+    },
+    /// A market stopped accepting wagers
+    BetClosed { bet: Bet },
+    /// A market was resolved, or voided, or its dispute was settled
+    BetResolved { bet: Bet },
+    /// The resolution of a market was challenged
+    BetDisputed { bet: Bet },
+    /// A market was cancelled
+    BetCancelled { bet: Bet },
+}
+
+impl BetEvent {
+    /// The bet after the event
+    pub fn bet(&self) -> &Bet {
+        match self {
+            BetEvent::BetCreated { bet }
+            | BetEvent::WagerPlaced { bet, .. }
+            | BetEvent::LiquidityAdded { bet, .. }
+            | BetEvent::SharesSold { bet, .. }
+            | BetEvent::BetClosed { bet }
+            | BetEvent::BetResolved { bet }
+            | BetEvent::BetDisputed { bet }
+            | BetEvent::BetCancelled { bet } => bet,
+        }
+    }
+}
+
+/// The name of the event stream of a market
+pub fn market_stream(bet_id: &str) -> StreamName {
+    StreamName::from(format!("bet:{bet_id}"))
+}
+
+/// The ID of the market an event stream belongs to, if it is a market stream
+pub fn market_of_stream(stream_name: &StreamName) -> Option<&str> {
+    std::str::from_utf8(&stream_name.0)
+        .ok()?
+        .strip_prefix("bet:")
 }
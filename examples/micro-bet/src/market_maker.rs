@@ -0,0 +1,123 @@
+// StormCast Micro-Bet Market Maker
+// Constant-product pricing of outcome shares, in the style of `examples/amm`
+//
+// The market maker holds a reserve of shares for every outcome. Buying an outcome with some
+// collateral mints that many complete sets of shares into the reserves, then takes shares of the
+// bought outcome out until the product of all reserves is back to its previous value. Selling
+// does the reverse. Each share of the winning outcome redeems one token once the bet is resolved.
+
+use linera_sdk::linera_base_types::Amount;
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive, Zero};
+
+/// Computes the shares of `outcome_index` received for `amount` of collateral, and the new
+/// reserves
+pub fn buy(reserves: &[Amount], outcome_index: usize, amount: Amount) -> (Amount, Vec<Amount>) {
+    let invariant = product(reserves.iter().copied());
+    let others = product(
+        other_reserves(reserves, outcome_index).map(|reserve| reserve.saturating_add(amount)),
+    );
+    let outcome_reserve = div_ceil(invariant, others);
+    let shares = big(reserves[outcome_index].saturating_add(amount)) - &outcome_reserve;
+
+    let mut new_reserves = reserves
+        .iter()
+        .map(|reserve| reserve.saturating_add(amount))
+        .collect::<Vec<_>>();
+    new_reserves[outcome_index] = to_amount(outcome_reserve);
+    (to_amount(shares), new_reserves)
+}
+
+/// Computes the shares of `outcome_index` needed to get `amount` of collateral back, and the
+/// new reserves, or `None` if the reserves cannot cover `amount`
+pub fn sell(
+    reserves: &[Amount],
+    outcome_index: usize,
+    amount: Amount,
+) -> Option<(Amount, Vec<Amount>)> {
+    if other_reserves(reserves, outcome_index).any(|reserve| reserve <= amount) {
+        return None;
+    }
+    let invariant = product(reserves.iter().copied());
+    let others = product(
+        other_reserves(reserves, outcome_index).map(|reserve| reserve.saturating_sub(amount)),
+    );
+    let outcome_reserve = div_ceil(invariant, others);
+    let shares = &outcome_reserve + big(amount) - big(reserves[outcome_index]);
+
+    let mut new_reserves = reserves
+        .iter()
+        .map(|reserve| reserve.saturating_sub(amount))
+        .collect::<Vec<_>>();
+    new_reserves[outcome_index] = to_amount(outcome_reserve);
+    Some((to_amount(shares), new_reserves))
+}
+
+/// The marginal price of one share of `outcome_index`, in tokens
+///
+/// Prices of all outcomes add up to one token.
+pub fn price(reserves: &[Amount], outcome_index: usize) -> Amount {
+    let weight = |index: usize| product(other_reserves(reserves, index));
+    let total = (0..reserves.len())
+        .map(weight)
+        .fold(BigUint::zero(), |sum, w| sum + w);
+    if total.is_zero() {
+        return Amount::ZERO;
+    }
+    to_amount(weight(outcome_index) * big(Amount::ONE) / total)
+}
+
+/// Adds `amount` of collateral to the reserves without moving prices, returning the new
+/// reserves and the shares of each outcome left over for the liquidity provider
+///
+/// The collateral mints that many complete sets of shares. Every reserve grows in proportion
+/// to its size, the largest one taking a whole set, and the rest of each set is returned.
+pub fn add_liquidity(reserves: &[Amount], amount: Amount) -> (Vec<Amount>, Vec<Amount>) {
+    let largest = reserves.iter().copied().max().unwrap_or(Amount::ZERO);
+    let added = reserves
+        .iter()
+        .map(|reserve| {
+            if largest == Amount::ZERO {
+                amount
+            } else {
+                to_amount(big(amount) * big(*reserve) / big(largest))
+            }
+        })
+        .collect::<Vec<_>>();
+    let new_reserves = reserves
+        .iter()
+        .zip(&added)
+        .map(|(reserve, added)| reserve.saturating_add(*added))
+        .collect();
+    let returned = added
+        .iter()
+        .map(|added| amount.saturating_sub(*added))
+        .collect();
+    (new_reserves, returned)
+}
+
+/// The reserves of all outcomes except `outcome_index`
+fn other_reserves(reserves: &[Amount], outcome_index: usize) -> impl Iterator<Item = Amount> + '_ {
+    reserves
+        .iter()
+        .enumerate()
+        .filter(move |(index, _)| *index != outcome_index)
+        .map(|(_, reserve)| *reserve)
+}
+
+fn product(amounts: impl Iterator<Item = Amount>) -> BigUint {
+    amounts.fold(BigUint::one(), |product, amount| product * big(amount))
+}
+
+/// Rounds up, so that the market maker never loses value to rounding
+fn div_ceil(numerator: BigUint, denominator: BigUint) -> BigUint {
+    (numerator + &denominator - BigUint::one()) / denominator
+}
+
+fn big(amount: Amount) -> BigUint {
+    BigUint::from(u128::from(amount))
+}
+
+fn to_amount(value: BigUint) -> Amount {
+    Amount::from_attos(value.to_u128().expect("Result does not fit into an amount"))
+}
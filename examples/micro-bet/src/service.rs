@@ -5,18 +5,30 @@
 
 mod state;
 
-use std::sync::Arc;
+use std::{pin::pin, sync::Arc};
 
-use async_graphql::{EmptySubscription, Request, Response, Schema};
+use async_graphql::{
+    futures_util::{stream, Stream, StreamExt as _, TryStreamExt as _},
+    ComplexObject, Context, Request, Response, Schema, Subscription,
+};
 use linera_sdk::{
+    ensure,
     graphql::GraphQLMutationRoot,
-    linera_base_types::WithServiceAbi,
-    views::View,
+    http,
+    linera_base_types::{AccountOwner, Amount, Timestamp, WithServiceAbi},
+    views::{linera_views::store::KeyRange, View},
     Service, ServiceRuntime,
 };
-use micro_bet::Operation;
+use micro_bet::{
+    bet_of_status_key, label_key, parse_decimal, status_key, Bet, BetStatus, BookOrder,
+    LiquidityPosition, MarketOdds, MarketSort, Operation, Parameters, Parlay, Quote, TradeSide,
+    UserBet,
+};
 use state::MicroBetState;
 
+/// The number of items returned by paginated queries, unless fewer are requested
+const MAX_PAGE_SIZE: usize = 100;
+
 pub struct MicroBetService {
     state: Arc<MicroBetState>,
     runtime: Arc<ServiceRuntime<Self>>,
@@ -29,7 +41,7 @@ impl WithServiceAbi for MicroBetService {
 }
 
 impl Service for MicroBetService {
-    type Parameters = ();
+    type Parameters = Parameters;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = MicroBetState::load(runtime.root_view_storage_context())
@@ -45,9 +57,370 @@ impl Service for MicroBetService {
         let schema = Schema::build(
             self.state.clone(),
             Operation::mutation_root(self.runtime.clone()),
-            EmptySubscription,
+            SubscriptionRoot {
+                state: self.state.clone(),
+            },
         )
+        .data(self.runtime.clone())
+        .data(self.runtime.system_time())
         .finish();
-        schema.execute(request).await
+        // Subscriptions are answered with their current value: the node service's
+        // `applicationQuery` subscription runs them again after every new block.
+        let mut responses = schema.execute_stream(request);
+        responses.next().await.unwrap_or_default()
+    }
+}
+
+/// Live updates of the markets, pushed to clients by the node service as blocks are added
+struct SubscriptionRoot {
+    state: Arc<MicroBetState>,
+}
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// The odds, pools, volume and number of bettors of a bet
+    async fn market_odds(
+        &self,
+        ctx: &Context<'_>,
+        bet_id: String,
+    ) -> async_graphql::Result<impl Stream<Item = MarketOdds>> {
+        let now = *ctx.data::<Timestamp>()?;
+        let odds = market_odds(&self.state, &bet_id, now).await?;
+        Ok(stream::iter(Some(odds)))
+    }
+}
+
+/// Takes a snapshot of the odds of a bet
+async fn market_odds(
+    state: &MicroBetState,
+    bet_id: &str,
+    now: Timestamp,
+) -> async_graphql::Result<MarketOdds> {
+    let bet = state
+        .bets
+        .get(bet_id)
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Bet not found"))?;
+    let stats = state.market_stats.get(bet_id).await?.unwrap_or_default();
+    Ok(MarketOdds::new(&bet, &stats, now))
+}
+
+#[ComplexObject]
+impl MicroBetState {
+    /// The positions in a bet, grouped by owner, of at most `first` owners following the
+    /// owner `after`
+    async fn positions(
+        &self,
+        bet_id: String,
+        after: Option<AccountOwner>,
+        first: Option<usize>,
+    ) -> async_graphql::Result<Vec<UserBet>> {
+        let Some(market_positions) = self.market_positions.try_load_entry(&bet_id).await? else {
+            return Ok(Vec::new());
+        };
+        let limit = first.unwrap_or(MAX_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let owners = market_positions.page(after.as_ref(), limit).await?;
+        Ok(owners
+            .into_iter()
+            .flat_map(|(_, positions)| positions)
+            .collect())
+    }
+
+    /// All positions of an owner, in every bet
+    async fn my_positions(&self, owner: AccountOwner) -> async_graphql::Result<Vec<UserBet>> {
+        let Some(bet_ids) = self.owner_markets.try_load_entry(&owner).await? else {
+            return Ok(Vec::new());
+        };
+        let mut positions = Vec::new();
+        for bet_id in bet_ids.indices().await? {
+            if let Some(market_positions) = self.market_positions.try_load_entry(&bet_id).await? {
+                positions.extend(market_positions.get(&owner).await?.unwrap_or_default());
+            }
+        }
+        Ok(positions)
+    }
+
+    /// All parlays placed by an owner, oldest first
+    async fn my_parlays(&self, owner: AccountOwner) -> async_graphql::Result<Vec<Parlay>> {
+        let Some(parlay_ids) = self.owner_parlays.try_load_entry(&owner).await? else {
+            return Ok(Vec::new());
+        };
+        let mut parlays = Vec::new();
+        for parlay_id in parlay_ids.indices().await? {
+            if let Some(parlay) = self.parlays.get(&parlay_id).await? {
+                parlays.push(parlay);
+            }
+        }
+        Ok(parlays)
+    }
+
+    /// The liquidity provider positions in a bet
+    async fn liquidity_providers(
+        &self,
+        bet_id: String,
+    ) -> async_graphql::Result<Vec<LiquidityPosition>> {
+        let Some(positions) = self.liquidity_positions.try_load_entry(&bet_id).await? else {
+            return Ok(Vec::new());
+        };
+        let mut providers = Vec::new();
+        positions
+            .for_each_index_value(|_, position| {
+                providers.push(position.into_owned());
+                Ok(())
+            })
+            .await?;
+        Ok(providers)
+    }
+
+    /// All liquidity provider positions of an owner, in every bet
+    async fn my_liquidity(
+        &self,
+        owner: AccountOwner,
+    ) -> async_graphql::Result<Vec<LiquidityPosition>> {
+        let Some(bet_ids) = self.owner_liquidity.try_load_entry(&owner).await? else {
+            return Ok(Vec::new());
+        };
+        let mut liquidity = Vec::new();
+        for bet_id in bet_ids.indices().await? {
+            if let Some(positions) = self.liquidity_positions.try_load_entry(&bet_id).await? {
+                liquidity.extend(positions.get(&owner).await?);
+            }
+        }
+        Ok(liquidity)
+    }
+
+    /// The resting orders on one side of the order book of an outcome, best price first
+    async fn order_book(
+        &self,
+        bet_id: String,
+        outcome_index: u32,
+        side: TradeSide,
+    ) -> async_graphql::Result<Vec<BookOrder>> {
+        let Some(book) = self.order_books.get(&(bet_id, outcome_index)).await? else {
+            return Ok(Vec::new());
+        };
+        let entries = match side {
+            TradeSide::Buy => book.bids,
+            TradeSide::Sell => book.asks,
+        };
+        let mut orders = Vec::new();
+        for entry in entries {
+            if let Some(order) = self.orders.get(&entry.order_id).await? {
+                orders.push(order);
+            }
+        }
+        Ok(orders)
+    }
+
+    /// The fees accrued to a recipient and not withdrawn yet
+    async fn accrued_fees(&self, owner: AccountOwner) -> async_graphql::Result<Amount> {
+        Ok(self.fees.get(&owner).await?.unwrap_or_default())
+    }
+
+    /// The bets currently in the given status, at most `first` of them following the bet
+    /// `after`, in the order of their IDs
+    async fn markets_by_status(
+        &self,
+        ctx: &Context<'_>,
+        status: BetStatus,
+        after: Option<String>,
+        first: Option<usize>,
+    ) -> async_graphql::Result<Vec<Bet>> {
+        let now = *ctx.data::<Timestamp>()?;
+        let limit = first.unwrap_or(MAX_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let scan = |recorded: BetStatus| {
+            let mut range = KeyRange::prefix(&[recorded as u8]);
+            if let Some(after) = &after {
+                range = range.after(&status_key(recorded, after));
+            }
+            self.status_markets
+                .keys_stream(range)
+                .map_ok(|key| bet_of_status_key(&key))
+        };
+        // Open bets past their expiry are closed even before `CloseExpired` records it, so
+        // closed bets are merged from both parts of the index
+        let mut recorded = pin!(scan(status));
+        let mut expired = pin!(scan(BetStatus::Open));
+        let mut next_recorded = recorded.try_next().await?;
+        let mut next_expired = match status {
+            BetStatus::Closed => expired.try_next().await?,
+            _ => None,
+        };
+
+        let mut page = Vec::new();
+        while page.len() < limit {
+            let is_expired = match (&next_recorded, &next_expired) {
+                (Some(recorded_id), Some(expired_id)) => expired_id < recorded_id,
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                (None, None) => break,
+            };
+            let bet_id = if is_expired {
+                std::mem::replace(&mut next_expired, expired.try_next().await?)
+            } else {
+                std::mem::replace(&mut next_recorded, recorded.try_next().await?)
+            };
+            let Some(bet) = self.bets.get(&bet_id.expect("Checked above")).await? else {
+                continue;
+            };
+            if bet.status_at(now) == status {
+                page.push(bet);
+            }
+        }
+        Ok(page)
+    }
+
+    /// The bets matching every given filter, in the given order, at most `first` of them
+    /// following the bet `after`
+    ///
+    /// Categories, regions and tags are matched ignoring case. Pages are read from an index
+    /// kept in the requested order, resuming at the position of the bet `after`. That position
+    /// never changes when sorting by creation or expiry; when sorting by volume, it follows
+    /// the volume of that bet as it trades.
+    #[allow(clippy::too_many_arguments)]
+    async fn markets(
+        &self,
+        ctx: &Context<'_>,
+        category: Option<String>,
+        region: Option<String>,
+        tag: Option<String>,
+        status: Option<BetStatus>,
+        sort_by: Option<MarketSort>,
+        after: Option<String>,
+        first: Option<usize>,
+    ) -> async_graphql::Result<Vec<Bet>> {
+        let now = *ctx.data::<Timestamp>()?;
+        let limit = first.unwrap_or(MAX_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let sort_by = sort_by.unwrap_or_default();
+
+        let filters = [
+            (&self.category_markets, category),
+            (&self.region_markets, region),
+            (&self.tag_markets, tag),
+        ];
+        let mut label_sets = Vec::new();
+        for (index, label) in filters {
+            let Some(label) = label else {
+                continue;
+            };
+            match index.try_load_entry(&label_key(&label)).await? {
+                Some(bet_ids) => label_sets.push(bet_ids),
+                None => return Ok(Vec::new()),
+            }
+        }
+
+        let mut range = KeyRange::full();
+        if let Some(after) = after {
+            let Some(bet) = self.bets.get(&after).await? else {
+                return Ok(Vec::new());
+            };
+            let stats = self.market_stats.get(&after).await?.unwrap_or_default();
+            range = range.after(&sort_by.index_key(&bet, stats.volume));
+        }
+        let index = match sort_by {
+            MarketSort::Created => &self.created_markets,
+            MarketSort::Expiry => &self.expiry_markets,
+            MarketSort::Volume => &self.volume_markets,
+        };
+        let mut keys = pin!(index.keys_stream(range));
+
+        let mut page = Vec::new();
+        'keys: while page.len() < limit {
+            let Some(key) = keys.try_next().await? else {
+                break;
+            };
+            let bet_id = sort_by.bet_of_key(&key);
+            for bet_ids in &label_sets {
+                if !bet_ids.contains(&bet_id).await? {
+                    continue 'keys;
+                }
+            }
+            let Some(bet) = self.bets.get(&bet_id).await? else {
+                continue;
+            };
+            if status.is_none_or(|status| bet.status_at(now) == status) {
+                page.push(bet);
+            }
+        }
+        Ok(page)
+    }
+
+    /// The odds, pools, volume and number of bettors of a bet
+    async fn market_odds(
+        &self,
+        ctx: &Context<'_>,
+        bet_id: String,
+    ) -> async_graphql::Result<MarketOdds> {
+        market_odds(self, &bet_id, *ctx.data::<Timestamp>()?).await
+    }
+
+    /// Quotes a trade of `amount` tokens against the market maker of a bet
+    async fn quote(
+        &self,
+        bet_id: String,
+        outcome_index: u32,
+        side: TradeSide,
+        amount: Amount,
+    ) -> async_graphql::Result<Quote> {
+        let bet = self
+            .bets
+            .get(&bet_id)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Bet not found"))?;
+        bet.quote(outcome_index, side, amount)
+            .ok_or_else(|| async_graphql::Error::new("Trade cannot be quoted"))
+    }
+
+    /// Fetches the number deciding the outcome of a bet from its oracle source
+    ///
+    /// The contract queries this as an oracle when executing `ResolveFromOracle`.
+    async fn oracle_value(
+        &self,
+        ctx: &Context<'_>,
+        bet_id: String,
+    ) -> async_graphql::Result<String> {
+        let bet = self
+            .bets
+            .get(&bet_id)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Bet not found"))?;
+        let oracle = bet
+            .oracle
+            .ok_or_else(|| async_graphql::Error::new("Bet has no oracle source"))?;
+
+        let runtime = ctx.data::<Arc<ServiceRuntime<MicroBetService>>>()?;
+        let response = runtime.http_request(http::Request::get(oracle.url));
+        ensure!(
+            response.status == 200,
+            async_graphql::Error::new(format!(
+                "HTTP request failed with status code {}",
+                response.status
+            ))
+        );
+
+        let json = serde_json::from_slice::<serde_json::Value>(&response.body)?;
+        let value = oracle
+            .json_path
+            .trim_start_matches("$.")
+            .split('.')
+            .try_fold(&json, |value, segment| match value {
+                serde_json::Value::Array(items) => segment
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| items.get(index)),
+                _ => value.get(segment),
+            })
+            .ok_or_else(|| async_graphql::Error::new("JSON path not found in oracle response"))?;
+
+        let number = match value {
+            serde_json::Value::Number(number) => number.to_string(),
+            serde_json::Value::String(string) => string.clone(),
+            _ => String::new(),
+        };
+        ensure!(
+            parse_decimal(&number).is_some(),
+            async_graphql::Error::new("Oracle value is not a number")
+        );
+        Ok(number)
     }
 }
@@ -1,21 +1,105 @@
 // StormCast Micro-Bet Application State
 
 use linera_sdk::{
-    linera_base_types::AccountOwner,
-    views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext},
+    linera_base_types::{AccountOwner, Amount},
+    views::{
+        linera_views::{self, set_view::ByteSetView},
+        CollectionView, MapView, RegisterView, RootView, SetView, ViewStorageContext,
+    },
 };
-use micro_bet::{Bet, InstantiationArgument, UserBet};
+use micro_bet::{
+    Bet, BookOrder, InstantiationArgument, LiquidityPosition, MarketStats, Parlay, UserBet,
+};
+use serde::{Deserialize, Serialize};
+
+/// A resting order on one side of an order book
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BookEntry {
+    pub price: Amount,
+    pub order_id: u64,
+}
+
+/// The resting orders on the shares of one outcome of a bet, best price first and oldest
+/// first among equal prices
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct OrderBook {
+    /// Buy orders, highest price first
+    pub bids: Vec<BookEntry>,
+    /// Sell orders, lowest price first
+    pub asks: Vec<BookEntry>,
+}
 
 /// The micro-bet application state
 #[derive(RootView, async_graphql::SimpleObject)]
+#[graphql(complex)]
 #[view(context = ViewStorageContext)]
 pub struct MicroBetState {
     /// Counter for generating unique bet IDs
     pub bet_counter: RegisterView<u64>,
     /// All bets in the system
     pub bets: MapView<String, Bet>,
-    /// User bets indexed by owner
-    pub user_bets: MapView<AccountOwner, Vec<UserBet>>,
+    /// The bets to look at again once a deadline passes, keyed by the big-endian deadline and
+    /// then the bet ID, so that the due ones come first
+    #[graphql(skip)]
+    pub deadlines: ByteSetView<ViewStorageContext>,
+    /// Positions indexed by bet ID, then by owner
+    #[graphql(skip)]
+    pub market_positions: CollectionView<String, MapView<AccountOwner, Vec<UserBet>>>,
+    /// The IDs of the bets each owner has positions in
+    #[graphql(skip)]
+    pub owner_markets: CollectionView<AccountOwner, SetView<String>>,
+    /// The IDs of the bets in each category, by lowercase category
+    #[graphql(skip)]
+    pub category_markets: CollectionView<String, SetView<String>>,
+    /// The IDs of the bets about each region, by lowercase region
+    #[graphql(skip)]
+    pub region_markets: CollectionView<String, SetView<String>>,
+    /// The IDs of the bets with each tag, by lowercase tag
+    #[graphql(skip)]
+    pub tag_markets: CollectionView<String, SetView<String>>,
+    /// The IDs of all bets, oldest first, keyed by `MarketSort::Created`
+    #[graphql(skip)]
+    pub created_markets: ByteSetView<ViewStorageContext>,
+    /// The IDs of all bets, soonest expiry first, keyed by `MarketSort::Expiry`
+    #[graphql(skip)]
+    pub expiry_markets: ByteSetView<ViewStorageContext>,
+    /// The IDs of all bets, highest volume first, keyed by `MarketSort::Volume`
+    #[graphql(skip)]
+    pub volume_markets: ByteSetView<ViewStorageContext>,
+    /// The IDs of all bets, by recorded status and then by ID, keyed by `status_key`
+    #[graphql(skip)]
+    pub status_markets: ByteSetView<ViewStorageContext>,
+    /// The number of unresolved bets of each creator
+    pub open_markets: MapView<AccountOwner, u32>,
+    /// Liquidity provider positions indexed by bet ID, then by provider
+    #[graphql(skip)]
+    pub liquidity_positions: CollectionView<String, MapView<AccountOwner, LiquidityPosition>>,
+    /// The IDs of the bets each owner provided liquidity to
+    #[graphql(skip)]
+    pub owner_liquidity: CollectionView<AccountOwner, SetView<String>>,
+    /// Aggregates over the positions of each bet
+    pub market_stats: MapView<String, MarketStats>,
+    /// Fees accrued to each recipient and not withdrawn yet
+    pub fees: MapView<AccountOwner, Amount>,
+    /// Counter for generating unique order IDs
+    pub order_counter: RegisterView<u64>,
+    /// The resting orders, by ID
+    pub orders: MapView<u64, BookOrder>,
+    /// The order book of each outcome of each bet, indexed by bet ID and outcome index
+    #[graphql(skip)]
+    pub order_books: MapView<(String, u32), OrderBook>,
+    /// Counter for generating unique parlay IDs
+    pub parlay_counter: RegisterView<u64>,
+    /// All parlays, by ID
+    pub parlays: MapView<u64, Parlay>,
+    /// The IDs of the parlays not settled yet, indexed by the bet ID of each of their legs
+    #[graphql(skip)]
+    pub leg_parlays: CollectionView<String, SetView<u64>>,
+    /// The IDs of the parlays placed by each owner
+    #[graphql(skip)]
+    pub owner_parlays: CollectionView<AccountOwner, SetView<u64>>,
+    /// Funds backing the potential winnings of open parlays, beyond their stakes
+    pub parlay_reserve: RegisterView<Amount>,
     /// The instantiation argument
     pub instantiation_argument: RegisterView<Option<InstantiationArgument>>,
 }
@@ -27,4 +111,18 @@ impl MicroBetState {
         *counter += 1;
         format!("bet_{}", counter)
     }
+
+    /// Generate a new unique order ID
+    pub fn next_order_id(&mut self) -> u64 {
+        let counter = self.order_counter.get_mut();
+        *counter += 1;
+        *counter
+    }
+
+    /// Generate a new unique parlay ID
+    pub fn next_parlay_id(&mut self) -> u64 {
+        let counter = self.parlay_counter.get_mut();
+        *counter += 1;
+        *counter
+    }
 }
@@ -0,0 +1,208 @@
+// StormCast Micro-Bet Integration Tests
+// Trading from user chains against the markets on the hub chain
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use linera_sdk::{
+    linera_base_types::{Account, AccountOwner, Amount, ApplicationId, ContractAbi},
+    test::{ActiveChain, MessageAction, QueryOutcome, TestValidator},
+};
+use micro_bet::{
+    InstantiationArgument, MicroBetAbi, MicroBetError, MicroBetResponse, Operation, Parameters,
+    Resolution,
+};
+
+/// Tests wagering from a user chain, mirroring the market there, and claiming the winnings.
+///
+/// The stake is sent to the hub's escrow together with the wager, and the hub pays the winnings
+/// back to the user chain once the bet is resolved.
+#[tokio::test]
+async fn remote_wager_and_claim() {
+    let argument = InstantiationArgument {
+        dispute_window_seconds: Some(0),
+        ..InstantiationArgument::default()
+    };
+    let (validator, app_id, hub) = TestValidator::with_current_application::<MicroBetAbi, _, _>(
+        Parameters::default(),
+        argument,
+    )
+    .await;
+    let user_chain = validator.new_chain().await;
+    let user = AccountOwner::from(user_chain.public_key());
+    fund(&validator, &user_chain, user).await;
+
+    let bet_id = create_bet(&hub, app_id).await;
+
+    let response = execute(
+        &user_chain,
+        app_id,
+        Operation::Subscribe {
+            bet_id: bet_id.clone(),
+        },
+    )
+    .await;
+    assert!(matches!(response, Ok(MicroBetResponse::Ok)));
+
+    let stake = Amount::from_tokens(3);
+    let operation = Operation::PlaceBet {
+        bet_id: bet_id.clone(),
+        outcome_index: 0,
+        amount: stake,
+    };
+    let response = execute(&user_chain, app_id, operation).await;
+    assert!(matches!(response, Ok(MicroBetResponse::Forwarded)));
+    assert_eq!(
+        user_chain.owner_balance(&user).await,
+        Some(INITIAL_BALANCE.saturating_sub(stake))
+    );
+
+    hub.handle_received_messages().await;
+    user_chain.handle_received_messages().await;
+    hub.handle_received_messages().await;
+    user_chain.handle_new_events().await;
+
+    // The user chain keeps a copy of the position and mirrors the market
+    let query = format!(
+        "query {{ \
+            myPositions(owner: \"{user}\") {{ amount }} \
+            bets {{ entry(key: \"{bet_id}\") {{ value {{ pools }} }} }} \
+        }}"
+    );
+    let QueryOutcome { response, .. } = user_chain.graphql_query(app_id, &*query).await;
+    assert_eq!(response["myPositions"][0]["amount"], stake.to_string());
+    assert_eq!(
+        response["bets"]["entry"]["value"]["pools"][0],
+        stake.to_string()
+    );
+
+    let resolution = Resolution::Outcome(0);
+    let operation = Operation::ResolveBet {
+        bet_id: bet_id.clone(),
+        resolution,
+    };
+    let response = execute(&hub, app_id, operation).await;
+    assert!(matches!(response, Ok(MicroBetResponse::Ok)));
+
+    let response = execute(&user_chain, app_id, Operation::ClaimWinnings { bet_id }).await;
+    assert!(matches!(response, Ok(MicroBetResponse::Forwarded)));
+
+    hub.handle_received_messages().await;
+    user_chain.handle_received_messages().await;
+
+    assert_eq!(user_chain.owner_balance(&user).await, Some(INITIAL_BALANCE));
+}
+
+/// Tests that a wager the hub rejects bounces back together with its stake.
+#[tokio::test]
+async fn rejected_wager_is_refunded() {
+    let (validator, app_id, hub) = TestValidator::with_current_application::<MicroBetAbi, _, _>(
+        Parameters::default(),
+        InstantiationArgument::default(),
+    )
+    .await;
+    let user_chain = validator.new_chain().await;
+    let user = AccountOwner::from(user_chain.public_key());
+    fund(&validator, &user_chain, user).await;
+
+    let (certificate, _) = user_chain
+        .add_block(|block| {
+            let operation = Operation::PlaceBet {
+                bet_id: "bet_404".to_string(),
+                outcome_index: 0,
+                amount: Amount::ONE,
+            };
+            block.with_operation(app_id, operation);
+        })
+        .await;
+    assert_eq!(
+        user_chain.owner_balance(&user).await,
+        Some(INITIAL_BALANCE.saturating_sub(Amount::ONE))
+    );
+
+    hub.add_block(|block| {
+        block.with_messages_from_by_action(&certificate, MessageAction::Reject);
+    })
+    .await;
+    user_chain.handle_received_messages().await;
+
+    assert_eq!(user_chain.owner_balance(&user).await, Some(INITIAL_BALANCE));
+}
+
+/// Tests that user chains cannot run operations that only make sense on the hub.
+#[tokio::test]
+async fn hub_only_operations() {
+    let (validator, app_id, hub) = TestValidator::with_current_application::<MicroBetAbi, _, _>(
+        Parameters::default(),
+        InstantiationArgument::default(),
+    )
+    .await;
+    let user_chain = validator.new_chain().await;
+    let bet_id = create_bet(&hub, app_id).await;
+
+    let response = execute(
+        &user_chain,
+        app_id,
+        Operation::CloseBet {
+            bet_id: bet_id.clone(),
+        },
+    )
+    .await;
+    assert_eq!(response.unwrap_err(), MicroBetError::HubOnly);
+
+    let response = execute(&hub, app_id, Operation::Subscribe { bet_id }).await;
+    assert_eq!(response.unwrap_err(), MicroBetError::AlreadyOnHub);
+}
+
+const INITIAL_BALANCE: Amount = Amount::from_tokens(10);
+
+/// Gives `owner` `INITIAL_BALANCE` tokens on the chain
+async fn fund(validator: &TestValidator, chain: &ActiveChain, owner: AccountOwner) {
+    let admin_chain = validator.get_chain(&validator.admin_chain_id());
+    let recipient = Account::new(chain.id(), owner);
+    let (certificate, _) = admin_chain
+        .add_block(|block| {
+            block.with_native_token_transfer(AccountOwner::CHAIN, recipient, INITIAL_BALANCE);
+        })
+        .await;
+    chain
+        .add_block(|block| {
+            block.with_messages_from(&certificate);
+        })
+        .await;
+}
+
+/// Creates a YES/NO bet expiring after a minute, returning its ID
+async fn create_bet(chain: &ActiveChain, app_id: ApplicationId<MicroBetAbi>) -> String {
+    let operation = Operation::CreateBet {
+        question: "Will it rain tomorrow?".to_string(),
+        duration_seconds: 60,
+        outcomes: None,
+        liquidity: None,
+        seed: None,
+        oracle: None,
+        creator_fee_bps: None,
+        lp_fee_bps: None,
+        metadata: None,
+    };
+    match execute(chain, app_id, operation).await {
+        Ok(MicroBetResponse::BetCreated(bet_id)) => bet_id,
+        response => panic!("Unexpected response: {response:?}"),
+    }
+}
+
+/// Executes an operation in a new block, returning the application's response
+async fn execute(
+    chain: &ActiveChain,
+    app_id: ApplicationId<MicroBetAbi>,
+    operation: Operation,
+) -> Result<MicroBetResponse, MicroBetError> {
+    let (certificate, _) = chain
+        .add_block(|block| {
+            block.with_operation(app_id, operation);
+        })
+        .await;
+    let result = certificate.inner().block().body.operation_results[0]
+        .0
+        .clone();
+    MicroBetAbi::deserialize_response(result).expect("Failed to deserialize response")
+}
@@ -0,0 +1,265 @@
+// StormCast Micro-Bet Integration Tests
+// Bet lifecycle on the hub chain, run against a test validator
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use linera_sdk::{
+    linera_base_types::{
+        Account, AccountOwner, AccountSecretKey, Amount, ApplicationId, ContractAbi, TimeDelta,
+        TimeoutConfig, Timestamp,
+    },
+    test::{ActiveChain, QueryOutcome, TestValidator},
+};
+use micro_bet::{
+    InstantiationArgument, MicroBetAbi, MicroBetError, MicroBetResponse, Operation, Parameters,
+    Resolution,
+};
+
+/// Tests creating a bet, wagering on it, closing and resolving it, and claiming the winnings.
+#[tokio::test]
+async fn bet_lifecycle() {
+    let argument = InstantiationArgument {
+        dispute_window_seconds: Some(0),
+        ..InstantiationArgument::default()
+    };
+    let (validator, app_id, mut hub) =
+        TestValidator::with_current_application::<MicroBetAbi, _, _>(
+            Parameters::default(),
+            argument,
+        )
+        .await;
+    let creator_key = hub.key_pair().copy();
+    let creator = AccountOwner::from(hub.public_key());
+    let bettor_key = AccountSecretKey::generate();
+    let bettor = AccountOwner::from(bettor_key.public());
+    fund(&validator, &hub, [creator, bettor]).await;
+    add_owners(&hub, [bettor]).await;
+
+    let bet_id = create_bet(&hub, app_id, 60).await;
+
+    hub.set_key_pair(bettor_key.copy());
+    let stake = Amount::from_tokens(3);
+    let response = execute(
+        &hub,
+        app_id,
+        Operation::PlaceBet {
+            bet_id: bet_id.clone(),
+            outcome_index: 0,
+            amount: stake,
+        },
+    )
+    .await;
+    assert!(matches!(response, Ok(MicroBetResponse::Position(_))));
+    assert_eq!(
+        hub.owner_balance(&bettor).await,
+        Some(INITIAL_BALANCE.saturating_sub(stake))
+    );
+
+    let QueryOutcome { response, .. } = hub
+        .graphql_query(
+            app_id,
+            "query { positions(betId: \"bet_1\") { owner amount } }",
+        )
+        .await;
+    assert_eq!(response["positions"].as_array().map(Vec::len), Some(1));
+
+    hub.set_key_pair(creator_key);
+    let response = execute(
+        &hub,
+        app_id,
+        Operation::CloseBet {
+            bet_id: bet_id.clone(),
+        },
+    )
+    .await;
+    assert!(matches!(response, Ok(MicroBetResponse::Ok)));
+
+    let resolution = Resolution::Outcome(0);
+    let response = execute(
+        &hub,
+        app_id,
+        Operation::ResolveBet {
+            bet_id: bet_id.clone(),
+            resolution,
+        },
+    )
+    .await;
+    assert!(matches!(response, Ok(MicroBetResponse::Ok)));
+
+    let QueryOutcome { response, .. } = hub
+        .graphql_query(
+            app_id,
+            "query { bets { entry(key: \"bet_1\") { value { status } } } }",
+        )
+        .await;
+    assert_eq!(response["bets"]["entry"]["value"]["status"], "RESOLVED");
+
+    hub.set_key_pair(bettor_key.copy());
+    let response = execute(&hub, app_id, Operation::ClaimWinnings { bet_id }).await;
+    assert!(matches!(response, Ok(MicroBetResponse::Payout(payout)) if payout == stake));
+    assert_eq!(hub.owner_balance(&bettor).await, Some(INITIAL_BALANCE));
+}
+
+/// Tests that only the creator or the admin can close and resolve a bet.
+#[tokio::test]
+async fn only_creator_or_admin_can_settle() {
+    let (validator, app_id, mut hub) =
+        TestValidator::with_current_application::<MicroBetAbi, _, _>(
+            Parameters::default(),
+            InstantiationArgument::default(),
+        )
+        .await;
+    let creator = AccountOwner::from(hub.public_key());
+    fund(&validator, &hub, [creator]).await;
+    let stranger_key = AccountSecretKey::generate();
+    add_owners(&hub, [AccountOwner::from(stranger_key.public())]).await;
+    let bet_id = create_bet(&hub, app_id, 60).await;
+
+    hub.set_key_pair(stranger_key);
+    let response = execute(
+        &hub,
+        app_id,
+        Operation::CloseBet {
+            bet_id: bet_id.clone(),
+        },
+    )
+    .await;
+    assert_eq!(response.unwrap_err(), MicroBetError::NotCreatorOrAdmin);
+
+    let resolution = Resolution::Outcome(1);
+    let response = execute(&hub, app_id, Operation::ResolveBet { bet_id, resolution }).await;
+    assert_eq!(response.unwrap_err(), MicroBetError::NotCreatorOrAdmin);
+}
+
+/// Tests that wagers are refused once the bet expires, and that `CloseExpired` closes it.
+#[tokio::test]
+async fn bets_expire() {
+    let (validator, app_id, mut hub) =
+        TestValidator::with_current_application::<MicroBetAbi, _, _>(
+            Parameters::default(),
+            InstantiationArgument::default(),
+        )
+        .await;
+    let creator = AccountOwner::from(hub.public_key());
+    let bettor_key = AccountSecretKey::generate();
+    let bettor = AccountOwner::from(bettor_key.public());
+    fund(&validator, &hub, [creator, bettor]).await;
+    add_owners(&hub, [bettor]).await;
+    let bet_id = create_bet(&hub, app_id, 60).await;
+
+    validator.clock().add(TimeDelta::from_secs(61));
+    let now = validator.clock().current_time();
+
+    hub.set_key_pair(bettor_key);
+    let operation = Operation::PlaceBet {
+        bet_id,
+        outcome_index: 1,
+        amount: Amount::ONE,
+    };
+    let response = execute_at(&hub, app_id, operation, now).await;
+    assert_eq!(response.unwrap_err(), MicroBetError::BetExpired);
+    assert_eq!(hub.owner_balance(&bettor).await, Some(INITIAL_BALANCE));
+
+    let response = execute_at(&hub, app_id, Operation::CloseExpired, now).await;
+    assert!(matches!(response, Ok(MicroBetResponse::Ok)));
+
+    let QueryOutcome { response, .. } = hub
+        .graphql_query(
+            app_id,
+            "query { bets { entry(key: \"bet_1\") { value { status } } } }",
+        )
+        .await;
+    assert_eq!(response["bets"]["entry"]["value"]["status"], "CLOSED");
+}
+
+const INITIAL_BALANCE: Amount = Amount::from_tokens(10);
+
+/// Gives each owner `INITIAL_BALANCE` tokens on the chain
+async fn fund(
+    validator: &TestValidator,
+    chain: &ActiveChain,
+    owners: impl IntoIterator<Item = AccountOwner>,
+) {
+    let admin_chain = validator.get_chain(&validator.admin_chain_id());
+    let (certificate, _) = admin_chain
+        .add_block(|block| {
+            for owner in owners {
+                let recipient = Account::new(chain.id(), owner);
+                block.with_native_token_transfer(AccountOwner::CHAIN, recipient, INITIAL_BALANCE);
+            }
+        })
+        .await;
+    chain
+        .add_block(|block| {
+            block.with_messages_from(&certificate);
+        })
+        .await;
+}
+
+/// Lets each owner propose blocks on the chain, alongside its current owner
+async fn add_owners(chain: &ActiveChain, owners: impl IntoIterator<Item = AccountOwner>) {
+    let current_owner = AccountOwner::from(chain.public_key());
+    let owners = [current_owner]
+        .into_iter()
+        .chain(owners)
+        .map(|owner| (owner, 100))
+        .collect();
+    chain
+        .add_block(|block| {
+            block.with_owner_change(Vec::new(), owners, 10, false, TimeoutConfig::default());
+        })
+        .await;
+}
+
+/// Creates a YES/NO bet expiring after `duration_seconds`, returning its ID
+async fn create_bet(
+    chain: &ActiveChain,
+    app_id: ApplicationId<MicroBetAbi>,
+    duration_seconds: u64,
+) -> String {
+    let operation = Operation::CreateBet {
+        question: "Will it rain tomorrow?".to_string(),
+        duration_seconds,
+        outcomes: None,
+        liquidity: None,
+        seed: None,
+        oracle: None,
+        creator_fee_bps: None,
+        lp_fee_bps: None,
+        metadata: None,
+    };
+    match execute(chain, app_id, operation).await {
+        Ok(MicroBetResponse::BetCreated(bet_id)) => bet_id,
+        response => panic!("Unexpected response: {response:?}"),
+    }
+}
+
+/// Executes an operation in a new block, returning the application's response
+async fn execute(
+    chain: &ActiveChain,
+    app_id: ApplicationId<MicroBetAbi>,
+    operation: Operation,
+) -> Result<MicroBetResponse, MicroBetError> {
+    execute_at(chain, app_id, operation, Timestamp::from(0)).await
+}
+
+/// Executes an operation in a new block with the given timestamp, returning the application's
+/// response
+async fn execute_at(
+    chain: &ActiveChain,
+    app_id: ApplicationId<MicroBetAbi>,
+    operation: Operation,
+    timestamp: Timestamp,
+) -> Result<MicroBetResponse, MicroBetError> {
+    let (certificate, _) = chain
+        .add_block(|block| {
+            block
+                .with_timestamp(timestamp)
+                .with_operation(app_id, operation);
+        })
+        .await;
+    let result = certificate.inner().block().body.operation_results[0]
+        .0
+        .clone();
+    MicroBetAbi::deserialize_response(result).expect("Failed to deserialize response")
+}
@@ -1,8 +1,10 @@
 'use client';
 
+import { useEffect, useState } from 'react';
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query';
 import {
   getAllBets,
+  getMarkets,
   getBetById,
   getUserBets,
   createBet,
@@ -10,8 +12,12 @@ import {
   closeBet,
   resolveBet,
   checkServiceHealth,
+  subscribeToMarketOdds,
   getConfig,
   MicroBet,
+  MarketOdds,
+  MarketFilter,
+  MarketMetadata,
   UserBet,
 } from './linera-client';
 
@@ -38,24 +44,65 @@ export function useAllBets() {
   });
 }
 
-// Hook to fetch a specific bet
+// Hook to browse markets matching a filter
+export function useMarkets(filter: MarketFilter) {
+  const config = getConfig();
+  const canConnect = canConnectToLinera();
+  return useQuery<MicroBet[], Error>({
+    queryKey: ['markets', filter],
+    queryFn: () => getMarkets(filter),
+    refetchInterval: 5000,
+    retry: 1,
+    enabled: config.isConfigured && canConnect,
+  });
+}
+
+// Hook to fetch a specific bet, kept up to date by its live odds instead of polling
 export function useBet(betId: string) {
   const config = getConfig();
   const canConnect = canConnectToLinera();
+  const queryClient = useQueryClient();
+  const enabled = !!betId && config.isConfigured && canConnect;
+
+  useEffect(() => {
+    if (!enabled) return;
+    return subscribeToMarketOdds(betId, ({ pools, status }) => {
+      queryClient.setQueryData<MicroBet | null>(['bet', betId], (bet) =>
+        bet ? { ...bet, pools, status, yesPool: pools[0] ?? '0', noPool: pools[1] ?? '0' } : bet,
+      );
+    });
+  }, [betId, enabled, queryClient]);
+
   return useQuery<MicroBet | null, Error>({
     queryKey: ['bet', betId],
     queryFn: () => getBetById(betId),
-    enabled: !!betId && config.isConfigured && canConnect,
-    refetchInterval: 3000,
+    enabled,
     retry: 1,
   });
 }
 
+// Hook to follow the odds, volume and bettors of a market as blocks are added
+export function useMarketOdds(betId: string) {
+  const config = getConfig();
+  const canConnect = canConnectToLinera();
+  const [odds, setOdds] = useState<MarketOdds | null>(null);
+  const [error, setError] = useState<Error | null>(null);
+
+  useEffect(() => {
+    if (!betId || !config.isConfigured || !canConnect) return;
+    setOdds(null);
+    setError(null);
+    return subscribeToMarketOdds(betId, setOdds, setError);
+  }, [betId, config.isConfigured, canConnect]);
+
+  return { data: odds, error };
+}
+
 // Hook to fetch user bets - always disabled since we use localStorage
-export function useUserBets() {
+export function useUserBets(owner: string) {
   return useQuery<UserBet[], Error>({
-    queryKey: ['userBets'],
-    queryFn: getUserBets,
+    queryKey: ['userBets', owner],
+    queryFn: () => getUserBets(owner),
     enabled: false, // We don't use this - localStorage is primary source
   });
 }
@@ -78,10 +125,18 @@ export function useCreateBet() {
   const queryClient = useQueryClient();
 
   return useMutation({
-    mutationFn: ({ question, durationSeconds }: { question: string; durationSeconds: number }) =>
-      createBet(question, durationSeconds),
+    mutationFn: ({
+      question,
+      durationSeconds,
+      metadata,
+    }: {
+      question: string;
+      durationSeconds: number;
+      metadata?: Partial<MarketMetadata>;
+    }) => createBet(question, durationSeconds, metadata),
     onSuccess: () => {
       queryClient.invalidateQueries({ queryKey: ['bets'] });
+      queryClient.invalidateQueries({ queryKey: ['markets'] });
     },
   });
 }
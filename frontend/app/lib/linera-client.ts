@@ -5,26 +5,99 @@ const LINERA_SERVICE_URL = process.env.NEXT_PUBLIC_LINERA_SERVICE_URL || 'http:/
 const CHAIN_ID = process.env.NEXT_PUBLIC_CHAIN_ID || '1b53596ca4b3f838ee0228ed1409643b94109fba950d3846f29d2369fa9f253e';
 const APP_ID = process.env.NEXT_PUBLIC_MICRO_BET_APP_ID || '';
 
+// Resolution as serialized by the contract: the winning outcome index, or "Invalid"
+export type Resolution = { Outcome: number } | 'Invalid';
+
+export interface MarketMetadata {
+  category: string | null;
+  region: string | null;
+  location: { latitude: number; longitude: number } | null;
+  sourceUrl: string | null;
+  criteria: string | null;
+  tags: string[];
+}
+
+export interface MarketFilter {
+  category?: string;
+  region?: string;
+  tag?: string;
+  status?: MicroBet['status'];
+  sortBy?: 'CREATED' | 'VOLUME' | 'EXPIRY';
+  // Cursor: the ID of the last market of the previous page
+  after?: string;
+  first?: number;
+}
+
 export interface MicroBet {
   id: string;
   question: string;
+  kind: 'BINARY' | 'CATEGORICAL';
+  outcomes: string[];
+  pools: string[];
+  // Pools of the YES (index 0) and NO (index 1) outcomes of binary markets
   yesPool: string;
   noPool: string;
-  status: 'Open' | 'Closed' | 'Resolved';
+  status: 'Open' | 'Closed' | 'Resolved' | 'Disputed' | 'Cancelled';
   creator: string;
-  resolution: boolean | null;
+  resolution: Resolution | null;
   createdAt: string;
   expiresAt: string;
+  metadata: MarketMetadata;
 }
 
 export interface UserBet {
   betId: string;
   owner: string;
+  outcomeIndex: number;
+  // Whether the position backs YES (outcome index 0) of a binary market
   side: boolean;
   amount: string;
   timestamp: string;
 }
 
+// A live snapshot of a market's prices and activity
+export interface MarketOdds {
+  betId: string;
+  status: MicroBet['status'];
+  pools: string[];
+  // Decimal odds of each outcome, null while an outcome has no stake to price it
+  odds: Array<string | null>;
+  volume: string;
+  bettors: number;
+}
+
+const BET_FIELDS = `
+  id
+  question
+  kind
+  outcomes
+  pools
+  status
+  creator
+  resolution
+  createdAt
+  expiresAt
+  metadata {
+    category
+    region
+    location { latitude longitude }
+    sourceUrl
+    criteria
+    tags
+  }
+`;
+
+type RawBet = Omit<MicroBet, 'yesPool' | 'noPool'>;
+type RawUserBet = Omit<UserBet, 'side'>;
+
+function toMicroBet(bet: RawBet): MicroBet {
+  return { ...bet, yesPool: bet.pools[0] ?? '0', noPool: bet.pools[1] ?? '0' };
+}
+
+function toUserBet(bet: RawUserBet): UserBet {
+  return { ...bet, side: bet.outcomeIndex === 0 };
+}
+
 // Get the GraphQL endpoint for the application
 function getAppEndpoint(): string {
   return `${LINERA_SERVICE_URL}/chains/${CHAIN_ID}/applications/${APP_ID}`;
@@ -71,17 +144,7 @@ export async function getAllBets(): Promise<MicroBet[]> {
     query {
       bets {
         entries {
-          value {
-            id
-            question
-            yesPool
-            noPool
-            status
-            creator
-            resolution
-            createdAt
-            expiresAt
-          }
+          value {${BET_FIELDS}}
         }
       }
     }
@@ -90,13 +153,13 @@ export async function getAllBets(): Promise<MicroBet[]> {
   interface QueryResult {
     bets: {
       entries: Array<{
-        value: MicroBet;
+        value: RawBet;
       }>;
     };
   }
 
   const data = await graphqlQuery<QueryResult>(query);
-  return data.bets.entries.map(entry => entry.value);
+  return data.bets.entries.map(entry => toMicroBet(entry.value));
 }
 
 // Get a specific bet by ID
@@ -105,17 +168,7 @@ export async function getBetById(betId: string): Promise<MicroBet | null> {
     query GetBet($betId: String!) {
       bets {
         entry(key: $betId) {
-          value {
-            id
-            question
-            yesPool
-            noPool
-            status
-            creator
-            resolution
-            createdAt
-            expiresAt
-          }
+          value {${BET_FIELDS}}
         }
       }
     }
@@ -124,67 +177,176 @@ export async function getBetById(betId: string): Promise<MicroBet | null> {
   interface QueryResult {
     bets: {
       entry: {
-        value: MicroBet | null;
+        value: RawBet | null;
       } | null;
     };
   }
 
   const data = await graphqlQuery<QueryResult>(query, { betId });
-  return data.bets.entry?.value || null;
+  const bet = data.bets.entry?.value;
+  return bet ? toMicroBet(bet) : null;
 }
 
-// Get user bets
-export async function getUserBets(): Promise<UserBet[]> {
+// Browse markets by category, region, tag and status, sorted and paginated by the service
+export async function getMarkets(filter: MarketFilter = {}): Promise<MicroBet[]> {
   const query = `
-    query {
-      userBets {
-        entries {
-          value {
-            betId
-            owner
-            side
-            amount
-            timestamp
-          }
-        }
+    query Markets(
+      $category: String, $region: String, $tag: String, $status: BetStatus,
+      $sortBy: MarketSort, $after: String, $first: Int
+    ) {
+      markets(
+        category: $category, region: $region, tag: $tag, status: $status,
+        sortBy: $sortBy, after: $after, first: $first
+      ) {${BET_FIELDS}}
+    }
+  `;
+
+  interface QueryResult {
+    markets: RawBet[];
+  }
+
+  const data = await graphqlQuery<QueryResult>(query, { ...filter });
+  return data.markets.map(toMicroBet);
+}
+
+// Get the positions of an owner in every bet
+export async function getUserBets(owner: string): Promise<UserBet[]> {
+  const query = `
+    query MyPositions($owner: AccountOwner!) {
+      myPositions(owner: $owner) {
+        betId
+        owner
+        outcomeIndex
+        amount
+        timestamp
       }
     }
   `;
 
   interface QueryResult {
-    userBets: {
-      entries: Array<{
-        value: UserBet;
-      }>;
-    };
+    myPositions: RawUserBet[];
   }
 
-  const data = await graphqlQuery<QueryResult>(query);
-  return data.userBets.entries.map(entry => entry.value);
+  const data = await graphqlQuery<QueryResult>(query, { owner });
+  return data.myPositions.map(toUserBet);
+}
+
+// ============ SUBSCRIPTIONS ============
+
+// Subscribe to a GraphQL request to the application over the node service's WebSocket, using
+// the `graphql-transport-ws` protocol. The node service runs the request again after every new
+// block on the chain and pushes the response whenever it changes. Returns a function closing
+// the subscription.
+function subscribeToApplication<T>(
+  query: string,
+  variables: Record<string, unknown>,
+  onData: (data: T) => void,
+  onError?: (error: Error) => void,
+): () => void {
+  const socket = new WebSocket(`${LINERA_SERVICE_URL.replace(/^http/, 'ws')}/ws`, 'graphql-transport-ws');
+  const subscription = `
+    subscription ApplicationQuery(
+      $chainId: ChainId!, $applicationId: ApplicationId!, $query: String!, $variables: JSON
+    ) {
+      applicationQuery(
+        chainId: $chainId, applicationId: $applicationId, query: $query, variables: $variables
+      )
+    }
+  `;
+
+  socket.onopen = () => socket.send(JSON.stringify({ type: 'connection_init' }));
+  socket.onmessage = (event) => {
+    const message = JSON.parse(event.data);
+    switch (message.type) {
+      case 'connection_ack':
+        socket.send(JSON.stringify({
+          id: '1',
+          type: 'subscribe',
+          payload: {
+            query: subscription,
+            variables: { chainId: CHAIN_ID, applicationId: APP_ID, query, variables },
+          },
+        }));
+        break;
+      case 'ping':
+        socket.send(JSON.stringify({ type: 'pong' }));
+        break;
+      case 'next': {
+        const { data, errors } = message.payload;
+        // The application's own response is nested in the node service's response
+        const response = data?.applicationQuery;
+        const error = errors?.[0] ?? response?.errors?.[0];
+        if (error) {
+          onError?.(new Error(error.message || 'GraphQL error'));
+        } else if (response?.data) {
+          onData(response.data);
+        }
+        break;
+      }
+      case 'error':
+        onError?.(new Error(message.payload?.[0]?.message || 'Subscription error'));
+        break;
+    }
+  };
+  socket.onerror = () => onError?.(new Error('WebSocket connection failed'));
+
+  return () => socket.close();
+}
+
+// Subscribe to the odds, pools, volume and bettors of a market as they change
+export function subscribeToMarketOdds(
+  betId: string,
+  onUpdate: (odds: MarketOdds) => void,
+  onError?: (error: Error) => void,
+): () => void {
+  const query = `
+    subscription MarketOdds($betId: String!) {
+      marketOdds(betId: $betId) {
+        betId
+        status
+        pools
+        odds
+        volume
+        bettors
+      }
+    }
+  `;
+
+  return subscribeToApplication<{ marketOdds: MarketOdds }>(
+    query,
+    { betId },
+    (data) => onUpdate(data.marketOdds),
+    onError,
+  );
 }
 
 // ============ MUTATIONS ============
 
-// Create a new bet/market
-export async function createBet(question: string, durationSeconds: number): Promise<void> {
+// Create a new bet/market, optionally with metadata for browsing
+export async function createBet(
+  question: string,
+  durationSeconds: number,
+  metadata?: Partial<MarketMetadata>,
+): Promise<void> {
   const mutation = `
-    mutation CreateBet($question: String!, $durationSeconds: Int!) {
-      createBet(question: $question, durationSeconds: $durationSeconds)
+    mutation CreateBet($question: String!, $durationSeconds: Int!, $metadata: MarketMetadataInput) {
+      createBet(question: $question, durationSeconds: $durationSeconds, metadata: $metadata)
     }
   `;
 
-  await graphqlMutation(mutation, { question, durationSeconds });
+  await graphqlMutation(mutation, { question, durationSeconds, metadata });
 }
 
-// Place a bet on a market
-export async function placeBet(betId: string, side: boolean, amount: string): Promise<void> {
+// Place a bet on a market; `side` picks YES (outcome 0) or NO (outcome 1)
+export async function placeBet(betId: string, side: boolean | number, amount: string): Promise<void> {
+  const outcomeIndex = typeof side === 'number' ? side : side ? 0 : 1;
   const mutation = `
-    mutation PlaceBet($betId: String!, $side: Boolean!, $amount: String!) {
-      placeBet(betId: $betId, side: $side, amount: $amount)
+    mutation PlaceBet($betId: String!, $outcomeIndex: Int!, $amount: String!) {
+      placeBet(betId: $betId, outcomeIndex: $outcomeIndex, amount: $amount)
     }
   `;
 
-  await graphqlMutation(mutation, { betId, side, amount });
+  await graphqlMutation(mutation, { betId, outcomeIndex, amount });
 }
 
 // Close a bet (stop accepting new bets)
@@ -198,15 +360,17 @@ export async function closeBet(betId: string): Promise<void> {
   await graphqlMutation(mutation, { betId });
 }
 
-// Resolve a bet with outcome
-export async function resolveBet(betId: string, outcome: boolean): Promise<void> {
+// Resolve a bet with outcome; `true`/`false` resolve binary markets to YES/NO
+export async function resolveBet(betId: string, outcome: boolean | Resolution): Promise<void> {
+  const resolution: Resolution =
+    typeof outcome === 'boolean' ? { Outcome: outcome ? 0 : 1 } : outcome;
   const mutation = `
-    mutation ResolveBet($betId: String!, $outcome: Boolean!) {
-      resolveBet(betId: $betId, outcome: $outcome)
+    mutation ResolveBet($betId: String!, $resolution: Resolution!) {
+      resolveBet(betId: $betId, resolution: $resolution)
     }
   `;
 
-  await graphqlMutation(mutation, { betId, outcome });
+  await graphqlMutation(mutation, { betId, resolution });
 }
 
 // ============ HELPERS ============
@@ -6,6 +6,8 @@ use std::path::PathBuf;
 use clap::Parser as _;
 use linera_service::storage::{StorageMigration, StoreConfig};
 use linera_views::{
+    compressing::{CompressingConfig, CompressionConfig},
+    encrypting::EncryptingConfig,
     lru_prefix_cache::StorageCacheConfig,
     rocks_db::{
         PathWithGuard, RocksDbDatabase, RocksDbSpawnMode, RocksDbStoreConfig,
@@ -95,6 +97,13 @@ impl RocksDbRunner {
             path_with_guard,
             max_stream_queries: config.client.max_stream_queries,
         };
+        let inner_config = CompressingConfig {
+            inner_config: EncryptingConfig {
+                inner_config,
+                encryption_config: None,
+            },
+            compression_config: CompressionConfig::default(),
+        };
         let rocksdb_store_config = RocksDbStoreConfig {
             inner_config,
             storage_cache_config,
@@ -3,6 +3,8 @@
 
 use linera_service::storage::{StorageMigration, StoreConfig};
 use linera_views::{
+    compressing::{CompressingConfig, CompressionConfig},
+    encrypting::EncryptingConfig,
     lru_prefix_cache::StorageCacheConfig,
     scylla_db::{ScyllaDbDatabase, ScyllaDbStoreConfig, ScyllaDbStoreInternalConfig},
     store::KeyValueDatabase,
@@ -89,6 +91,13 @@ impl ScyllaDbRunner {
             max_concurrent_queries: config.client.max_concurrent_queries,
             replication_factor: config.client.replication_factor,
         };
+        let inner_config = CompressingConfig {
+            inner_config: EncryptingConfig {
+                inner_config,
+                encryption_config: None,
+            },
+            compression_config: CompressionConfig::default(),
+        };
         let scylladb_store_config = ScyllaDbStoreConfig {
             inner_config,
             storage_cache_config,
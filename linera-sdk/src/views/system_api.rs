@@ -6,6 +6,7 @@
 #[cfg(with_testing)]
 use std::sync::Arc;
 
+use futures::{stream, Stream, TryStreamExt as _};
 use linera_base::ensure;
 use linera_views::{
     batch::Batch,
@@ -187,6 +188,28 @@ impl ReadableKeyValueStore for KeyValueStore {
         yield_once().await;
         Ok(self.wit_api.find_key_values_wait(promise))
     }
+
+    // The system API has no range scans, so the streams read the prefix once rather than
+    // reading it again for every page.
+    fn find_keys_by_prefix_stream(
+        &self,
+        key_prefix: &[u8],
+    ) -> impl Stream<Item = Result<Vec<u8>, KeyValueStoreError>> {
+        let key_prefix = key_prefix.to_vec();
+        stream::once(async move { self.find_keys_by_prefix(&key_prefix).await })
+            .map_ok(|keys| stream::iter(keys.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    fn find_key_values_by_prefix_stream(
+        &self,
+        key_prefix: &[u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), KeyValueStoreError>> {
+        let key_prefix = key_prefix.to_vec();
+        stream::once(async move { self.find_key_values_by_prefix(&key_prefix).await })
+            .map_ok(|key_values| stream::iter(key_values.into_iter().map(Ok)))
+            .try_flatten()
+    }
 }
 
 impl WritableKeyValueStore for KeyValueStore {
@@ -95,6 +95,8 @@ impl StorageConfigProvider {
             InnerStorageConfig::RocksDb { .. } => {
                 anyhow::bail!("Not possible to work with RocksDB")
             }
+            #[cfg(feature = "redb")]
+            InnerStorageConfig::Redb { .. } => anyhow::bail!("Not possible to work with redb"),
             #[cfg(feature = "storage-service")]
             InnerStorageConfig::Service { .. } => Ok(Database::Service),
             #[cfg(feature = "dynamodb")]
@@ -213,8 +213,17 @@ impl Options {
                     inner_storage_config,
                     namespace,
                 })
+            } else if #[cfg(feature = "redb")] {
+                let inner_storage_config = linera_service::storage::InnerStorageConfig::Redb {
+                    path: Self::config_path()?.join("wallet.redb"),
+                };
+                let namespace = linera_storage::DEFAULT_NAMESPACE.to_string();
+                Ok(StorageConfig {
+                    inner_storage_config,
+                    namespace,
+                })
             } else {
-                bail!("Cannot apply default storage because the feature 'rocksdb' was not selected");
+                bail!("Cannot apply default storage because neither the feature 'rocksdb' nor 'redb' was selected");
             }
         }
     }
@@ -97,7 +97,7 @@ async fn make_testing_config(database: Database) -> Result<InnerStorageConfig> {
             {
                 let config = ScyllaDbDatabase::new_test_config().await?;
                 Ok(InnerStorageConfig::ScyllaDb {
-                    uri: config.inner_config.uri,
+                    uri: config.inner_config.inner_config.inner_config.uri,
                 })
             }
             #[cfg(not(feature = "scylladb"))]
@@ -110,9 +110,13 @@ async fn make_testing_config(database: Database) -> Result<InnerStorageConfig> {
                 let scylla_config = ScyllaDbDatabase::new_test_config().await?;
                 let spawn_mode = RocksDbSpawnMode::get_spawn_mode_from_runtime();
                 Ok(InnerStorageConfig::DualRocksDbScyllaDb {
-                    path_with_guard: rocksdb_config.inner_config.path_with_guard,
+                    path_with_guard: rocksdb_config
+                        .inner_config
+                        .inner_config
+                        .inner_config
+                        .path_with_guard,
                     spawn_mode,
-                    uri: scylla_config.inner_config.uri,
+                    uri: scylla_config.inner_config.inner_config.inner_config.uri,
                 })
             }
             #[cfg(not(all(feature = "rocksdb", feature = "scylladb")))]
@@ -9,7 +9,9 @@ use async_graphql::{
 };
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
 use axum::{extract::Path, http::StatusCode, response, response::IntoResponse, Extension, Router};
-use futures::{lock::Mutex, Future, FutureExt as _, TryStreamExt as _};
+use futures::{
+    future, lock::Mutex, stream, Future, FutureExt as _, StreamExt as _, TryStreamExt as _,
+};
 use linera_base::{
     crypto::{CryptoError, CryptoHash},
     data_types::{
@@ -33,7 +35,7 @@ use linera_core::{
     client::{ChainClient, ChainClientError},
     data_types::ClientOutcome,
     wallet::Wallet as _,
-    worker::Notification,
+    worker::{Notification, Reason},
 };
 use linera_execution::{
     committee::Committee, system::AdminOperation, Operation, Query, QueryOutcome, QueryResponse,
@@ -122,6 +124,65 @@ where
             .await?;
         Ok(client.subscribe()?)
     }
+
+    /// Subscribes to the response of a GraphQL request to an application on the specified chain.
+    ///
+    /// The request is executed once immediately, then again after every new block on the chain;
+    /// a response is only sent when it differs from the previous one. Applications may also be
+    /// sent `subscription` operations, which they resolve to the current value of the field.
+    async fn application_query(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        query: String,
+        variables: Option<async_graphql::Json<serde_json::Value>>,
+    ) -> Result<impl Stream<Item = Result<async_graphql::Json<serde_json::Value>, Error>>, Error>
+    {
+        let client = self
+            .context
+            .lock()
+            .await
+            .make_chain_client(chain_id)
+            .await?;
+        let notifications = client.subscribe()?;
+        let request = json!({ "query": query, "variables": variables.map(|json| json.0) });
+        let bytes = serde_json::to_vec(&request)?;
+        let new_blocks = notifications.filter_map(|notification| {
+            future::ready(matches!(notification.reason, Reason::NewBlock { .. }).then_some(()))
+        });
+        let mut last_response = None;
+        Ok(stream::once(future::ready(()))
+            .chain(new_blocks)
+            .then(move |()| {
+                let client = client.clone();
+                let query = Query::User {
+                    application_id,
+                    bytes: bytes.clone(),
+                };
+                async move {
+                    match client.query_application(query, None).await?.response {
+                        QueryResponse::System(_) => {
+                            unreachable!("cannot get a system response for a user query")
+                        }
+                        QueryResponse::User(bytes) => {
+                            let response: serde_json::Value = serde_json::from_slice(&bytes)?;
+                            Ok::<_, NodeServiceError>(response)
+                        }
+                    }
+                }
+            })
+            .filter_map(move |result| {
+                let update = match result {
+                    Ok(response) if last_response.as_ref() == Some(&response) => None,
+                    Ok(response) => {
+                        last_response = Some(response.clone());
+                        Some(Ok(async_graphql::Json(response)))
+                    }
+                    Err(error) => Some(Err(error.into())),
+                };
+                future::ready(update)
+            }))
+    }
 }
 
 impl<C> MutationRoot<C>
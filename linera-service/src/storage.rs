@@ -13,14 +13,19 @@ use linera_storage_service::{
     client::StorageServiceDatabase,
     common::{StorageServiceStoreConfig, StorageServiceStoreInternalConfig},
 };
+#[cfg(any(feature = "rocksdb", feature = "redb"))]
+use linera_views::common::PathWithGuard;
 #[cfg(feature = "dynamodb")]
 use linera_views::dynamo_db::{DynamoDbDatabase, DynamoDbStoreConfig, DynamoDbStoreInternalConfig};
+#[cfg(feature = "redb")]
+use linera_views::redb::{RedbDatabase, RedbStoreConfig, RedbStoreInternalConfig};
 #[cfg(feature = "rocksdb")]
 use linera_views::rocks_db::{
-    PathWithGuard, RocksDbDatabase, RocksDbSpawnMode, RocksDbStoreConfig,
-    RocksDbStoreInternalConfig,
+    RocksDbDatabase, RocksDbSpawnMode, RocksDbStoreConfig, RocksDbStoreInternalConfig,
 };
 use linera_views::{
+    compressing::{CompressingConfig, CompressionAlgorithm, CompressionConfig},
+    encrypting::{EncryptingConfig, EncryptionConfig, EncryptionConfigError},
     lru_prefix_cache::StorageCacheConfig,
     memory::{MemoryDatabase, MemoryStoreConfig},
     store::{KeyValueDatabase, KeyValueStore},
@@ -85,6 +90,15 @@ pub struct CommonStorageOptions {
     /// The replication factor for the keyspace
     #[arg(long, default_value = "1", global = true)]
     pub storage_replication_factor: u32,
+
+    /// The compression of the stored values: `none`, `lz4`, `zstd` or `zstd:LEVEL`.
+    /// Values written without compression remain readable.
+    #[arg(long, default_value = "none", global = true)]
+    pub storage_compression: CompressionAlgorithm,
+
+    /// The minimal size in bytes of the values being compressed.
+    #[arg(long, default_value = "1024", global = true)]
+    pub storage_compression_threshold: usize,
 }
 
 impl CommonStorageOptions {
@@ -100,6 +114,29 @@ impl CommonStorageOptions {
             max_cache_find_key_values_size: self.storage_max_cache_find_key_values_size,
         }
     }
+
+    pub fn storage_compression_config(&self) -> CompressionConfig {
+        CompressionConfig {
+            algorithm: self.storage_compression,
+            threshold: self.storage_compression_threshold,
+        }
+    }
+
+    /// Wraps the configuration of a database with the compression and the encryption of its
+    /// values. The encryption keys are secrets, so they are read from the environment, as in
+    /// [`EncryptionConfig::from_env`], rather than from the command line.
+    pub fn storage_values_config<C>(
+        &self,
+        inner_config: C,
+    ) -> Result<CompressingConfig<EncryptingConfig<C>>, EncryptionConfigError> {
+        Ok(CompressingConfig {
+            inner_config: EncryptingConfig {
+                inner_config,
+                encryption_config: EncryptionConfig::from_env()?,
+            },
+            compression_config: self.storage_compression_config(),
+        })
+    }
 }
 
 /// The configuration of the key value store in use.
@@ -123,6 +160,12 @@ pub enum StoreConfig {
         config: RocksDbStoreConfig,
         namespace: String,
     },
+    /// The redb key value store
+    #[cfg(feature = "redb")]
+    Redb {
+        config: RedbStoreConfig,
+        namespace: String,
+    },
     /// The DynamoDB key value store
     #[cfg(feature = "dynamodb")]
     DynamoDb {
@@ -166,6 +209,12 @@ pub enum InnerStorageConfig {
         /// Whether to use `block_in_place` or `spawn_blocking`.
         spawn_mode: RocksDbSpawnMode,
     },
+    /// The redb description.
+    #[cfg(feature = "redb")]
+    Redb {
+        /// The path used.
+        path: PathBuf,
+    },
     /// The DynamoDB description.
     #[cfg(feature = "dynamodb")]
     DynamoDb {
@@ -204,6 +253,8 @@ const MEMORY: &str = "memory:";
 const STORAGE_SERVICE: &str = "service:";
 #[cfg(feature = "rocksdb")]
 const ROCKS_DB: &str = "rocksdb:";
+#[cfg(feature = "redb")]
+const REDB: &str = "redb:";
 #[cfg(feature = "dynamodb")]
 const DYNAMO_DB: &str = "dynamodb:";
 #[cfg(feature = "scylladb")]
@@ -304,6 +355,26 @@ example service:tcp:127.0.0.1:7878:table_do_my_test"
             }
             bail!("We should have one, two or three parts");
         }
+        #[cfg(feature = "redb")]
+        if let Some(s) = input.strip_prefix(REDB) {
+            let parts = s.split(':').collect::<Vec<_>>();
+            if parts[0].is_empty() || parts.len() > 2 {
+                bail!(
+                    "For redb, the formatting has to be redb:directory or redb:directory:namespace"
+                );
+            }
+            let path = parts[0].to_string().into();
+            let namespace = parts
+                .get(1)
+                .copied()
+                .unwrap_or(DEFAULT_NAMESPACE)
+                .to_string();
+            let inner_storage_config = InnerStorageConfig::Redb { path };
+            return Ok(StorageConfig {
+                inner_storage_config,
+                namespace,
+            });
+        }
         #[cfg(feature = "dynamodb")]
         if let Some(s) = input.strip_prefix(DYNAMO_DB) {
             let mut parts = s.splitn(2, ':');
@@ -420,6 +491,8 @@ example service:tcp:127.0.0.1:7878:table_do_my_test"
         error!("Also available is linera-storage-service");
         #[cfg(feature = "rocksdb")]
         error!("Also available is RocksDB");
+        #[cfg(feature = "redb")]
+        error!("Also available is redb");
         #[cfg(feature = "dynamodb")]
         error!("Also available is DynamoDB");
         #[cfg(feature = "scylladb")]
@@ -488,11 +561,23 @@ impl StorageConfig {
                     max_stream_queries: options.storage_max_stream_queries,
                 };
                 let config = RocksDbStoreConfig {
-                    inner_config,
+                    inner_config: options.storage_values_config(inner_config)?,
                     storage_cache_config: options.storage_cache_config(),
                 };
                 Ok(StoreConfig::RocksDb { config, namespace })
             }
+            #[cfg(feature = "redb")]
+            InnerStorageConfig::Redb { path } => {
+                let inner_config = RedbStoreInternalConfig {
+                    path_with_guard: PathWithGuard::new(path.to_path_buf()),
+                    max_stream_queries: options.storage_max_stream_queries,
+                };
+                let config = RedbStoreConfig {
+                    inner_config: options.storage_values_config(inner_config)?,
+                    storage_cache_config: options.storage_cache_config(),
+                };
+                Ok(StoreConfig::Redb { config, namespace })
+            }
             #[cfg(feature = "dynamodb")]
             InnerStorageConfig::DynamoDb { use_dynamodb_local } => {
                 let inner_config = DynamoDbStoreInternalConfig {
@@ -501,7 +586,7 @@ impl StorageConfig {
                     max_stream_queries: options.storage_max_stream_queries,
                 };
                 let config = DynamoDbStoreConfig {
-                    inner_config,
+                    inner_config: options.storage_values_config(inner_config)?,
                     storage_cache_config: options.storage_cache_config(),
                 };
                 Ok(StoreConfig::DynamoDb { config, namespace })
@@ -515,7 +600,7 @@ impl StorageConfig {
                     replication_factor: options.storage_replication_factor,
                 };
                 let config = ScyllaDbStoreConfig {
-                    inner_config,
+                    inner_config: options.storage_values_config(inner_config)?,
                     storage_cache_config: options.storage_cache_config(),
                 };
                 Ok(StoreConfig::ScyllaDb { config, namespace })
@@ -532,7 +617,7 @@ impl StorageConfig {
                     max_stream_queries: options.storage_max_stream_queries,
                 };
                 let first_config = RocksDbStoreConfig {
-                    inner_config,
+                    inner_config: options.storage_values_config(inner_config)?,
                     storage_cache_config: options.storage_cache_config(),
                 };
 
@@ -543,7 +628,7 @@ impl StorageConfig {
                     replication_factor: options.storage_replication_factor,
                 };
                 let second_config = ScyllaDbStoreConfig {
-                    inner_config,
+                    inner_config: options.storage_values_config(inner_config)?,
                     storage_cache_config: options.storage_cache_config(),
                 };
 
@@ -573,6 +658,10 @@ impl fmt::Display for StorageConfig {
                 let spawn_mode = spawn_mode.to_string();
                 write!(f, "rocksdb:{}:{}:{}", path.display(), spawn_mode, namespace)
             }
+            #[cfg(feature = "redb")]
+            InnerStorageConfig::Redb { path } => {
+                write!(f, "redb:{}:{}", path.display(), namespace)
+            }
             #[cfg(feature = "dynamodb")]
             InnerStorageConfig::DynamoDb { use_dynamodb_local } => match use_dynamodb_local {
                 true => write!(f, "dynamodb:{}:dynamodb_local", namespace),
@@ -672,6 +761,14 @@ impl StoreConfig {
                         .with_allow_application_logs(allow_application_logs);
                 Ok(job.run(storage).await)
             }
+            #[cfg(feature = "redb")]
+            StoreConfig::Redb { config, namespace } => {
+                let storage =
+                    DbStorage::<RedbDatabase, _>::connect(&config, &namespace, wasm_runtime)
+                        .await?
+                        .with_allow_application_logs(allow_application_logs);
+                Ok(job.run(storage).await)
+            }
             #[cfg(feature = "dynamodb")]
             StoreConfig::DynamoDb { config, namespace } => {
                 let storage =
@@ -718,6 +815,10 @@ impl StoreConfig {
             StoreConfig::RocksDb { config, namespace } => {
                 Ok(job.run::<RocksDbDatabase>(config, namespace).await?)
             }
+            #[cfg(feature = "redb")]
+            StoreConfig::Redb { config, namespace } => {
+                Ok(job.run::<RedbDatabase>(config, namespace).await?)
+            }
             #[cfg(feature = "dynamodb")]
             StoreConfig::DynamoDb { config, namespace } => {
                 Ok(job.run::<DynamoDbDatabase>(config, namespace).await?)
@@ -891,6 +992,37 @@ fn test_rocks_db_storage_config_from_str() {
     );
 }
 
+#[cfg(feature = "redb")]
+#[test]
+fn test_redb_storage_config_from_str() {
+    assert!(StorageConfig::from_str("redb").is_err());
+    assert!(StorageConfig::from_str("redb:").is_err());
+    assert!(StorageConfig::from_str("redb:foo.db:namespace:extra").is_err());
+    assert_eq!(
+        StorageConfig::from_str("redb:foo.db").unwrap(),
+        StorageConfig {
+            inner_storage_config: InnerStorageConfig::Redb {
+                path: "foo.db".into(),
+            },
+            namespace: DEFAULT_NAMESPACE.to_string()
+        }
+    );
+    let config = StorageConfig::from_str("redb:foo.db:chosen_namespace").unwrap();
+    assert_eq!(
+        config,
+        StorageConfig {
+            inner_storage_config: InnerStorageConfig::Redb {
+                path: "foo.db".into(),
+            },
+            namespace: "chosen_namespace".into()
+        }
+    );
+    assert_eq!(
+        StorageConfig::from_str(&config.to_string()).unwrap(),
+        config
+    );
+}
+
 #[cfg(feature = "dynamodb")]
 #[test]
 fn test_aws_storage_config_from_str() {
@@ -3,6 +3,7 @@
 
 use std::{
     mem,
+    ops::Bound,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -19,7 +20,7 @@ use linera_views::store::TestKeyValueDatabase;
 use linera_views::{
     batch::{Batch, WriteOperation},
     lru_caching::LruCachingDatabase,
-    store::{KeyValueDatabase, ReadableKeyValueStore, WithError, WritableKeyValueStore},
+    store::{KeyRange, KeyValueDatabase, ReadableKeyValueStore, WithError, WritableKeyValueStore},
 };
 use serde::de::DeserializeOwned;
 use tonic::transport::{Channel, Endpoint};
@@ -31,14 +32,15 @@ use crate::{
         KeyPrefix, StorageServiceStoreError, StorageServiceStoreInternalConfig, MAX_PAYLOAD_SIZE,
     },
     key_value_store::{
-        statement::Operation, storage_service_client::StorageServiceClient, KeyValue,
-        KeyValueAppend, ReplyContainsKey, ReplyContainsKeys, ReplyExistsNamespace,
-        ReplyFindKeyValuesByPrefix, ReplyFindKeysByPrefix, ReplyListAll, ReplyListRootKeys,
-        ReplyReadMultiValues, ReplyReadValue, ReplySpecificChunk, RequestContainsKey,
-        RequestContainsKeys, RequestCreateNamespace, RequestDeleteNamespace,
-        RequestExistsNamespace, RequestFindKeyValuesByPrefix, RequestFindKeysByPrefix,
-        RequestListRootKeys, RequestReadMultiValues, RequestReadValue, RequestSpecificChunk,
-        RequestWriteBatchExtended, Statement,
+        self, key_bound, statement::Operation, storage_service_client::StorageServiceClient,
+        KeyBound, KeyValue, KeyValueAppend, ReplyContainsKey, ReplyContainsKeys,
+        ReplyExistsNamespace, ReplyFindKeyValuesByPrefix, ReplyFindKeysByPrefix, ReplyListAll,
+        ReplyListRootKeys, ReplyReadMultiValues, ReplyReadValue, ReplySpecificChunk,
+        RequestContainsKey, RequestContainsKeys, RequestCreateNamespace, RequestDeleteNamespace,
+        RequestExistsNamespace, RequestFindKeyValuesByPrefix, RequestFindKeyValuesByPrefixInRange,
+        RequestFindKeysByPrefix, RequestFindKeysByPrefixInRange, RequestListRootKeys,
+        RequestReadMultiValues, RequestReadValue, RequestSpecificChunk, RequestWriteBatchExtended,
+        Statement,
     },
 };
 
@@ -278,6 +280,106 @@ impl ReadableKeyValueStore for StorageServiceStoreInternal {
             self.read_entries(message_index, num_chunks).await
         }
     }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, StorageServiceStoreError> {
+        ensure!(
+            key_prefix.len() <= MAX_KEY_SIZE,
+            StorageServiceStoreError::KeyTooLong
+        );
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut full_key_prefix = self.start_key.clone();
+        full_key_prefix.extend(key_prefix);
+        let query = RequestFindKeysByPrefixInRange {
+            key_prefix: full_key_prefix,
+            range: Some(to_proto_range(range)),
+        };
+        let request = tonic::Request::new(query);
+        let channel = self.channel.clone();
+        let mut client = StorageServiceClient::new(channel);
+        let _guard = self.acquire().await;
+        let response = client
+            .process_find_keys_by_prefix_in_range(request)
+            .make_sync()
+            .await?;
+        let response = response.into_inner();
+        let ReplyFindKeysByPrefix {
+            keys,
+            message_index,
+            num_chunks,
+        } = response;
+        if num_chunks == 0 {
+            Ok(keys)
+        } else {
+            self.read_entries(message_index, num_chunks).await
+        }
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StorageServiceStoreError> {
+        ensure!(
+            key_prefix.len() <= MAX_KEY_SIZE,
+            StorageServiceStoreError::KeyTooLong
+        );
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut full_key_prefix = self.start_key.clone();
+        full_key_prefix.extend(key_prefix);
+        let query = RequestFindKeyValuesByPrefixInRange {
+            key_prefix: full_key_prefix,
+            range: Some(to_proto_range(range)),
+        };
+        let request = tonic::Request::new(query);
+        let channel = self.channel.clone();
+        let mut client = StorageServiceClient::new(channel);
+        let _guard = self.acquire().await;
+        let response = client
+            .process_find_key_values_by_prefix_in_range(request)
+            .make_sync()
+            .await?;
+        let response = response.into_inner();
+        let ReplyFindKeyValuesByPrefix {
+            key_values,
+            message_index,
+            num_chunks,
+        } = response;
+        if num_chunks == 0 {
+            let key_values = key_values
+                .into_iter()
+                .map(|x| (x.key, x.value))
+                .collect::<Vec<_>>();
+            Ok(key_values)
+        } else {
+            self.read_entries(message_index, num_chunks).await
+        }
+    }
+}
+
+/// Converts a range to its form in the requests.
+fn to_proto_range(range: &KeyRange) -> key_value_store::KeyRange {
+    let bound = |bound: &Bound<Vec<u8>>| {
+        let bound = match bound {
+            Bound::Included(key) => key_bound::Bound::Included(key.clone()),
+            Bound::Excluded(key) => key_bound::Bound::Excluded(key.clone()),
+            Bound::Unbounded => return None,
+        };
+        Some(KeyBound { bound: Some(bound) })
+    };
+    key_value_store::KeyRange {
+        start: bound(&range.start),
+        end: bound(&range.end),
+        reverse: range.reverse,
+        limit: range.limit.map(|limit| limit as u64),
+    }
 }
 
 impl WritableKeyValueStore for StorageServiceStoreInternal {
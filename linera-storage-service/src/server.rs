@@ -1,17 +1,19 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, ops::Bound, sync::Arc};
 
 use async_lock::RwLock;
 use linera_storage_service::common::{KeyPrefix, MAX_PAYLOAD_SIZE};
 use linera_views::{
     batch::Batch,
     memory::{MemoryDatabase, MemoryStoreConfig},
-    store::{KeyValueDatabase, ReadableKeyValueStore, WritableKeyValueStore},
+    store::{KeyRange, KeyValueDatabase, ReadableKeyValueStore, WritableKeyValueStore},
 };
 #[cfg(with_rocksdb)]
 use linera_views::{
+    compressing::{CompressingConfig, CompressionConfig},
+    encrypting::EncryptingConfig,
     lru_prefix_cache::StorageCacheConfig,
     rocks_db::{
         PathWithGuard, RocksDbDatabase, RocksDbSpawnMode, RocksDbStoreConfig,
@@ -24,14 +26,16 @@ use tracing::{info, instrument};
 use tracing_subscriber::fmt::format::FmtSpan;
 
 use crate::key_value_store::{
+    key_bound,
     statement::Operation,
     storage_service_server::{StorageService, StorageServiceServer},
-    KeyValue, OptValue, ReplyContainsKey, ReplyContainsKeys, ReplyExistsNamespace,
+    KeyBound, KeyValue, OptValue, ReplyContainsKey, ReplyContainsKeys, ReplyExistsNamespace,
     ReplyFindKeyValuesByPrefix, ReplyFindKeysByPrefix, ReplyListAll, ReplyListRootKeys,
     ReplyReadMultiValues, ReplyReadValue, ReplySpecificChunk, RequestContainsKey,
     RequestContainsKeys, RequestCreateNamespace, RequestDeleteNamespace, RequestExistsNamespace,
-    RequestFindKeyValuesByPrefix, RequestFindKeysByPrefix, RequestListRootKeys,
-    RequestReadMultiValues, RequestReadValue, RequestSpecificChunk, RequestWriteBatchExtended,
+    RequestFindKeyValuesByPrefix, RequestFindKeyValuesByPrefixInRange, RequestFindKeysByPrefix,
+    RequestFindKeysByPrefixInRange, RequestListRootKeys, RequestReadMultiValues, RequestReadValue,
+    RequestSpecificChunk, RequestWriteBatchExtended,
 };
 
 pub mod key_value_store {
@@ -164,6 +168,62 @@ impl StorageServer {
         }
     }
 
+    pub async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, Status> {
+        match &self.store {
+            LocalStore::Memory(store) => store
+                .find_keys_by_prefix_in_range(key_prefix, range)
+                .await
+                .map_err(|e| {
+                    Status::unknown(format!(
+                        "Memory error {:?} at find_keys_by_prefix_in_range",
+                        e
+                    ))
+                }),
+            #[cfg(with_rocksdb)]
+            LocalStore::RocksDb(store) => store
+                .find_keys_by_prefix_in_range(key_prefix, range)
+                .await
+                .map_err(|e| {
+                    Status::unknown(format!(
+                        "RocksDB error {:?} at find_keys_by_prefix_in_range",
+                        e
+                    ))
+                }),
+        }
+    }
+
+    pub async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Status> {
+        match &self.store {
+            LocalStore::Memory(store) => store
+                .find_key_values_by_prefix_in_range(key_prefix, range)
+                .await
+                .map_err(|e| {
+                    Status::unknown(format!(
+                        "Memory error {:?} at find_key_values_by_prefix_in_range",
+                        e
+                    ))
+                }),
+            #[cfg(with_rocksdb)]
+            LocalStore::RocksDb(store) => store
+                .find_key_values_by_prefix_in_range(key_prefix, range)
+                .await
+                .map_err(|e| {
+                    Status::unknown(format!(
+                        "RocksDB error {:?} at find_key_values_by_prefix_in_range",
+                        e
+                    ))
+                }),
+        }
+    }
+
     pub async fn write_batch(&self, batch: Batch) -> Result<(), Status> {
         match &self.store {
             LocalStore::Memory(store) => store
@@ -242,6 +302,76 @@ impl StorageServer {
         pending_big_reads.big_reads.insert(message_index, big_read);
         (message_index, num_chunks)
     }
+
+    async fn reply_find_keys(&self, keys: Vec<Vec<u8>>) -> ReplyFindKeysByPrefix {
+        let size = keys.iter().map(|x| x.len()).sum::<usize>();
+        if size < MAX_PAYLOAD_SIZE {
+            ReplyFindKeysByPrefix {
+                keys,
+                message_index: 0,
+                num_chunks: 0,
+            }
+        } else {
+            let (message_index, num_chunks) = self.insert_pending_read(keys).await;
+            ReplyFindKeysByPrefix {
+                keys: Vec::default(),
+                message_index,
+                num_chunks,
+            }
+        }
+    }
+
+    async fn reply_find_key_values(
+        &self,
+        key_values: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> ReplyFindKeyValuesByPrefix {
+        let size = key_values
+            .iter()
+            .map(|x| x.0.len() + x.1.len())
+            .sum::<usize>();
+        if size < MAX_PAYLOAD_SIZE {
+            let key_values = key_values
+                .into_iter()
+                .map(|x| KeyValue {
+                    key: x.0,
+                    value: x.1,
+                })
+                .collect::<Vec<_>>();
+            ReplyFindKeyValuesByPrefix {
+                key_values,
+                message_index: 0,
+                num_chunks: 0,
+            }
+        } else {
+            let (message_index, num_chunks) = self.insert_pending_read(key_values).await;
+            ReplyFindKeyValuesByPrefix {
+                key_values: Vec::default(),
+                message_index,
+                num_chunks,
+            }
+        }
+    }
+}
+
+/// Converts the range of a request, where a missing bound is unbounded.
+fn key_range(range: Option<crate::key_value_store::KeyRange>) -> Result<KeyRange, Status> {
+    let range = range.unwrap_or_default();
+    let bound = |bound: Option<KeyBound>| match bound.and_then(|bound| bound.bound) {
+        None => Bound::Unbounded,
+        Some(key_bound::Bound::Included(key)) => Bound::Included(key),
+        Some(key_bound::Bound::Excluded(key)) => Bound::Excluded(key),
+    };
+    let limit = range
+        .limit
+        .map(usize::try_from)
+        .transpose()
+        .map_err(|_| Status::invalid_argument("The limit of the range is too large"))?;
+    Ok(KeyRange {
+        start: bound(range.start),
+        end: bound(range.end),
+        reverse: range.reverse,
+        limit,
+    })
 }
 
 #[derive(clap::Parser)]
@@ -405,22 +535,21 @@ impl StorageService for StorageServer {
         let request = request.into_inner();
         let RequestFindKeysByPrefix { key_prefix } = request;
         let keys = self.find_keys_by_prefix(&key_prefix).await?;
-        let size = keys.iter().map(|x| x.len()).sum::<usize>();
-        let response = if size < MAX_PAYLOAD_SIZE {
-            ReplyFindKeysByPrefix {
-                keys,
-                message_index: 0,
-                num_chunks: 0,
-            }
-        } else {
-            let (message_index, num_chunks) = self.insert_pending_read(keys).await;
-            ReplyFindKeysByPrefix {
-                keys: Vec::default(),
-                message_index,
-                num_chunks,
-            }
-        };
-        Ok(Response::new(response))
+        Ok(Response::new(self.reply_find_keys(keys).await))
+    }
+
+    #[instrument(target = "store_server", skip_all, err, fields(key_prefix_len = ?request.get_ref().key_prefix.len()))]
+    async fn process_find_keys_by_prefix_in_range(
+        &self,
+        request: Request<RequestFindKeysByPrefixInRange>,
+    ) -> Result<Response<ReplyFindKeysByPrefix>, Status> {
+        let request = request.into_inner();
+        let RequestFindKeysByPrefixInRange { key_prefix, range } = request;
+        let range = key_range(range)?;
+        let keys = self
+            .find_keys_by_prefix_in_range(&key_prefix, &range)
+            .await?;
+        Ok(Response::new(self.reply_find_keys(keys).await))
     }
 
     #[instrument(target = "store_server", skip_all, err, fields(key_prefix_len = ?request.get_ref().key_prefix.len()))]
@@ -431,32 +560,21 @@ impl StorageService for StorageServer {
         let request = request.into_inner();
         let RequestFindKeyValuesByPrefix { key_prefix } = request;
         let key_values = self.find_key_values_by_prefix(&key_prefix).await?;
-        let size = key_values
-            .iter()
-            .map(|x| x.0.len() + x.1.len())
-            .sum::<usize>();
-        let response = if size < MAX_PAYLOAD_SIZE {
-            let key_values = key_values
-                .into_iter()
-                .map(|x| KeyValue {
-                    key: x.0,
-                    value: x.1,
-                })
-                .collect::<Vec<_>>();
-            ReplyFindKeyValuesByPrefix {
-                key_values,
-                message_index: 0,
-                num_chunks: 0,
-            }
-        } else {
-            let (message_index, num_chunks) = self.insert_pending_read(key_values).await;
-            ReplyFindKeyValuesByPrefix {
-                key_values: Vec::default(),
-                message_index,
-                num_chunks,
-            }
-        };
-        Ok(Response::new(response))
+        Ok(Response::new(self.reply_find_key_values(key_values).await))
+    }
+
+    #[instrument(target = "store_server", skip_all, err, fields(key_prefix_len = ?request.get_ref().key_prefix.len()))]
+    async fn process_find_key_values_by_prefix_in_range(
+        &self,
+        request: Request<RequestFindKeyValuesByPrefixInRange>,
+    ) -> Result<Response<ReplyFindKeyValuesByPrefix>, Status> {
+        let request = request.into_inner();
+        let RequestFindKeyValuesByPrefixInRange { key_prefix, range } = request;
+        let range = key_range(range)?;
+        let key_values = self
+            .find_key_values_by_prefix_in_range(&key_prefix, &range)
+            .await?;
+        Ok(Response::new(self.reply_find_key_values(key_values).await))
     }
 
     #[instrument(target = "store_server", skip_all, err, fields(n_statements = ?request.get_ref().statements.len()))]
@@ -688,6 +806,13 @@ async fn main() {
                 max_cache_find_keys_size,
                 max_cache_find_key_values_size,
             };
+            let inner_config = CompressingConfig {
+                inner_config: EncryptingConfig {
+                    inner_config,
+                    encryption_config: None,
+                },
+                compression_config: CompressionConfig::default(),
+            };
             let config = RocksDbStoreConfig {
                 inner_config,
                 storage_cache_config,
@@ -9,6 +9,7 @@ fn main() {
         with_wasmtime: { all(not(target_arch = "wasm32"), feature = "wasmtime") },
         with_wasm_runtime: { any(with_wasmer, with_wasmtime) },
         with_rocksdb: { all(not(target_arch = "wasm32"), feature = "rocksdb") },
+        with_redb: { all(not(target_arch = "wasm32"), feature = "redb") },
         with_scylladb: { all(not(target_arch = "wasm32"), feature = "scylladb") },
         with_revm: { feature = "revm" },
         web: { all(target_arch = "wasm32", feature = "web") },
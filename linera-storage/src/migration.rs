@@ -220,6 +220,8 @@ mod tests {
         crypto::CryptoHash,
         identifiers::{BlobId, BlobType, ChainId, EventId, StreamId, StreamName},
     };
+    #[cfg(feature = "redb")]
+    use linera_views::redb::RedbDatabase;
     #[cfg(feature = "rocksdb")]
     use linera_views::rocks_db::RocksDbDatabase;
     #[cfg(feature = "scylladb")]
@@ -594,6 +596,7 @@ mod tests {
 
     #[test_case(PhantomData::<MemoryDatabase>; "MemoryDatabase")]
     #[cfg_attr(with_rocksdb, test_case(PhantomData::<RocksDbDatabase>; "RocksDbDatabase"))]
+    #[cfg_attr(with_redb, test_case(PhantomData::<RedbDatabase>; "RedbDatabase"))]
     #[cfg_attr(with_scylladb, test_case(PhantomData::<ScyllaDbDatabase>; "ScyllaDbDatabase"))]
     #[tokio::test]
     async fn test_storage_migration_cases<D>(_storage_type: PhantomData<D>) -> Result<(), ViewError>
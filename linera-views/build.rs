@@ -9,7 +9,10 @@ fn main() {
         with_dynamodb: { all(not(target_arch = "wasm32"), feature = "dynamodb") },
         with_indexeddb: { all(web, feature = "indexeddb") },
         with_rocksdb: { all(not(target_arch = "wasm32"), feature = "rocksdb") },
+        with_redb: { all(not(target_arch = "wasm32"), feature = "redb") },
         with_scylladb: { all(not(target_arch = "wasm32"), feature = "scylladb") },
+        with_compression: { not(target_arch = "wasm32") },
+        with_encryption: { not(target_arch = "wasm32") },
         with_graphql: { not(web) },
     };
 }
@@ -0,0 +1,566 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Adds transparent compression of the values to a given store.
+//!
+//! Values of at least [`CompressionConfig::threshold`] bytes are compressed, and stored
+//! after a header made of [`MAGIC`], a version byte and an algorithm byte whenever this
+//! makes them smaller. Other values are stored as they are, so that the values written
+//! before compression was enabled remain readable. A new value that happens to start with
+//! [`MAGIC`] is written with a header, so that it cannot be mistaken for a compressed one.
+//! A value written without this layer and starting with [`MAGIC`] is read as it is unless it
+//! is followed by a known version and algorithm, in which case it would be misread.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[cfg(with_testing)]
+use crate::store::TestKeyValueDatabase;
+use crate::{
+    batch::{Batch, WriteOperation},
+    store::{
+        KeyRange, KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore, WithError,
+        WritableKeyValueStore,
+    },
+};
+
+/// The first bytes of a value stored with a compression header.
+pub const MAGIC: [u8; 4] = [0xc0, 0x3b, 0x5a, 0x17];
+
+/// The current version of the compression header.
+const VERSION: u8 = 1;
+
+/// The size of the compression header.
+const HEADER_SIZE: usize = MAGIC.len() + 2;
+
+/// The algorithm bytes of the header.
+const RAW_TAG: u8 = 0;
+const LZ4_TAG: u8 = 1;
+const ZSTD_TAG: u8 = 2;
+
+/// The default minimal size of the values being compressed.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+
+/// The compression algorithm applied to the new values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompressionAlgorithm {
+    /// The values are stored without compression.
+    #[default]
+    None,
+    /// The values are compressed with LZ4, which is fast but compresses less.
+    Lz4,
+    /// The values are compressed with Zstandard at the given level.
+    Zstd {
+        /// The compression level, from 1 to 22.
+        level: i32,
+    },
+}
+
+impl CompressionAlgorithm {
+    fn label(&self) -> &'static str {
+        match self {
+            CompressionAlgorithm::None => "none",
+            CompressionAlgorithm::Lz4 => "lz4",
+            CompressionAlgorithm::Zstd { .. } => "zstd",
+        }
+    }
+}
+
+impl fmt::Display for CompressionAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressionAlgorithm::Zstd { level } => write!(f, "zstd:{level}"),
+            algorithm => write!(f, "{}", algorithm.label()),
+        }
+    }
+}
+
+impl FromStr for CompressionAlgorithm {
+    type Err = String;
+
+    /// Parses `none`, `lz4`, `zstd` or `zstd:LEVEL`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.split_once(':') {
+            None if input == "none" => Ok(CompressionAlgorithm::None),
+            None if input == "lz4" => Ok(CompressionAlgorithm::Lz4),
+            None if input == "zstd" => Ok(CompressionAlgorithm::Zstd {
+                level: zstd::DEFAULT_COMPRESSION_LEVEL,
+            }),
+            Some(("zstd", level)) => {
+                let level = level
+                    .parse()
+                    .map_err(|_| format!("Invalid zstd compression level {level:?}"))?;
+                Ok(CompressionAlgorithm::Zstd { level })
+            }
+            _ => Err(format!(
+                "Invalid compression algorithm {input:?}, expected none, lz4, zstd or zstd:LEVEL"
+            )),
+        }
+    }
+}
+
+/// The compression settings of a [`CompressingDatabase`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionConfig {
+    /// The algorithm used for the new values.
+    pub algorithm: CompressionAlgorithm,
+    /// The values smaller than this number of bytes are stored uncompressed.
+    pub threshold: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: CompressionAlgorithm::None,
+            threshold: DEFAULT_COMPRESSION_THRESHOLD,
+        }
+    }
+}
+
+/// The configuration type for the `CompressingDatabase`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressingConfig<C> {
+    /// The inner configuration of the `CompressingDatabase`.
+    pub inner_config: C,
+    /// The compression settings.
+    pub compression_config: CompressionConfig,
+}
+
+/// A key-value database compressing the values of the underlying database.
+#[derive(Clone)]
+pub struct CompressingDatabase<D> {
+    /// The underlying database.
+    database: D,
+    /// The compression settings.
+    config: CompressionConfig,
+}
+
+/// A key-value store compressing the values of the underlying store.
+#[derive(Clone)]
+pub struct CompressingStore<S> {
+    /// The underlying store.
+    store: S,
+    /// The compression settings.
+    config: CompressionConfig,
+}
+
+/// The composed error type built from the inner error type.
+#[derive(Error, Debug)]
+pub enum CompressingError<E> {
+    /// inner store error
+    #[error(transparent)]
+    InnerStoreError(#[from] E),
+
+    /// The value could not be compressed or decompressed
+    #[error("compression error: {0}")]
+    Compression(String),
+}
+
+impl<E: KeyValueStoreError> From<bcs::Error> for CompressingError<E> {
+    fn from(error: bcs::Error) -> Self {
+        let error = E::from(error);
+        CompressingError::InnerStoreError(error)
+    }
+}
+
+impl<E: KeyValueStoreError + 'static> KeyValueStoreError for CompressingError<E> {
+    const BACKEND: &'static str = "compressing";
+}
+
+impl<D> WithError for CompressingDatabase<D>
+where
+    D: WithError,
+    D::Error: 'static,
+{
+    type Error = CompressingError<D::Error>;
+}
+
+impl<S> WithError for CompressingStore<S>
+where
+    S: WithError,
+    S::Error: 'static,
+{
+    type Error = CompressingError<S::Error>;
+}
+
+impl<S> ReadableKeyValueStore for CompressingStore<S>
+where
+    S: ReadableKeyValueStore,
+    S::Error: 'static,
+{
+    const MAX_KEY_SIZE: usize = S::MAX_KEY_SIZE;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    fn root_key(&self) -> Result<Vec<u8>, Self::Error> {
+        Ok(self.store.root_key()?)
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        let value = self.store.read_value_bytes(key).await?;
+        value.map(decompress).transpose()
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
+        Ok(self.store.contains_key(key).await?)
+    }
+
+    async fn contains_keys(&self, keys: &[Vec<u8>]) -> Result<Vec<bool>, Self::Error> {
+        Ok(self.store.contains_keys(keys).await?)
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: &[Vec<u8>],
+    ) -> Result<Vec<Option<Vec<u8>>>, Self::Error> {
+        let values = self.store.read_multi_values_bytes(keys).await?;
+        values
+            .into_iter()
+            .map(|value| value.map(decompress).transpose())
+            .collect()
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+        Ok(self.store.find_keys_by_prefix(key_prefix).await?)
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+        let key_values = self.store.find_key_values_by_prefix(key_prefix).await?;
+        decompress_key_values(key_values)
+    }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        Ok(self
+            .store
+            .find_keys_by_prefix_in_range(key_prefix, range)
+            .await?)
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+        let key_values = self
+            .store
+            .find_key_values_by_prefix_in_range(key_prefix, range)
+            .await?;
+        decompress_key_values(key_values)
+    }
+}
+
+impl<S> WritableKeyValueStore for CompressingStore<S>
+where
+    S: WritableKeyValueStore,
+    S::Error: 'static,
+{
+    // A value that cannot be compressed may still need a header.
+    const MAX_VALUE_SIZE: usize = S::MAX_VALUE_SIZE.saturating_sub(HEADER_SIZE);
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error> {
+        let mut batch_new = Batch::new();
+        for operation in batch.operations {
+            match operation {
+                WriteOperation::Put { key, value } => {
+                    let value = compress(&self.config, value)?;
+                    batch_new.put_key_value_bytes(key, value);
+                }
+                operation => batch_new.operations.push(operation),
+            }
+        }
+        Ok(self.store.write_batch(batch_new).await?)
+    }
+
+    async fn clear_journal(&self) -> Result<(), Self::Error> {
+        Ok(self.store.clear_journal().await?)
+    }
+}
+
+impl<D> KeyValueDatabase for CompressingDatabase<D>
+where
+    D: KeyValueDatabase,
+    D::Error: 'static,
+{
+    type Config = CompressingConfig<D::Config>;
+
+    type Store = CompressingStore<D::Store>;
+
+    // The compression is transparent, so the metrics keep the name of the inner database.
+    fn get_name() -> String {
+        D::get_name()
+    }
+
+    async fn connect(config: &Self::Config, namespace: &str) -> Result<Self, Self::Error> {
+        let database = D::connect(&config.inner_config, namespace).await?;
+        Ok(CompressingDatabase {
+            database,
+            config: config.compression_config,
+        })
+    }
+
+    fn open_shared(&self, root_key: &[u8]) -> Result<Self::Store, Self::Error> {
+        let store = self.database.open_shared(root_key)?;
+        Ok(CompressingStore::new(store, self.config))
+    }
+
+    fn open_exclusive(&self, root_key: &[u8]) -> Result<Self::Store, Self::Error> {
+        let store = self.database.open_exclusive(root_key)?;
+        Ok(CompressingStore::new(store, self.config))
+    }
+
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, Self::Error> {
+        Ok(D::list_all(&config.inner_config).await?)
+    }
+
+    async fn list_root_keys(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        Ok(self.database.list_root_keys().await?)
+    }
+
+    async fn delete_all(config: &Self::Config) -> Result<(), Self::Error> {
+        Ok(D::delete_all(&config.inner_config).await?)
+    }
+
+    async fn exists(config: &Self::Config, namespace: &str) -> Result<bool, Self::Error> {
+        Ok(D::exists(&config.inner_config, namespace).await?)
+    }
+
+    async fn create(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        Ok(D::create(&config.inner_config, namespace).await?)
+    }
+
+    async fn delete(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        Ok(D::delete(&config.inner_config, namespace).await?)
+    }
+}
+
+#[cfg(with_testing)]
+impl<D> TestKeyValueDatabase for CompressingDatabase<D>
+where
+    D: TestKeyValueDatabase,
+    D::Error: 'static,
+{
+    // The tests compress most values, in order to exercise the compressed format.
+    async fn new_test_config() -> Result<CompressingConfig<D::Config>, Self::Error> {
+        let inner_config = D::new_test_config().await?;
+        let compression_config = CompressionConfig {
+            algorithm: CompressionAlgorithm::Lz4,
+            threshold: 16,
+        };
+        Ok(CompressingConfig {
+            inner_config,
+            compression_config,
+        })
+    }
+}
+
+impl<S> CompressingStore<S> {
+    /// Creates a new store that compresses the values of the given one.
+    pub fn new(store: S, config: CompressionConfig) -> Self {
+        CompressingStore { store, config }
+    }
+}
+
+fn with_header(tag: u8, payload: &[u8]) -> Vec<u8> {
+    let mut value = Vec::with_capacity(HEADER_SIZE + payload.len());
+    value.extend(MAGIC);
+    value.push(VERSION);
+    value.push(tag);
+    value.extend(payload);
+    value
+}
+
+/// Returns the bytes to be stored for `value`.
+fn compress<E>(config: &CompressionConfig, value: Vec<u8>) -> Result<Vec<u8>, CompressingError<E>> {
+    let compressed = if value.len() < config.threshold {
+        None
+    } else {
+        match config.algorithm {
+            CompressionAlgorithm::None => None,
+            CompressionAlgorithm::Lz4 => Some((LZ4_TAG, lz4_flex::compress_prepend_size(&value))),
+            CompressionAlgorithm::Zstd { level } => {
+                let payload = zstd::bulk::compress(&value, level)
+                    .map_err(|error| CompressingError::Compression(error.to_string()))?;
+                Some((ZSTD_TAG, payload))
+            }
+        }
+    };
+    #[cfg(with_metrics)]
+    let (attempted, input_size) = (compressed.is_some(), value.len());
+    let stored_value = match compressed {
+        Some((tag, payload)) if HEADER_SIZE + payload.len() < value.len() => {
+            with_header(tag, &payload)
+        }
+        _ if value.starts_with(&MAGIC) => with_header(RAW_TAG, &value),
+        _ => value,
+    };
+    #[cfg(with_metrics)]
+    if attempted && input_size > 0 {
+        crate::metering::observe_compression(
+            config.algorithm.label(),
+            input_size,
+            stored_value.len(),
+        );
+    }
+    Ok(stored_value)
+}
+
+/// Returns the original value from the stored bytes. Values without a known header are
+/// legacy values, returned as they are.
+fn decompress<E>(value: Vec<u8>) -> Result<Vec<u8>, CompressingError<E>> {
+    if value.len() < HEADER_SIZE || !value.starts_with(&MAGIC) || value[MAGIC.len()] != VERSION {
+        return Ok(value);
+    }
+    let payload = &value[HEADER_SIZE..];
+    match value[MAGIC.len() + 1] {
+        RAW_TAG => Ok(payload.to_vec()),
+        LZ4_TAG => lz4_flex::decompress_size_prepended(payload)
+            .map_err(|error| CompressingError::Compression(error.to_string())),
+        ZSTD_TAG => zstd::stream::decode_all(payload)
+            .map_err(|error| CompressingError::Compression(error.to_string())),
+        _ => Ok(value),
+    }
+}
+
+#[expect(clippy::type_complexity)]
+fn decompress_key_values<E>(
+    key_values: Vec<(Vec<u8>, Vec<u8>)>,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>, CompressingError<E>> {
+    key_values
+        .into_iter()
+        .map(|(key, value)| decompress(value).map(|value| (key, value)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{MemoryDatabase, MemoryStore};
+
+    async fn make_stores(
+        algorithm: CompressionAlgorithm,
+    ) -> (MemoryStore, CompressingStore<MemoryStore>) {
+        let database = MemoryDatabase::connect_test_namespace().await.unwrap();
+        let store = database.open_exclusive(&[]).unwrap();
+        let config = CompressionConfig {
+            algorithm,
+            threshold: 100,
+        };
+        (store.clone(), CompressingStore::new(store, config))
+    }
+
+    #[tokio::test]
+    async fn test_compressing_round_trip() {
+        let big_value = b"linera".repeat(100);
+        let magic_value = [&MAGIC[..], b"short"].concat();
+        for algorithm in [
+            CompressionAlgorithm::None,
+            CompressionAlgorithm::Lz4,
+            CompressionAlgorithm::Zstd { level: 3 },
+        ] {
+            let (inner_store, store) = make_stores(algorithm).await;
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![0], vec![1, 2, 3]);
+            batch.put_key_value_bytes(vec![1], big_value.clone());
+            batch.put_key_value_bytes(vec![2], magic_value.clone());
+            store.write_batch(batch).await.unwrap();
+
+            // Small values are stored as they are, unless they could be taken for a header.
+            let stored = inner_store.read_value_bytes(&[0]).await.unwrap();
+            assert_eq!(stored, Some(vec![1, 2, 3]));
+            let stored = inner_store.read_value_bytes(&[2]).await.unwrap().unwrap();
+            assert_eq!(stored.len(), magic_value.len() + HEADER_SIZE);
+            let stored = inner_store.read_value_bytes(&[1]).await.unwrap().unwrap();
+            if algorithm == CompressionAlgorithm::None {
+                assert_eq!(stored, big_value);
+            } else {
+                assert!(stored.len() < big_value.len() / 4);
+            }
+
+            let expected = vec![
+                (vec![0], vec![1, 2, 3]),
+                (vec![1], big_value.clone()),
+                (vec![2], magic_value.clone()),
+            ];
+            let key_values = store.find_key_values_by_prefix(&[]).await.unwrap();
+            assert_eq!(key_values, expected);
+            let range = KeyRange::full().rev().with_limit(2);
+            let key_values = store
+                .find_key_values_by_prefix_in_range(&[], &range)
+                .await
+                .unwrap();
+            assert_eq!(key_values, vec![expected[2].clone(), expected[1].clone()]);
+            let keys = vec![vec![1], vec![3], vec![2]];
+            let values = store.read_multi_values_bytes(&keys).await.unwrap();
+            assert_eq!(
+                values,
+                vec![Some(big_value.clone()), None, Some(magic_value.clone())]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compressing_reads_legacy_values() {
+        let (inner_store, store) = make_stores(CompressionAlgorithm::Lz4).await;
+        let legacy_value = b"legacy".repeat(100);
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![0], legacy_value.clone());
+        inner_store.write_batch(batch).await.unwrap();
+        let value = store.read_value_bytes(&[0]).await.unwrap();
+        assert_eq!(value, Some(legacy_value));
+    }
+
+    #[tokio::test]
+    async fn test_compressing_reads_legacy_magic_values() {
+        let (inner_store, store) = make_stores(CompressionAlgorithm::Lz4).await;
+        let unknown_algorithm = with_header(17, b"payload");
+        let mut unknown_version = with_header(RAW_TAG, b"payload");
+        unknown_version[MAGIC.len()] = VERSION + 1;
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![0], unknown_algorithm.clone());
+        batch.put_key_value_bytes(vec![1], unknown_version.clone());
+        batch.put_key_value_bytes(vec![2], MAGIC.to_vec());
+        inner_store.write_batch(batch).await.unwrap();
+
+        // Values starting with `MAGIC` without a known header are read as they are.
+        let keys = vec![vec![0], vec![1], vec![2]];
+        let values = store.read_multi_values_bytes(&keys).await.unwrap();
+        assert_eq!(
+            values,
+            vec![
+                Some(unknown_algorithm),
+                Some(unknown_version),
+                Some(MAGIC.to_vec())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compression_algorithm_from_str() {
+        for algorithm in [
+            CompressionAlgorithm::None,
+            CompressionAlgorithm::Lz4,
+            CompressionAlgorithm::Zstd { level: 19 },
+        ] {
+            assert_eq!(algorithm.to_string().parse(), Ok(algorithm));
+        }
+        assert_eq!(
+            "zstd".parse(),
+            Ok(CompressionAlgorithm::Zstd {
+                level: zstd::DEFAULT_COMPRESSION_LEVEL
+            })
+        );
+        assert!("zstd:high".parse::<CompressionAlgorithm>().is_err());
+        assert!("gzip".parse::<CompressionAlgorithm>().is_err());
+    }
+}
@@ -11,7 +11,7 @@ use crate::store::TestKeyValueDatabase;
 use crate::{
     batch::Batch,
     store::{
-        KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore, WithError,
+        KeyRange, KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore, WithError,
         WritableKeyValueStore,
     },
 };
@@ -192,6 +192,42 @@ where
         };
         Ok(result)
     }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        let result = match self {
+            Self::First(store) => store
+                .find_keys_by_prefix_in_range(key_prefix, range)
+                .await
+                .map_err(DualStoreError::First)?,
+            Self::Second(store) => store
+                .find_keys_by_prefix_in_range(key_prefix, range)
+                .await
+                .map_err(DualStoreError::Second)?,
+        };
+        Ok(result)
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+        let result = match self {
+            Self::First(store) => store
+                .find_key_values_by_prefix_in_range(key_prefix, range)
+                .await
+                .map_err(DualStoreError::First)?,
+            Self::Second(store) => store
+                .find_key_values_by_prefix_in_range(key_prefix, range)
+                .await
+                .map_err(DualStoreError::Second)?,
+        };
+        Ok(result)
+    }
 }
 
 impl<S1, S2> WritableKeyValueStore for DualStore<S1, S2>
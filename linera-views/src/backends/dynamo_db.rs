@@ -6,6 +6,7 @@
 use std::{
     collections::HashMap,
     env,
+    ops::Bound::{Excluded, Included, Unbounded},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -43,11 +44,13 @@ use crate::store::TestKeyValueDatabase;
 use crate::{
     batch::SimpleUnorderedBatch,
     common::get_uleb128_size,
+    compressing::{CompressingConfig, CompressingDatabase, CompressingError},
+    encrypting::{EncryptingConfig, EncryptingDatabase, EncryptingError},
     journaling::{JournalConsistencyError, JournalingKeyValueDatabase},
     lru_caching::{LruCachingConfig, LruCachingDatabase},
     store::{
-        DirectWritableKeyValueStore, KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore,
-        WithError,
+        DirectWritableKeyValueStore, KeyRange, KeyValueDatabase, KeyValueStoreError,
+        ReadableKeyValueStore, WithError,
     },
     value_splitting::{ValueSplittingDatabase, ValueSplittingError},
 };
@@ -252,6 +255,20 @@ fn extract_key_value(
     Ok((key, value))
 }
 
+/// Tests whether an item returned by a range query has a key in the range below
+/// `key_prefix`. Items with a malformed key are kept so that the error is reported when the
+/// key is extracted.
+fn is_in_range(
+    key_prefix: &[u8],
+    range: &KeyRange,
+    attributes: &HashMap<String, AttributeValue>,
+) -> bool {
+    match extract_key(0, attributes) {
+        Ok(key) => key.starts_with(key_prefix) && range.contains(&key[key_prefix.len()..]),
+        Err(_) => true,
+    }
+}
+
 struct TransactionBuilder {
     start_key: Vec<u8>,
     transactions: Vec<TransactWriteItem>,
@@ -612,6 +629,54 @@ impl DynamoDbStoreInternal {
         Ok(response)
     }
 
+    async fn get_range_query_output(
+        &self,
+        attribute_str: &str,
+        key_prefix: &[u8],
+        range: &KeyRange,
+        limit: Option<usize>,
+        start_key_map: Option<HashMap<String, AttributeValue>>,
+    ) -> Result<QueryOutput, DynamoDbStoreInternalError> {
+        let _guard = self.acquire().await;
+        let start_key = self.start_key.to_vec();
+        // DynamoDB only has inclusive bounds in `BETWEEN`, so the excluded bounds are
+        // filtered out afterwards.
+        let (start, end) = range.bounds_for_prefix(key_prefix);
+        let (Included(lower_bound) | Excluded(lower_bound)) = start else {
+            unreachable!("the lower bound of a prefix is always bounded");
+        };
+        let upper_bound = match end {
+            Included(upper_bound) | Excluded(upper_bound) => Some(upper_bound),
+            Unbounded => None,
+        };
+        let condition = match upper_bound {
+            Some(_) => "BETWEEN :lower AND :upper",
+            None => ">= :lower",
+        };
+        let mut query = self
+            .client
+            .query()
+            .table_name(&self.namespace)
+            .projection_expression(attribute_str)
+            .key_condition_expression(format!(
+                "{PARTITION_ATTRIBUTE} = :partition and {KEY_ATTRIBUTE} {condition}"
+            ))
+            .expression_attribute_values(":partition", AttributeValue::B(Blob::new(start_key)))
+            .expression_attribute_values(":lower", AttributeValue::B(Blob::new(lower_bound)));
+        if let Some(upper_bound) = upper_bound {
+            query = query
+                .expression_attribute_values(":upper", AttributeValue::B(Blob::new(upper_bound)));
+        }
+        let response = query
+            .scan_index_forward(!range.reverse)
+            .set_limit(limit.map(|limit| i32::try_from(limit).unwrap_or(i32::MAX)))
+            .set_exclusive_start_key(start_key_map)
+            .send()
+            .boxed_sync()
+            .await?;
+        Ok(response)
+    }
+
     async fn read_value_bytes_general(
         &self,
         key_db: HashMap<String, AttributeValue>,
@@ -682,6 +747,44 @@ impl DynamoDbStoreInternal {
             responses,
         })
     }
+
+    async fn get_range_responses(
+        &self,
+        attribute: &str,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<QueryResponses, DynamoDbStoreInternalError> {
+        check_key_size(key_prefix)?;
+        let mut responses = Vec::new();
+        if !range.is_empty() {
+            let mut count = 0;
+            let mut start_key_map = None;
+            loop {
+                let limit = range.limit.map(|limit| limit - count);
+                let mut response = self
+                    .get_range_query_output(attribute, key_prefix, range, limit, start_key_map)
+                    .await?;
+                if let Some(items) = &mut response.items {
+                    items.retain(|item| is_in_range(key_prefix, range, item));
+                    count += items.len();
+                }
+                let last_evaluated = response.last_evaluated_key.clone();
+                responses.push(response);
+                match last_evaluated {
+                    Some(value) if range.limit.is_none_or(|limit| count < limit) => {
+                        start_key_map = Some(value);
+                    }
+                    _ => {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(QueryResponses {
+            prefix_len: key_prefix.len(),
+            responses,
+        })
+    }
 }
 
 struct QueryResponses {
@@ -797,6 +900,34 @@ impl ReadableKeyValueStore for DynamoDbStoreInternal {
             .map(|entry| entry.map(|(key, value)| (key.to_vec(), value.to_vec())))
             .collect()
     }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, DynamoDbStoreInternalError> {
+        let result_queries = self
+            .get_range_responses(KEY_ATTRIBUTE, key_prefix, range)
+            .await?;
+        result_queries
+            .keys()
+            .map(|key| key.map(|k| k.to_vec()))
+            .collect()
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DynamoDbStoreInternalError> {
+        let result_queries = self
+            .get_range_responses(KEY_VALUE_ATTRIBUTE, key_prefix, range)
+            .await?;
+        result_queries
+            .key_values()
+            .map(|entry| entry.map(|(key, value)| (key.to_vec(), value.to_vec())))
+            .collect()
+    }
 }
 
 impl DirectWritableKeyValueStore for DynamoDbStoreInternal {
@@ -1006,18 +1137,24 @@ impl TestKeyValueDatabase for JournalingKeyValueDatabase<DynamoDbDatabaseInterna
 }
 
 /// The combined error type for [`DynamoDbDatabase`].
-pub type DynamoDbStoreError = ValueSplittingError<DynamoDbStoreInternalError>;
+pub type DynamoDbStoreError =
+    CompressingError<EncryptingError<ValueSplittingError<DynamoDbStoreInternalError>>>;
 
 /// The config type for [`DynamoDbDatabase`]`
-pub type DynamoDbStoreConfig = LruCachingConfig<DynamoDbStoreInternalConfig>;
+pub type DynamoDbStoreConfig =
+    LruCachingConfig<CompressingConfig<EncryptingConfig<DynamoDbStoreInternalConfig>>>;
 
 /// A shared DB client for DynamoDB with metrics
 #[cfg(with_metrics)]
 pub type DynamoDbDatabase = MeteredDatabase<
     LruCachingDatabase<
-        MeteredDatabase<
-            ValueSplittingDatabase<
-                MeteredDatabase<JournalingKeyValueDatabase<DynamoDbDatabaseInternal>>,
+        CompressingDatabase<
+            EncryptingDatabase<
+                MeteredDatabase<
+                    ValueSplittingDatabase<
+                        MeteredDatabase<JournalingKeyValueDatabase<DynamoDbDatabaseInternal>>,
+                    >,
+                >,
             >,
         >,
     >,
@@ -1025,7 +1162,11 @@ pub type DynamoDbDatabase = MeteredDatabase<
 /// A shared DB client for DynamoDB
 #[cfg(not(with_metrics))]
 pub type DynamoDbDatabase = LruCachingDatabase<
-    ValueSplittingDatabase<JournalingKeyValueDatabase<DynamoDbDatabaseInternal>>,
+    CompressingDatabase<
+        EncryptingDatabase<
+            ValueSplittingDatabase<JournalingKeyValueDatabase<DynamoDbDatabaseInternal>>,
+        >,
+    >,
 >;
 
 #[cfg(test)]
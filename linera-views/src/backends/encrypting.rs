@@ -0,0 +1,1321 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Adds encryption at rest of the values to a given store.
+//!
+//! Every value is sealed with AES-256-GCM under the active key of the [`EncryptionConfig`],
+//! and stored after a version byte, the identifier of the key and the nonce. The root key
+//! and the stored key are used as associated data, so that an encrypted value cannot be moved
+//! to another key, in the same store or in the store of another root key. All the keys of the
+//! configuration can decrypt, which allows rotating them: the new values use the active key
+//! and [`EncryptingStore::reencrypt`] rewrites the existing ones.
+//!
+//! Optionally, the keys are hidden as well. Only their first
+//! [`KeyHashingConfig::plaintext_prefix_len`] bytes are kept, followed by an HMAC of the root
+//! key and the whole key, and the original key is encrypted together with the value. Prefix
+//! queries then scan the entries sharing the plaintext portion of the prefix, and filter them
+//! after decryption.
+//!
+//! The root keys are not encrypted. Without an [`EncryptionConfig`], the values are stored in
+//! plaintext. The values are not marked as encrypted or not, so the encryption must be
+//! enabled on a fresh namespace: the values written in plaintext before cannot be read, and
+//! [`EncryptingStore::reencrypt`] does not convert them.
+
+use std::{collections::BTreeMap, fmt, pin::pin, str::FromStr, sync::Arc};
+
+use futures::{future::Either, stream, Stream, StreamExt as _, TryStreamExt as _};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    hmac,
+    rand::{SecureRandom as _, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[cfg(with_testing)]
+use crate::store::TestKeyValueDatabase;
+use crate::{
+    batch::{Batch, WriteOperation},
+    store::{
+        KeyRange, KeyValueDatabase, KeyValueStore, KeyValueStoreError, ReadableKeyValueStore,
+        WithError, WritableKeyValueStore,
+    },
+};
+
+/// The size in bytes of the encryption keys and of the key hashing secret.
+pub const KEY_SIZE: usize = 32;
+
+/// The environment variable listing the encryption keys, as in [`EncryptionConfig::from_str`].
+pub const ENCRYPTION_KEYS_VARIABLE: &str = "LINERA_STORAGE_ENCRYPTION_KEYS";
+
+/// The environment variable containing the hex-encoded key hashing secret, if any.
+pub const KEY_HASHING_SECRET_VARIABLE: &str = "LINERA_STORAGE_KEY_HASHING_SECRET";
+
+/// The environment variable containing the number of bytes of the keys kept in plaintext
+/// when hashing the keys.
+pub const PLAINTEXT_KEY_PREFIX_LEN_VARIABLE: &str = "LINERA_STORAGE_PLAINTEXT_KEY_PREFIX_LEN";
+
+/// The current version of the encrypted values.
+const VERSION: u8 = 1;
+
+/// The size of the header made of the version, the key identifier and the nonce.
+const HEADER_SIZE: usize = 1 + 4 + NONCE_LEN;
+
+/// The size of the authentication tag of AES-256-GCM.
+const TAG_SIZE: usize = 16;
+
+/// The size of the HMAC-SHA256 of the hashed keys.
+const HASH_SIZE: usize = 32;
+
+/// The maximal number of values rewritten by a batch of [`EncryptingStore::reencrypt`].
+const REENCRYPTION_BATCH_SIZE: usize = 1000;
+
+/// A secret key of [`KEY_SIZE`] bytes, written in hexadecimal.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct EncryptionKey([u8; KEY_SIZE]);
+
+impl EncryptionKey {
+    /// Creates a key from its bytes.
+    pub fn new(bytes: [u8; KEY_SIZE]) -> Self {
+        EncryptionKey(bytes)
+    }
+
+    /// Generates a random key.
+    pub fn generate() -> Result<Self, EncryptionConfigError> {
+        let mut bytes = [0; KEY_SIZE];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| EncryptionConfigError::Randomness)?;
+        Ok(EncryptionKey(bytes))
+    }
+}
+
+// The keys must not end up in the logs.
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EncryptionKey(..)")
+    }
+}
+
+impl FromStr for EncryptionKey {
+    type Err = EncryptionConfigError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(input.trim()).map_err(|_| EncryptionConfigError::InvalidKey)?;
+        let bytes = bytes
+            .try_into()
+            .map_err(|_| EncryptionConfigError::InvalidKey)?;
+        Ok(EncryptionKey(bytes))
+    }
+}
+
+impl TryFrom<String> for EncryptionKey {
+    type Error = EncryptionConfigError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        input.parse()
+    }
+}
+
+impl From<EncryptionKey> for String {
+    fn from(key: EncryptionKey) -> Self {
+        hex::encode(key.0)
+    }
+}
+
+/// The hashing of the keys of an [`EncryptingDatabase`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyHashingConfig {
+    /// The secret of the HMAC. Unlike the encryption keys, it cannot be rotated.
+    pub secret: EncryptionKey,
+    /// The number of leading bytes of the keys that are kept in plaintext. Deleting a prefix
+    /// longer than this reads and decrypts every entry sharing its plaintext portion, so it
+    /// should cover the base keys of the views that get cleared.
+    pub plaintext_prefix_len: usize,
+}
+
+/// The encryption settings of an [`EncryptingDatabase`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    /// The keys able to decrypt the values, by identifier.
+    pub keys: BTreeMap<u32, EncryptionKey>,
+    /// The identifier of the key encrypting the new values.
+    pub active_key_id: u32,
+    /// The hashing of the keys, if they are to be hidden.
+    pub key_hashing: Option<KeyHashingConfig>,
+}
+
+impl EncryptionConfig {
+    /// Creates a configuration with a single key, and without key hashing.
+    pub fn new(key_id: u32, key: EncryptionKey) -> Self {
+        EncryptionConfig {
+            keys: BTreeMap::from([(key_id, key)]),
+            active_key_id: key_id,
+            key_hashing: None,
+        }
+    }
+
+    /// Reads the configuration from the environment. Returns `None` if
+    /// [`ENCRYPTION_KEYS_VARIABLE`] is not set. The keys are hashed if
+    /// [`KEY_HASHING_SECRET_VARIABLE`] is set, in which case
+    /// [`PLAINTEXT_KEY_PREFIX_LEN_VARIABLE`] must be set as well.
+    pub fn from_env() -> Result<Option<Self>, EncryptionConfigError> {
+        let Ok(keys) = std::env::var(ENCRYPTION_KEYS_VARIABLE) else {
+            return Ok(None);
+        };
+        let mut config = keys.parse::<EncryptionConfig>()?;
+        if let Ok(secret) = std::env::var(KEY_HASHING_SECRET_VARIABLE) {
+            let length = std::env::var(PLAINTEXT_KEY_PREFIX_LEN_VARIABLE).unwrap_or_default();
+            let plaintext_prefix_len = length
+                .parse()
+                .map_err(|_| EncryptionConfigError::InvalidPrefixLength(length))?;
+            config.key_hashing = Some(KeyHashingConfig {
+                secret: secret.parse()?,
+                plaintext_prefix_len,
+            });
+        }
+        Ok(Some(config))
+    }
+}
+
+impl FromStr for EncryptionConfig {
+    type Err = EncryptionConfigError;
+
+    /// Parses a comma-separated list of `ID:KEY`, where the keys are hex-encoded. The first
+    /// key is the active one.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut keys = BTreeMap::new();
+        let mut active_key_id = None;
+        for entry in input.split(',') {
+            let (key_id, key) = entry
+                .split_once(':')
+                .ok_or_else(|| EncryptionConfigError::InvalidKeyList(entry.to_string()))?;
+            let key_id = key_id
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| EncryptionConfigError::InvalidKeyList(entry.to_string()))?;
+            if keys.insert(key_id, key.parse()?).is_some() {
+                return Err(EncryptionConfigError::DuplicateKeyId(key_id));
+            }
+            active_key_id.get_or_insert(key_id);
+        }
+        Ok(EncryptionConfig {
+            keys,
+            active_key_id: active_key_id.expect("`split` returns at least one entry"),
+            key_hashing: None,
+        })
+    }
+}
+
+/// The configuration type for the `EncryptingDatabase`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptingConfig<C> {
+    /// The inner configuration of the `EncryptingDatabase`.
+    pub inner_config: C,
+    /// The encryption settings, or `None` to store the values in plaintext.
+    pub encryption_config: Option<EncryptionConfig>,
+}
+
+/// The errors in an [`EncryptionConfig`].
+#[derive(Error, Debug)]
+pub enum EncryptionConfigError {
+    /// A key is not made of the expected number of hex-encoded bytes
+    #[error("encryption keys must be made of {KEY_SIZE} hex-encoded bytes")]
+    InvalidKey,
+
+    /// An entry of the key list is not of the form `ID:KEY`
+    #[error("invalid entry {0:?} in the list of encryption keys, expected ID:KEY")]
+    InvalidKeyList(String),
+
+    /// The same key identifier is used twice
+    #[error("the key identifier {0} is used twice")]
+    DuplicateKeyId(u32),
+
+    /// The active key is not one of the keys
+    #[error("the active key {0} is not one of the encryption keys")]
+    MissingActiveKey(u32),
+
+    /// The length of the plaintext prefix of the keys is invalid
+    #[error("invalid length {0:?} of the plaintext prefix of the keys")]
+    InvalidPrefixLength(String),
+
+    /// The plaintext prefix of the keys leaves no room for their HMAC in the store
+    #[error("the plaintext prefix of {0} bytes leaves no room for the HMAC of the keys")]
+    PrefixTooLong(usize),
+
+    /// The keys are hashed above a store with a maximal value size
+    #[error("hashing the keys requires a store accepting values of any size")]
+    BoundedValueSize,
+
+    /// The system randomness failed
+    #[error("failed to obtain randomness from the system")]
+    Randomness,
+}
+
+/// A key-value database encrypting the values of the underlying database.
+#[derive(Clone)]
+pub struct EncryptingDatabase<D> {
+    /// The underlying database.
+    database: D,
+    /// The ciphers of the configuration, if the values are encrypted.
+    cipher: Option<Arc<Cipher>>,
+}
+
+/// A key-value store encrypting the values of the underlying store.
+#[derive(Clone)]
+pub struct EncryptingStore<S> {
+    /// The underlying store.
+    store: S,
+    /// The ciphers of the configuration, if the values are encrypted.
+    cipher: Option<Arc<Cipher>>,
+    /// The root key of the underlying store, which the encrypted values are bound to.
+    root_key: Vec<u8>,
+}
+
+/// The composed error type built from the inner error type.
+#[derive(Error, Debug)]
+pub enum EncryptingError<E> {
+    /// inner store error
+    #[error(transparent)]
+    InnerStoreError(#[from] E),
+
+    /// The encryption configuration is invalid
+    #[error(transparent)]
+    Config(EncryptionConfigError),
+
+    /// The value was encrypted with a key that is not in the configuration
+    #[error("the value was encrypted with the unknown key {0}")]
+    UnknownKeyId(u32),
+
+    /// The value has a version that we cannot read
+    #[error("unsupported encrypted value version {0}")]
+    UnsupportedVersion(u8),
+
+    /// The value is too short to be an encrypted value
+    #[error("the encrypted value is truncated")]
+    TruncatedValue,
+
+    /// The value could not be encrypted
+    #[error("the value could not be encrypted")]
+    Encryption,
+
+    /// The value could not be decrypted, because it was altered or the key is wrong
+    #[error("the value could not be decrypted")]
+    Decryption,
+}
+
+impl<E: KeyValueStoreError> From<bcs::Error> for EncryptingError<E> {
+    fn from(error: bcs::Error) -> Self {
+        let error = E::from(error);
+        EncryptingError::InnerStoreError(error)
+    }
+}
+
+impl<E: KeyValueStoreError + 'static> KeyValueStoreError for EncryptingError<E> {
+    const BACKEND: &'static str = "encrypting";
+}
+
+/// The keys derived from an [`EncryptionConfig`].
+struct Cipher {
+    /// The keys able to decrypt the values.
+    keys: BTreeMap<u32, LessSafeKey>,
+    /// The identifier of the key encrypting the new values.
+    active_key_id: u32,
+    /// The HMAC key and the length of the plaintext prefix, if the keys are hashed.
+    key_hashing: Option<(hmac::Key, usize)>,
+    /// The source of the nonces.
+    random: SystemRandom,
+}
+
+impl Cipher {
+    fn new(config: &EncryptionConfig) -> Result<Self, EncryptionConfigError> {
+        if !config.keys.contains_key(&config.active_key_id) {
+            return Err(EncryptionConfigError::MissingActiveKey(
+                config.active_key_id,
+            ));
+        }
+        let keys = config
+            .keys
+            .iter()
+            .map(|(key_id, key)| {
+                let key = UnboundKey::new(&AES_256_GCM, &key.0)
+                    .expect("AES-256-GCM keys have KEY_SIZE bytes");
+                (*key_id, LessSafeKey::new(key))
+            })
+            .collect();
+        let key_hashing = config.key_hashing.as_ref().map(|key_hashing| {
+            let key = hmac::Key::new(hmac::HMAC_SHA256, &key_hashing.secret.0);
+            (key, key_hashing.plaintext_prefix_len)
+        });
+        Ok(Cipher {
+            keys,
+            active_key_id: config.active_key_id,
+            key_hashing,
+            random: SystemRandom::new(),
+        })
+    }
+
+    /// Checks that the entries fit in the stores of type `S` when the keys are hashed.
+    fn check_limits<S: KeyValueStore>(&self) -> Result<(), EncryptionConfigError> {
+        let Some((_, plaintext_prefix_len)) = &self.key_hashing else {
+            return Ok(());
+        };
+        if plaintext_prefix_len.saturating_add(HASH_SIZE) > S::MAX_KEY_SIZE {
+            return Err(EncryptionConfigError::PrefixTooLong(*plaintext_prefix_len));
+        }
+        if S::MAX_VALUE_SIZE != usize::MAX {
+            return Err(EncryptionConfigError::BoundedValueSize);
+        }
+        Ok(())
+    }
+
+    /// Returns the key under which the entry of `key` is stored in the store of `root_key`.
+    fn stored_key(&self, root_key: &[u8], key: &[u8]) -> Vec<u8> {
+        match &self.key_hashing {
+            None => key.to_vec(),
+            Some((hmac_key, plaintext_prefix_len)) => {
+                let prefix = &key[..key.len().min(*plaintext_prefix_len)];
+                let mut context = hmac::Context::with_key(hmac_key);
+                context.update(&(root_key.len() as u64).to_be_bytes());
+                context.update(root_key);
+                context.update(key);
+                [prefix, context.sign().as_ref()].concat()
+            }
+        }
+    }
+
+    fn stored_keys(&self, root_key: &[u8], keys: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        keys.iter()
+            .map(|key| self.stored_key(root_key, key))
+            .collect()
+    }
+
+    /// Returns the key of the entry and its encrypted value.
+    fn seal_entry<E>(
+        &self,
+        root_key: &[u8],
+        key: Vec<u8>,
+        value: Vec<u8>,
+    ) -> Result<(Vec<u8>, Vec<u8>), EncryptingError<E>>
+    where
+        E: KeyValueStoreError,
+    {
+        let stored_key = self.stored_key(root_key, &key);
+        let plaintext = match self.key_hashing {
+            None => value,
+            Some(_) => bcs::to_bytes(&(key, value))?,
+        };
+        let value = self.seal(root_key, &stored_key, plaintext)?;
+        Ok((stored_key, value))
+    }
+
+    /// Returns the original key and value of the entry stored under `stored_key`.
+    fn open_entry<E>(
+        &self,
+        root_key: &[u8],
+        stored_key: Vec<u8>,
+        value: Vec<u8>,
+    ) -> Result<(Vec<u8>, Vec<u8>), EncryptingError<E>>
+    where
+        E: KeyValueStoreError,
+    {
+        let plaintext = self.open(root_key, &stored_key, value)?;
+        match self.key_hashing {
+            None => Ok((stored_key, plaintext)),
+            Some(_) => Ok(bcs::from_bytes(&plaintext)?),
+        }
+    }
+
+    fn seal<E>(
+        &self,
+        root_key: &[u8],
+        stored_key: &[u8],
+        mut plaintext: Vec<u8>,
+    ) -> Result<Vec<u8>, EncryptingError<E>> {
+        let mut nonce = [0; NONCE_LEN];
+        self.random
+            .fill(&mut nonce)
+            .map_err(|_| EncryptingError::Encryption)?;
+        self.keys[&self.active_key_id]
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(associated_data(root_key, stored_key)),
+                &mut plaintext,
+            )
+            .map_err(|_| EncryptingError::Encryption)?;
+        let mut value = Vec::with_capacity(HEADER_SIZE + plaintext.len());
+        value.push(VERSION);
+        value.extend(self.active_key_id.to_be_bytes());
+        value.extend(nonce);
+        value.extend(plaintext);
+        Ok(value)
+    }
+
+    fn open<E>(
+        &self,
+        root_key: &[u8],
+        stored_key: &[u8],
+        mut value: Vec<u8>,
+    ) -> Result<Vec<u8>, EncryptingError<E>> {
+        let key_id = self.key_id(&value)?;
+        let key = self
+            .keys
+            .get(&key_id)
+            .ok_or(EncryptingError::UnknownKeyId(key_id))?;
+        let nonce = Nonce::try_assume_unique_for_key(&value[5..HEADER_SIZE])
+            .expect("the nonce has NONCE_LEN bytes");
+        let plaintext_len = key
+            .open_in_place(
+                nonce,
+                Aad::from(associated_data(root_key, stored_key)),
+                &mut value[HEADER_SIZE..],
+            )
+            .map_err(|_| EncryptingError::Decryption)?
+            .len();
+        value.truncate(HEADER_SIZE + plaintext_len);
+        Ok(value.split_off(HEADER_SIZE))
+    }
+
+    /// Returns the identifier of the key that encrypted `value`.
+    fn key_id<E>(&self, value: &[u8]) -> Result<u32, EncryptingError<E>> {
+        if value.len() < HEADER_SIZE + TAG_SIZE {
+            return Err(EncryptingError::TruncatedValue);
+        }
+        if value[0] != VERSION {
+            return Err(EncryptingError::UnsupportedVersion(value[0]));
+        }
+        Ok(u32::from_be_bytes(value[1..5].try_into().unwrap()))
+    }
+}
+
+/// Returns the associated data binding an encrypted value to its root key and stored key.
+fn associated_data(root_key: &[u8], stored_key: &[u8]) -> Vec<u8> {
+    let root_key_len = (root_key.len() as u64).to_be_bytes();
+    [&root_key_len[..], root_key, stored_key].concat()
+}
+
+impl<D> WithError for EncryptingDatabase<D>
+where
+    D: WithError,
+    D::Error: 'static,
+{
+    type Error = EncryptingError<D::Error>;
+}
+
+impl<S> WithError for EncryptingStore<S>
+where
+    S: WithError,
+    S::Error: 'static,
+{
+    type Error = EncryptingError<S::Error>;
+}
+
+impl<S> ReadableKeyValueStore for EncryptingStore<S>
+where
+    S: ReadableKeyValueStore,
+    S::Error: 'static,
+{
+    // With key hashing, the stored keys are at most as long as the plaintext portion followed
+    // by the HMAC, which `Cipher::check_limits` fits in the keys of the underlying store.
+    const MAX_KEY_SIZE: usize = S::MAX_KEY_SIZE;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    fn root_key(&self) -> Result<Vec<u8>, Self::Error> {
+        Ok(self.store.root_key()?)
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(self.store.read_value_bytes(key).await?);
+        };
+        let stored_key = cipher.stored_key(&self.root_key, key);
+        let Some(value) = self.store.read_value_bytes(&stored_key).await? else {
+            return Ok(None);
+        };
+        let (_, value) = cipher.open_entry(&self.root_key, stored_key, value)?;
+        Ok(Some(value))
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(self.store.contains_key(key).await?);
+        };
+        let stored_key = cipher.stored_key(&self.root_key, key);
+        Ok(self.store.contains_key(&stored_key).await?)
+    }
+
+    async fn contains_keys(&self, keys: &[Vec<u8>]) -> Result<Vec<bool>, Self::Error> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(self.store.contains_keys(keys).await?);
+        };
+        let stored_keys = cipher.stored_keys(&self.root_key, keys);
+        Ok(self.store.contains_keys(&stored_keys).await?)
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: &[Vec<u8>],
+    ) -> Result<Vec<Option<Vec<u8>>>, Self::Error> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(self.store.read_multi_values_bytes(keys).await?);
+        };
+        let stored_keys = cipher.stored_keys(&self.root_key, keys);
+        let values = self.store.read_multi_values_bytes(&stored_keys).await?;
+        let mut result = Vec::with_capacity(values.len());
+        for (stored_key, value) in stored_keys.into_iter().zip(values) {
+            let value = match value {
+                None => None,
+                Some(value) => Some(cipher.open_entry(&self.root_key, stored_key, value)?.1),
+            };
+            result.push(value);
+        }
+        Ok(result)
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+        match &self.cipher {
+            Some(cipher) if cipher.key_hashing.is_some() => {
+                let key_values = self.find_hashed_key_values(cipher, key_prefix).await?;
+                Ok(key_values.into_iter().map(|(key, _)| key).collect())
+            }
+            _ => Ok(self.store.find_keys_by_prefix(key_prefix).await?),
+        }
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(self.store.find_key_values_by_prefix(key_prefix).await?);
+        };
+        if cipher.key_hashing.is_some() {
+            return self.find_hashed_key_values(cipher, key_prefix).await;
+        }
+        let key_values = self.store.find_key_values_by_prefix(key_prefix).await?;
+        self.open_key_values(cipher, key_prefix, key_values)
+    }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        let cipher = match &self.cipher {
+            Some(cipher) if cipher.key_hashing.is_some() => cipher,
+            _ => {
+                return Ok(self
+                    .store
+                    .find_keys_by_prefix_in_range(key_prefix, range)
+                    .await?)
+            }
+        };
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let key_values = self.find_hashed_key_values(cipher, key_prefix).await?;
+        let keys = key_values.into_iter().map(|(key, _)| key).collect();
+        Ok(range.select_keys(keys))
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(self
+                .store
+                .find_key_values_by_prefix_in_range(key_prefix, range)
+                .await?);
+        };
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        if cipher.key_hashing.is_some() {
+            let key_values = self.find_hashed_key_values(cipher, key_prefix).await?;
+            return Ok(range.select_key_values(key_values));
+        }
+        let key_values = self
+            .store
+            .find_key_values_by_prefix_in_range(key_prefix, range)
+            .await?;
+        self.open_key_values(cipher, key_prefix, key_values)
+    }
+
+    // With key hashing, every range scans the plaintext portion of the prefix, so the streams
+    // read the prefix once rather than by pages.
+    fn find_keys_by_prefix_stream(
+        &self,
+        key_prefix: &[u8],
+    ) -> impl Stream<Item = Result<Vec<u8>, Self::Error>> {
+        match &self.cipher {
+            Some(cipher) if cipher.key_hashing.is_some() => {
+                let key_prefix = key_prefix.to_vec();
+                let keys = async move { self.find_keys_by_prefix(&key_prefix).await };
+                Either::Left(
+                    stream::once(keys)
+                        .map_ok(|keys| stream::iter(keys.into_iter().map(Ok)))
+                        .try_flatten(),
+                )
+            }
+            _ => Either::Right(
+                self.store
+                    .find_keys_by_prefix_stream(key_prefix)
+                    .map_err(EncryptingError::InnerStoreError),
+            ),
+        }
+    }
+
+    fn find_key_values_by_prefix_stream(
+        &self,
+        key_prefix: &[u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), Self::Error>> {
+        let cipher = self.cipher.as_deref();
+        match cipher {
+            Some(cipher) if cipher.key_hashing.is_some() => {
+                let key_prefix = key_prefix.to_vec();
+                let key_values = async move { self.find_key_values_by_prefix(&key_prefix).await };
+                Either::Left(
+                    stream::once(key_values)
+                        .map_ok(|key_values| stream::iter(key_values.into_iter().map(Ok)))
+                        .try_flatten(),
+                )
+            }
+            _ => Either::Right(self.store.find_key_values_by_prefix_stream(key_prefix).map(
+                move |entry| {
+                    let (suffix, value) = entry?;
+                    let Some(cipher) = cipher else {
+                        return Ok::<_, EncryptingError<S::Error>>((suffix, value));
+                    };
+                    let stored_key = [key_prefix, &suffix].concat();
+                    let (_, value) = cipher.open_entry(&self.root_key, stored_key, value)?;
+                    Ok((suffix, value))
+                },
+            )),
+        }
+    }
+}
+
+impl<S> WritableKeyValueStore for EncryptingStore<S>
+where
+    S: ReadableKeyValueStore + WritableKeyValueStore,
+    S::Error: 'static,
+{
+    // With key hashing, the original key is encrypted together with the value, which
+    // `Cipher::check_limits` only allows when the values of the underlying store are unbounded.
+    const MAX_VALUE_SIZE: usize = if S::MAX_VALUE_SIZE == usize::MAX {
+        usize::MAX
+    } else {
+        S::MAX_VALUE_SIZE.saturating_sub(HEADER_SIZE + TAG_SIZE)
+    };
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(self.store.write_batch(batch).await?);
+        };
+        if cipher.key_hashing.is_none() {
+            let mut batch_new = Batch::new();
+            for operation in batch.operations {
+                match operation {
+                    WriteOperation::Put { key, value } => {
+                        let (key, value) = cipher.seal_entry(&self.root_key, key, value)?;
+                        batch_new.put_key_value_bytes(key, value);
+                    }
+                    operation => batch_new.operations.push(operation),
+                }
+            }
+            return Ok(self.store.write_batch(batch_new).await?);
+        }
+        // The stored keys only share the plaintext portion of the original keys. The deletions
+        // of prefixes fitting in it are kept, and the longer ones are replaced by the deletions
+        // of the keys currently matching them, which reads and decrypts all the entries sharing
+        // the plaintext portion of the prefix, and does not see the entries written
+        // concurrently.
+        let plaintext_prefix_len = cipher.key_hashing.as_ref().map_or(0, |(_, length)| *length);
+        let mut operations = Vec::<(Vec<u8>, WriteOperation)>::new();
+        for operation in batch.operations {
+            match operation {
+                WriteOperation::Put { key, value } => {
+                    let (stored_key, value) =
+                        cipher.seal_entry(&self.root_key, key.clone(), value)?;
+                    let operation = WriteOperation::Put {
+                        key: stored_key,
+                        value,
+                    };
+                    operations.push((key, operation));
+                }
+                WriteOperation::Delete { key } => {
+                    let stored_key = cipher.stored_key(&self.root_key, &key);
+                    operations.push((key, WriteOperation::Delete { key: stored_key }));
+                }
+                WriteOperation::DeletePrefix { key_prefix } => {
+                    operations.retain(|(key, _)| !key.starts_with(&key_prefix));
+                    if key_prefix.len() <= plaintext_prefix_len {
+                        let shadowed = self.shadowed_entries(cipher, &key_prefix, &operations);
+                        let shadowed = shadowed.await?;
+                        let operation = WriteOperation::DeletePrefix {
+                            key_prefix: key_prefix.clone(),
+                        };
+                        operations.push((key_prefix, operation));
+                        operations.extend(shadowed);
+                        continue;
+                    }
+                    for (suffix, _) in self.find_hashed_key_values(cipher, &key_prefix).await? {
+                        let key = [&key_prefix[..], &suffix[..]].concat();
+                        let stored_key = cipher.stored_key(&self.root_key, &key);
+                        operations.push((key, WriteOperation::Delete { key: stored_key }));
+                    }
+                }
+            }
+        }
+        let mut batch_new = Batch::new();
+        batch_new.operations = operations
+            .into_iter()
+            .map(|(_, operation)| operation)
+            .collect();
+        Ok(self.store.write_batch(batch_new).await?)
+    }
+
+    async fn clear_journal(&self) -> Result<(), Self::Error> {
+        Ok(self.store.clear_journal().await?)
+    }
+}
+
+impl<D> KeyValueDatabase for EncryptingDatabase<D>
+where
+    D: KeyValueDatabase,
+    D::Store: KeyValueStore,
+    D::Error: 'static,
+{
+    type Config = EncryptingConfig<D::Config>;
+
+    type Store = EncryptingStore<D::Store>;
+
+    // The encryption is transparent, so the metrics keep the name of the inner database.
+    fn get_name() -> String {
+        D::get_name()
+    }
+
+    async fn connect(config: &Self::Config, namespace: &str) -> Result<Self, Self::Error> {
+        let cipher = match &config.encryption_config {
+            None => None,
+            Some(config) => {
+                let cipher = Cipher::new(config).map_err(EncryptingError::Config)?;
+                cipher
+                    .check_limits::<D::Store>()
+                    .map_err(EncryptingError::Config)?;
+                Some(Arc::new(cipher))
+            }
+        };
+        let database = D::connect(&config.inner_config, namespace).await?;
+        Ok(EncryptingDatabase { database, cipher })
+    }
+
+    fn open_shared(&self, root_key: &[u8]) -> Result<Self::Store, Self::Error> {
+        let store = self.database.open_shared(root_key)?;
+        let cipher = self.cipher.clone();
+        let root_key = root_key.to_vec();
+        Ok(EncryptingStore {
+            store,
+            cipher,
+            root_key,
+        })
+    }
+
+    fn open_exclusive(&self, root_key: &[u8]) -> Result<Self::Store, Self::Error> {
+        let store = self.database.open_exclusive(root_key)?;
+        let cipher = self.cipher.clone();
+        let root_key = root_key.to_vec();
+        Ok(EncryptingStore {
+            store,
+            cipher,
+            root_key,
+        })
+    }
+
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, Self::Error> {
+        Ok(D::list_all(&config.inner_config).await?)
+    }
+
+    async fn list_root_keys(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        Ok(self.database.list_root_keys().await?)
+    }
+
+    async fn delete_all(config: &Self::Config) -> Result<(), Self::Error> {
+        Ok(D::delete_all(&config.inner_config).await?)
+    }
+
+    async fn exists(config: &Self::Config, namespace: &str) -> Result<bool, Self::Error> {
+        Ok(D::exists(&config.inner_config, namespace).await?)
+    }
+
+    async fn create(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        Ok(D::create(&config.inner_config, namespace).await?)
+    }
+
+    async fn delete(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        Ok(D::delete(&config.inner_config, namespace).await?)
+    }
+}
+
+#[cfg(with_testing)]
+impl<D> TestKeyValueDatabase for EncryptingDatabase<D>
+where
+    D: TestKeyValueDatabase,
+    D::Store: KeyValueStore,
+    D::Error: 'static,
+{
+    // The tests hash the keys, in order to exercise the filtering of the prefix queries.
+    async fn new_test_config() -> Result<EncryptingConfig<D::Config>, Self::Error> {
+        let inner_config = D::new_test_config().await?;
+        let key = EncryptionKey::generate().map_err(EncryptingError::Config)?;
+        let secret = EncryptionKey::generate().map_err(EncryptingError::Config)?;
+        let mut encryption_config = EncryptionConfig::new(0, key);
+        encryption_config.key_hashing = Some(KeyHashingConfig {
+            secret,
+            plaintext_prefix_len: 2,
+        });
+        Ok(EncryptingConfig {
+            inner_config,
+            encryption_config: Some(encryption_config),
+        })
+    }
+}
+
+impl<D> EncryptingDatabase<D>
+where
+    D: KeyValueDatabase,
+    D::Store: KeyValueStore + WithError<Error = D::Error>,
+    D::Error: 'static,
+{
+    /// Re-encrypts with the active key the values of all the root keys listed by
+    /// [`KeyValueDatabase::list_root_keys`], as [`EncryptingStore::reencrypt`] does for a
+    /// single store. Returns the number of values that were re-encrypted.
+    pub async fn reencrypt(&self) -> Result<usize, EncryptingError<D::Error>> {
+        let mut count = 0;
+        for root_key in self.list_root_keys().await? {
+            let store = self.open_exclusive(&root_key)?;
+            count += store.reencrypt().await?;
+        }
+        Ok(count)
+    }
+}
+
+impl<S> EncryptingStore<S>
+where
+    S: KeyValueStore,
+    S::Error: 'static,
+{
+    /// Creates a new store that encrypts the values of the given one.
+    pub fn new(store: S, config: &EncryptionConfig) -> Result<Self, EncryptingError<S::Error>> {
+        let cipher = Cipher::new(config).map_err(EncryptingError::Config)?;
+        cipher
+            .check_limits::<S>()
+            .map_err(EncryptingError::Config)?;
+        let root_key = store.root_key()?;
+        Ok(EncryptingStore {
+            store,
+            cipher: Some(Arc::new(cipher)),
+            root_key,
+        })
+    }
+}
+
+impl<S> EncryptingStore<S>
+where
+    S: ReadableKeyValueStore,
+    S::Error: 'static,
+{
+    #[expect(clippy::type_complexity)]
+    fn open_key_values(
+        &self,
+        cipher: &Cipher,
+        key_prefix: &[u8],
+        key_values: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, EncryptingError<S::Error>> {
+        let mut result = Vec::with_capacity(key_values.len());
+        for (suffix, value) in key_values {
+            let stored_key = [key_prefix, &suffix].concat();
+            let (_, value) = cipher.open_entry(&self.root_key, stored_key, value)?;
+            result.push((suffix, value));
+        }
+        Ok(result)
+    }
+
+    /// Returns the operations restoring the entries that the deletion of `key_prefix` would
+    /// remove by accident, because they are shorter than `key_prefix` and their HMAC happens
+    /// to complete it. Only the keys that are strict prefixes of `key_prefix` can be affected,
+    /// and the pending `operations` of the batch take precedence over the stored entries.
+    async fn shadowed_entries(
+        &self,
+        cipher: &Cipher,
+        key_prefix: &[u8],
+        operations: &[(Vec<u8>, WriteOperation)],
+    ) -> Result<Vec<(Vec<u8>, WriteOperation)>, EncryptingError<S::Error>> {
+        let mut shadowed = Vec::new();
+        for length in 0..key_prefix.len() {
+            let key = &key_prefix[..length];
+            let stored_key = cipher.stored_key(&self.root_key, key);
+            if !stored_key.starts_with(key_prefix) {
+                continue;
+            }
+            let pending = operations
+                .iter()
+                .rev()
+                .find(|(other, operation)| match operation {
+                    WriteOperation::DeletePrefix { .. } => key.starts_with(other),
+                    _ => other == key,
+                });
+            let operation = match pending {
+                Some((_, WriteOperation::DeletePrefix { .. })) => continue,
+                Some((_, operation)) => operation.clone(),
+                None => match self.store.read_value_bytes(&stored_key).await? {
+                    Some(value) => WriteOperation::Put {
+                        key: stored_key,
+                        value,
+                    },
+                    None => continue,
+                },
+            };
+            shadowed.push((key.to_vec(), operation));
+        }
+        Ok(shadowed)
+    }
+
+    /// Finds the entries matching `key_prefix` when the keys are hashed, by scanning the
+    /// entries sharing its plaintext portion.
+    async fn find_hashed_key_values(
+        &self,
+        cipher: &Cipher,
+        key_prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, EncryptingError<S::Error>> {
+        let plaintext_prefix_len = cipher
+            .key_hashing
+            .as_ref()
+            .map_or(key_prefix.len(), |(_, length)| *length);
+        let lookup_prefix = &key_prefix[..key_prefix.len().min(plaintext_prefix_len)];
+        let entries = self.store.find_key_values_by_prefix(lookup_prefix).await?;
+        let mut key_values = Vec::new();
+        for (suffix, value) in entries {
+            let stored_key = [lookup_prefix, &suffix].concat();
+            let (key, value) = cipher.open_entry(&self.root_key, stored_key, value)?;
+            if let Some(suffix) = key.strip_prefix(key_prefix) {
+                key_values.push((suffix.to_vec(), value));
+            }
+        }
+        key_values.sort_unstable_by(|(key1, _), (key2, _)| key1.cmp(key2));
+        Ok(key_values)
+    }
+}
+
+impl<S> EncryptingStore<S>
+where
+    S: ReadableKeyValueStore + WritableKeyValueStore,
+    S::Error: 'static,
+{
+    /// Re-encrypts with the active key the values that were encrypted with another key, so
+    /// that the previous keys can be removed from the configuration. The values are read as
+    /// a stream and rewritten by batches of bounded size. Returns the number of values that
+    /// were re-encrypted.
+    pub async fn reencrypt(&self) -> Result<usize, EncryptingError<S::Error>> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(0);
+        };
+        let mut entries = pin!(self.store.find_key_values_by_prefix_stream(&[]));
+        let mut count = 0;
+        let mut batch = Batch::new();
+        while let Some((stored_key, value)) = entries.try_next().await? {
+            if cipher.key_id(&value)? == cipher.active_key_id {
+                continue;
+            }
+            let plaintext = cipher.open(&self.root_key, &stored_key, value)?;
+            let value = cipher.seal(&self.root_key, &stored_key, plaintext)?;
+            batch.put_key_value_bytes(stored_key, value);
+            if batch.operations.len() == REENCRYPTION_BATCH_SIZE {
+                count += batch.operations.len();
+                self.store.write_batch(std::mem::take(&mut batch)).await?;
+            }
+        }
+        if !batch.operations.is_empty() {
+            count += batch.operations.len();
+            self.store.write_batch(batch).await?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        memory::{MemoryDatabase, MemoryStore},
+        random::generate_test_namespace,
+    };
+
+    fn make_config(key_hashing: bool) -> EncryptionConfig {
+        let mut config = EncryptionConfig::new(1, EncryptionKey::new([1; KEY_SIZE]));
+        if key_hashing {
+            config.key_hashing = Some(KeyHashingConfig {
+                secret: EncryptionKey::new([2; KEY_SIZE]),
+                plaintext_prefix_len: 1,
+            });
+        }
+        config
+    }
+
+    async fn make_stores(config: &EncryptionConfig) -> (MemoryStore, EncryptingStore<MemoryStore>) {
+        let database = MemoryDatabase::connect_test_namespace().await.unwrap();
+        let store = database.open_exclusive(&[]).unwrap();
+        (store.clone(), EncryptingStore::new(store, config).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_encrypting_round_trip() {
+        for key_hashing in [false, true] {
+            let (inner_store, store) = make_stores(&make_config(key_hashing)).await;
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![0, 1], b"first".to_vec());
+            batch.put_key_value_bytes(vec![0, 2, 3], b"second".to_vec());
+            batch.put_key_value_bytes(vec![0], b"short".to_vec());
+            batch.put_key_value_bytes(vec![1, 1], b"other".to_vec());
+            store.write_batch(batch).await.unwrap();
+
+            // Neither the values nor the hashed keys appear in the underlying store.
+            let stored = inner_store.find_key_values_by_prefix(&[]).await.unwrap();
+            assert_eq!(stored.len(), 4);
+            for (key, value) in &stored {
+                assert!(!value.windows(5).any(|window| window == b"first"));
+                assert_eq!(key.len() > 3, key_hashing);
+            }
+
+            let value = store.read_value_bytes(&[0, 2, 3]).await.unwrap();
+            assert_eq!(value, Some(b"second".to_vec()));
+            assert!(store.contains_key(&[0]).await.unwrap());
+            assert!(!store.contains_key(&[0, 3]).await.unwrap());
+            let keys = store.find_keys_by_prefix(&[0]).await.unwrap();
+            assert_eq!(keys, vec![vec![], vec![1], vec![2, 3]]);
+            let key_values = store.find_key_values_by_prefix(&[0, 2]).await.unwrap();
+            assert_eq!(key_values, vec![(vec![3], b"second".to_vec())]);
+            let key_values = store.find_key_values_by_prefix_stream(&[0]);
+            let key_values = key_values.try_collect::<Vec<_>>().await.unwrap();
+            assert_eq!(
+                key_values,
+                store.find_key_values_by_prefix(&[0]).await.unwrap()
+            );
+            let keys = store.find_keys_by_prefix_stream(&[0]);
+            let keys = keys.try_collect::<Vec<_>>().await.unwrap();
+            assert_eq!(keys, vec![vec![], vec![1], vec![2, 3]]);
+            let range = KeyRange::full().rev().with_limit(1);
+            let keys = store
+                .find_keys_by_prefix_in_range(&[0], &range)
+                .await
+                .unwrap();
+            assert_eq!(keys, vec![vec![2, 3]]);
+
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![0, 2, 4], b"third".to_vec());
+            batch.delete_key_prefix(vec![0, 2]);
+            batch.put_key_value_bytes(vec![0, 2, 5], b"fourth".to_vec());
+            store.write_batch(batch).await.unwrap();
+            let keys = store.find_keys_by_prefix(&[]).await.unwrap();
+            assert_eq!(keys, vec![vec![0], vec![0, 1], vec![0, 2, 5], vec![1, 1]]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypting_detects_tampering() {
+        let (inner_store, store) = make_stores(&make_config(false)).await;
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![0], b"value".to_vec());
+        store.write_batch(batch).await.unwrap();
+
+        // A value moved to another key does not decrypt.
+        let value = inner_store.read_value_bytes(&[0]).await.unwrap().unwrap();
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![1], value);
+        inner_store.write_batch(batch).await.unwrap();
+        assert!(matches!(
+            store.read_value_bytes(&[1]).await,
+            Err(EncryptingError::Decryption)
+        ));
+
+        // A store with another key cannot read the values.
+        let config = EncryptionConfig::new(1, EncryptionKey::new([3; KEY_SIZE]));
+        let other_store = EncryptingStore::new(inner_store, &config).unwrap();
+        assert!(matches!(
+            other_store.read_value_bytes(&[0]).await,
+            Err(EncryptingError::Decryption)
+        ));
+
+        // An entry moved to the store of another root key is not readable there.
+        for key_hashing in [false, true] {
+            let config = make_config(key_hashing);
+            let database = MemoryDatabase::connect_test_namespace().await.unwrap();
+            let inner_store1 = database.open_exclusive(&[1]).unwrap();
+            let inner_store2 = database.open_exclusive(&[2]).unwrap();
+            let store1 = EncryptingStore::new(inner_store1.clone(), &config).unwrap();
+            let store2 = EncryptingStore::new(inner_store2.clone(), &config).unwrap();
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![0], b"value".to_vec());
+            store1.write_batch(batch).await.unwrap();
+            let mut batch = Batch::new();
+            for (key, value) in inner_store1.find_key_values_by_prefix(&[]).await.unwrap() {
+                batch.put_key_value_bytes(key, value);
+            }
+            inner_store2.write_batch(batch).await.unwrap();
+            let result = store2.read_value_bytes(&[0]).await;
+            if key_hashing {
+                assert!(matches!(result, Ok(None)));
+            } else {
+                assert!(matches!(result, Err(EncryptingError::Decryption)));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypting_short_prefix_deletion() {
+        let (inner_store, store) = make_stores(&make_config(true)).await;
+        let cipher = store.cipher.clone().unwrap();
+        // The empty key is stored under its HMAC, which starts with `byte`.
+        let byte = cipher.stored_key(&store.root_key, &[])[0];
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![], b"empty".to_vec());
+        batch.put_key_value_bytes(vec![byte, 1], b"first".to_vec());
+        batch.put_key_value_bytes(vec![byte.wrapping_add(1), 1], b"second".to_vec());
+        store.write_batch(batch).await.unwrap();
+
+        // The prefix fits in the plaintext portion, so it is deleted as such.
+        let mut batch = Batch::new();
+        batch.delete_key_prefix(vec![byte]);
+        store.write_batch(batch).await.unwrap();
+        assert_eq!(inner_store.find_keys_by_prefix(&[]).await.unwrap().len(), 2);
+        let keys = store.find_keys_by_prefix(&[]).await.unwrap();
+        assert_eq!(keys, vec![vec![], vec![byte.wrapping_add(1), 1]]);
+        let value = store.read_value_bytes(&[]).await.unwrap();
+        assert_eq!(value, Some(b"empty".to_vec()));
+
+        // The pending operations on the shadowed keys are kept as well.
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![], b"new".to_vec());
+        batch.delete_key_prefix(vec![byte]);
+        store.write_batch(batch).await.unwrap();
+        let value = store.read_value_bytes(&[]).await.unwrap();
+        assert_eq!(value, Some(b"new".to_vec()));
+        let mut batch = Batch::new();
+        batch.delete_key_prefix(vec![]);
+        batch.delete_key_prefix(vec![byte]);
+        store.write_batch(batch).await.unwrap();
+        assert!(store.find_keys_by_prefix(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_encrypting_key_rotation() {
+        let old_config = make_config(true);
+        let (inner_store, old_store) = make_stores(&old_config).await;
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![0, 1], b"old".to_vec());
+        old_store.write_batch(batch).await.unwrap();
+
+        let mut config = old_config.clone();
+        config.keys.insert(2, EncryptionKey::new([4; KEY_SIZE]));
+        config.active_key_id = 2;
+        let store = EncryptingStore::new(inner_store.clone(), &config).unwrap();
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![0, 2], b"new".to_vec());
+        store.write_batch(batch).await.unwrap();
+        let value = store.read_value_bytes(&[0, 1]).await.unwrap();
+        assert_eq!(value, Some(b"old".to_vec()));
+        assert_eq!(store.reencrypt().await.unwrap(), 1);
+        assert_eq!(store.reencrypt().await.unwrap(), 0);
+
+        // Once the values are re-encrypted, the previous key can be removed.
+        config.keys.remove(&1);
+        let store = EncryptingStore::new(inner_store, &config).unwrap();
+        let key_values = store.find_key_values_by_prefix(&[0]).await.unwrap();
+        assert_eq!(
+            key_values,
+            vec![(vec![1], b"old".to_vec()), (vec![2], b"new".to_vec())]
+        );
+        assert!(matches!(
+            old_store.read_value_bytes(&[0, 1]).await,
+            Err(EncryptingError::UnknownKeyId(2))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_encrypting_database_reencrypt() {
+        let old_config = make_config(false);
+        let mut config = old_config.clone();
+        config.keys.insert(2, EncryptionKey::new([4; KEY_SIZE]));
+        config.active_key_id = 2;
+        let namespace = generate_test_namespace();
+        let connect = |encryption_config| {
+            let namespace = namespace.clone();
+            async move {
+                let config = EncryptingConfig {
+                    inner_config: MemoryDatabase::new_test_config().await.unwrap(),
+                    encryption_config,
+                };
+                EncryptingDatabase::<MemoryDatabase>::maybe_create_and_connect(&config, &namespace)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // More values than fit in a single batch of the re-encryption.
+        let database = connect(Some(old_config)).await;
+        for root_key in [[1], [2]] {
+            let store = database.open_exclusive(&root_key).unwrap();
+            let mut batch = Batch::new();
+            for index in 0..REENCRYPTION_BATCH_SIZE as u32 {
+                batch.put_key_value_bytes(index.to_be_bytes().to_vec(), root_key.to_vec());
+            }
+            store.write_batch(batch).await.unwrap();
+        }
+        let count = 2 * REENCRYPTION_BATCH_SIZE;
+        assert_eq!(connect(None).await.reencrypt().await.unwrap(), 0);
+        assert_eq!(
+            connect(Some(config.clone()))
+                .await
+                .reencrypt()
+                .await
+                .unwrap(),
+            count
+        );
+
+        config.keys.remove(&1);
+        let database = connect(Some(config)).await;
+        assert_eq!(database.reencrypt().await.unwrap(), 0);
+        let store = database.open_exclusive(&[2]).unwrap();
+        let key_values = store.find_key_values_by_prefix(&[]).await.unwrap();
+        assert_eq!(key_values.len(), REENCRYPTION_BATCH_SIZE);
+        assert!(key_values.iter().all(|(_, value)| value == &[2]));
+    }
+
+    #[test]
+    fn test_encryption_config_from_str() {
+        let key1 = "11".repeat(KEY_SIZE);
+        let key2 = "22".repeat(KEY_SIZE);
+        let config = format!("7:{key1},3:{key2}")
+            .parse::<EncryptionConfig>()
+            .unwrap();
+        assert_eq!(config.active_key_id, 7);
+        assert_eq!(config.keys[&3], EncryptionKey::new([0x22; KEY_SIZE]));
+        assert!(format!("7:{key1},7:{key2}")
+            .parse::<EncryptionConfig>()
+            .is_err());
+        assert!("7:1234".parse::<EncryptionConfig>().is_err());
+        assert!(key1.parse::<EncryptionConfig>().is_err());
+        let config = EncryptionConfig::new(7, EncryptionKey::new([0x11; KEY_SIZE]));
+        assert!(Cipher::new(&EncryptionConfig {
+            active_key_id: 8,
+            ..config
+        })
+        .is_err());
+    }
+}
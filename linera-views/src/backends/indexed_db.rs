@@ -3,7 +3,10 @@
 
 //! Implements [`crate::store::KeyValueStore`] for the IndexedDB Web database.
 
-use std::rc::Rc;
+use std::{
+    ops::Bound::{Excluded, Included, Unbounded},
+    rc::Rc,
+};
 
 use futures::future;
 use indexed_db_futures::{js_sys, prelude::*, web_sys};
@@ -14,7 +17,7 @@ use crate::{
     batch::{Batch, WriteOperation},
     common::get_upper_bound_option,
     store::{
-        KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore, WithError,
+        KeyRange, KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore, WithError,
         WritableKeyValueStore,
     },
 };
@@ -104,6 +107,40 @@ fn prefix_to_range(prefix: &[u8]) -> Result<web_sys::IdbKeyRange, wasm_bindgen::
     }
 }
 
+fn key_range_to_range(
+    prefix: &[u8],
+    range: &KeyRange,
+) -> Result<web_sys::IdbKeyRange, wasm_bindgen::JsValue> {
+    let (start, end) = range.bounds_for_prefix(prefix);
+    let (lower, lower_open) = match start {
+        Included(lower) => (lower, false),
+        Excluded(lower) => (lower, true),
+        Unbounded => unreachable!("the lower bound of a prefix is always bounded"),
+    };
+    let lower = js_sys::Uint8Array::from(&lower[..]);
+    match end {
+        Included(upper) | Excluded(upper) => {
+            let upper_open = matches!(end, Excluded(_));
+            let upper = js_sys::Uint8Array::from(&upper[..]);
+            web_sys::IdbKeyRange::bound_with_lower_open_and_upper_open(
+                &lower.into(),
+                &upper.into(),
+                lower_open,
+                upper_open,
+            )
+        }
+        Unbounded => web_sys::IdbKeyRange::lower_bound_with_open(&lower.into(), lower_open),
+    }
+}
+
+fn cursor_direction(range: &KeyRange) -> web_sys::IdbCursorDirection {
+    if range.reverse {
+        web_sys::IdbCursorDirection::Prev
+    } else {
+        web_sys::IdbCursorDirection::Next
+    }
+}
+
 impl WithError for IndexedDbStore {
     type Error = IndexedDbStoreError;
 }
@@ -205,6 +242,79 @@ impl ReadableKeyValueStore for IndexedDbStore {
 
         Ok(key_values)
     }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, IndexedDbStoreError> {
+        let mut keys = vec![];
+        if range.is_empty() {
+            return Ok(keys);
+        }
+        let limit = range.limit.unwrap_or(usize::MAX);
+        let key_prefix = self.full_key(key_prefix);
+        let key_range = key_range_to_range(&key_prefix, range)?;
+        let transaction = self.database.transaction_on_one(&self.object_store_name)?;
+        let object_store = transaction.object_store(&self.object_store_name)?;
+        let Some(cursor) = object_store
+            .open_key_cursor_with_range_and_direction_owned(key_range, cursor_direction(range))?
+            .await?
+        else {
+            return Ok(keys);
+        };
+
+        while keys.len() < limit {
+            let Some(key) = cursor.primary_key() else {
+                break;
+            };
+            let key = js_sys::Uint8Array::new(&key);
+            keys.push(key.subarray(key_prefix.len() as u32, key.length()).to_vec());
+            if !cursor.continue_cursor()?.await? {
+                break;
+            }
+        }
+
+        Ok(keys)
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, IndexedDbStoreError> {
+        let mut key_values = vec![];
+        if range.is_empty() {
+            return Ok(key_values);
+        }
+        let limit = range.limit.unwrap_or(usize::MAX);
+        let key_prefix = self.full_key(key_prefix);
+        let key_range = key_range_to_range(&key_prefix, range)?;
+        let transaction = self.database.transaction_on_one(&self.object_store_name)?;
+        let object_store = transaction.object_store(&self.object_store_name)?;
+        let Some(cursor) = object_store
+            .open_cursor_with_range_and_direction_owned(key_range, cursor_direction(range))?
+            .await?
+        else {
+            return Ok(key_values);
+        };
+
+        while key_values.len() < limit {
+            let Some(key) = cursor.primary_key() else {
+                break;
+            };
+            let key = js_sys::Uint8Array::new(&key);
+            key_values.push((
+                key.subarray(key_prefix.len() as u32, key.length()).to_vec(),
+                js_sys::Uint8Array::new(&cursor.value()).to_vec(),
+            ));
+            if !cursor.continue_cursor()?.await? {
+                break;
+            }
+        }
+
+        Ok(key_values)
+    }
 }
 
 impl WritableKeyValueStore for IndexedDbStore {
@@ -14,7 +14,7 @@ use crate::store::TestKeyValueDatabase;
 use crate::{
     batch::{Batch, WriteOperation},
     lru_prefix_cache::{LruPrefixCache, StorageCacheConfig},
-    store::{KeyValueDatabase, ReadableKeyValueStore, WithError, WritableKeyValueStore},
+    store::{KeyRange, KeyValueDatabase, ReadableKeyValueStore, WithError, WritableKeyValueStore},
 };
 
 #[cfg(with_metrics)]
@@ -343,6 +343,40 @@ where
         cache.insert_find_key_values(key_prefix.to_vec(), &key_values);
         Ok(key_values)
     }
+
+    // A range scan is answered from a cached prefix scan when there is one. Otherwise, it
+    // is delegated to the store and its partial result is not cached.
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        if let Some(cache) = self.get_exclusive_cache() {
+            let mut cache = cache.lock().unwrap();
+            if let Some(keys) = cache.query_find_keys(key_prefix) {
+                return Ok(range.select_keys(keys));
+            }
+        }
+        self.store
+            .find_keys_by_prefix_in_range(key_prefix, range)
+            .await
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+        if let Some(cache) = self.get_exclusive_cache() {
+            let mut cache = cache.lock().unwrap();
+            if let Some(key_values) = cache.query_find_key_values(key_prefix) {
+                return Ok(range.select_key_values(key_values));
+            }
+        }
+        self.store
+            .find_key_values_by_prefix_in_range(key_prefix, range)
+            .await
+    }
 }
 
 impl<K> WritableKeyValueStore for LruCachingStore<K>
@@ -17,7 +17,7 @@ use crate::{
     batch::{Batch, WriteOperation},
     common::get_key_range_for_prefix,
     store::{
-        KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore, WithError,
+        KeyRange, KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore, WithError,
         WritableKeyValueStore,
     },
 };
@@ -211,6 +211,44 @@ impl ReadableKeyValueStore for MemoryStore {
         }
         Ok(key_values)
     }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, MemoryStoreError> {
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let map = self
+            .map
+            .read()
+            .expect("MemoryStore lock should not be poisoned");
+        let len = key_prefix.len();
+        let entries = map
+            .range(range.bounds_for_prefix(key_prefix))
+            .map(|(key, _value)| key[len..].to_vec());
+        Ok(range.take_from(entries))
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, MemoryStoreError> {
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let map = self
+            .map
+            .read()
+            .expect("MemoryStore lock should not be poisoned");
+        let len = key_prefix.len();
+        let entries = map
+            .range(range.bounds_for_prefix(key_prefix))
+            .map(|(key, value)| (key[len..].to_vec(), value.to_vec()));
+        Ok(range.take_from(entries))
+    }
 }
 
 impl WritableKeyValueStore for MemoryStore {
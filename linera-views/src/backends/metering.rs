@@ -10,7 +10,7 @@ use std::{
 
 use convert_case::{Case, Casing};
 use linera_base::prometheus_util::{
-    register_histogram_vec, register_int_counter_vec, MeasureLatency as _,
+    linear_bucket_interval, register_histogram_vec, register_int_counter_vec, MeasureLatency as _,
 };
 use prometheus::{HistogramVec, IntCounterVec};
 
@@ -18,7 +18,7 @@ use prometheus::{HistogramVec, IntCounterVec};
 use crate::store::TestKeyValueDatabase;
 use crate::{
     batch::Batch,
-    store::{KeyValueDatabase, ReadableKeyValueStore, WithError, WritableKeyValueStore},
+    store::{KeyRange, KeyValueDatabase, ReadableKeyValueStore, WithError, WritableKeyValueStore},
 };
 
 #[derive(Clone)]
@@ -30,6 +30,8 @@ pub struct KeyValueStoreMetrics {
     read_multi_values_bytes_latency: HistogramVec,
     find_keys_by_prefix_latency: HistogramVec,
     find_key_values_by_prefix_latency: HistogramVec,
+    find_keys_by_prefix_in_range_latency: HistogramVec,
+    find_key_values_by_prefix_in_range_latency: HistogramVec,
     write_batch_latency: HistogramVec,
     clear_journal_latency: HistogramVec,
     connect_latency: HistogramVec,
@@ -55,6 +57,8 @@ pub struct KeyValueStoreMetrics {
     find_key_values_by_prefix_prefix_size: HistogramVec,
     find_key_values_by_prefix_num_keys: HistogramVec,
     find_key_values_by_prefix_key_values_size: HistogramVec,
+    find_keys_by_prefix_in_range_num_keys: HistogramVec,
+    find_key_values_by_prefix_in_range_num_keys: HistogramVec,
     write_batch_size: HistogramVec,
     list_all_sizes: HistogramVec,
     exists_true_cases: IntCounterVec,
@@ -69,6 +73,51 @@ struct StoreMetrics {
 static STORE_COUNTERS: LazyLock<Mutex<StoreMetrics>> =
     LazyLock::new(|| Mutex::new(StoreMetrics::default()));
 
+/// The ratio between the stored and the original sizes of the compressed values.
+#[cfg(with_compression)]
+static COMPRESSION_RATIO: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec(
+        "compression_ratio",
+        "Ratio between the stored and the original sizes of the compressed values",
+        &["algorithm"],
+        linear_bucket_interval(0.05, 0.05, 1.0),
+    )
+});
+
+/// The number of bytes given to the compression.
+#[cfg(with_compression)]
+static COMPRESSION_INPUT_BYTES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "compression_input_bytes",
+        "Number of bytes given to the compression",
+        &["algorithm"],
+    )
+});
+
+/// The number of bytes stored after the compression.
+#[cfg(with_compression)]
+static COMPRESSION_OUTPUT_BYTES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "compression_output_bytes",
+        "Number of bytes stored after the compression",
+        &["algorithm"],
+    )
+});
+
+/// Records the compression of a value of `input_size` bytes into `output_size` bytes.
+#[cfg(with_compression)]
+pub(crate) fn observe_compression(algorithm: &str, input_size: usize, output_size: usize) {
+    COMPRESSION_RATIO
+        .with_label_values(&[algorithm])
+        .observe(output_size as f64 / input_size as f64);
+    COMPRESSION_INPUT_BYTES
+        .with_label_values(&[algorithm])
+        .inc_by(input_size as u64);
+    COMPRESSION_OUTPUT_BYTES
+        .with_label_values(&[algorithm])
+        .inc_by(output_size as u64);
+}
+
 fn get_counter(name: &str) -> Arc<KeyValueStoreMetrics> {
     let mut store_metrics = STORE_COUNTERS.lock().unwrap();
     let key = name.to_string();
@@ -116,6 +165,16 @@ impl KeyValueStoreMetrics {
         let entry2 = format!("{} find key values by prefix latency", title_name);
         let find_key_values_by_prefix_latency = register_histogram_vec(&entry1, &entry2, &[], None);
 
+        let entry1 = format!("{}_find_keys_by_prefix_in_range_latency", var_name);
+        let entry2 = format!("{} find keys by prefix in range latency", title_name);
+        let find_keys_by_prefix_in_range_latency =
+            register_histogram_vec(&entry1, &entry2, &[], None);
+
+        let entry1 = format!("{}_find_key_values_by_prefix_in_range_latency", var_name);
+        let entry2 = format!("{} find key values by prefix in range latency", title_name);
+        let find_key_values_by_prefix_in_range_latency =
+            register_histogram_vec(&entry1, &entry2, &[], None);
+
         let entry1 = format!("{}_write_batch_latency", var_name);
         let entry2 = format!("{} write batch latency", title_name);
         let write_batch_latency = register_histogram_vec(&entry1, &entry2, &[], None);
@@ -219,6 +278,16 @@ impl KeyValueStoreMetrics {
         let find_key_values_by_prefix_key_values_size =
             register_histogram_vec(&entry1, &entry2, &[], None);
 
+        let entry1 = format!("{}_find_keys_by_prefix_in_range_num_keys", var_name);
+        let entry2 = format!("{} find keys by prefix in range num keys", title_name);
+        let find_keys_by_prefix_in_range_num_keys =
+            register_histogram_vec(&entry1, &entry2, &[], None);
+
+        let entry1 = format!("{}_find_key_values_by_prefix_in_range_num_keys", var_name);
+        let entry2 = format!("{} find key values by prefix in range num keys", title_name);
+        let find_key_values_by_prefix_in_range_num_keys =
+            register_histogram_vec(&entry1, &entry2, &[], None);
+
         let entry1 = format!("{}_write_batch_size", var_name);
         let entry2 = format!("{} write batch size", title_name);
         let write_batch_size = register_histogram_vec(&entry1, &entry2, &[], None);
@@ -238,6 +307,8 @@ impl KeyValueStoreMetrics {
             read_multi_values_bytes_latency,
             find_keys_by_prefix_latency,
             find_key_values_by_prefix_latency,
+            find_keys_by_prefix_in_range_latency,
+            find_key_values_by_prefix_in_range_latency,
             write_batch_latency,
             clear_journal_latency,
             connect_latency,
@@ -263,6 +334,8 @@ impl KeyValueStoreMetrics {
             find_key_values_by_prefix_prefix_size,
             find_key_values_by_prefix_num_keys,
             find_key_values_by_prefix_key_values_size,
+            find_keys_by_prefix_in_range_num_keys,
+            find_key_values_by_prefix_in_range_num_keys,
             write_batch_size,
             list_all_sizes,
             exists_true_cases,
@@ -430,6 +503,46 @@ where
             .observe(key_values_size as f64);
         Ok(result)
     }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        let _latency = self
+            .counter
+            .find_keys_by_prefix_in_range_latency
+            .measure_latency();
+        let result = self
+            .store
+            .find_keys_by_prefix_in_range(key_prefix, range)
+            .await?;
+        self.counter
+            .find_keys_by_prefix_in_range_num_keys
+            .with_label_values(&[])
+            .observe(result.len() as f64);
+        Ok(result)
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+        let _latency = self
+            .counter
+            .find_key_values_by_prefix_in_range_latency
+            .measure_latency();
+        let result = self
+            .store
+            .find_key_values_by_prefix_in_range(key_prefix, range)
+            .await?;
+        self.counter
+            .find_key_values_by_prefix_in_range_num_keys
+            .with_label_values(&[])
+            .observe(result.len() as f64);
+        Ok(result)
+    }
 }
 
 impl<S> WritableKeyValueStore for MeteredStore<S>
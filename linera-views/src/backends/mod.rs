@@ -12,6 +12,12 @@ pub mod memory;
 
 pub mod lru_caching;
 
+#[cfg(with_compression)]
+pub mod compressing;
+
+#[cfg(with_encryption)]
+pub mod encrypting;
+
 pub mod dual;
 
 #[cfg(with_scylladb)]
@@ -20,6 +26,9 @@ pub mod scylla_db;
 #[cfg(with_rocksdb)]
 pub mod rocks_db;
 
+#[cfg(with_redb)]
+pub mod redb;
+
 #[cfg(with_dynamodb)]
 pub mod dynamo_db;
 
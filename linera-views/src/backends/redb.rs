@@ -0,0 +1,563 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements [`crate::store::KeyValueStore`] for the redb database.
+//!
+//! redb is an embedded key-value store written in pure Rust. It is a lighter alternative
+//! to RocksDB for the environments where building or running RocksDB is not an option.
+
+use std::{
+    ffi::OsString,
+    ops::Bound::{self, Included},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use linera_base::ensure;
+use redb::TableDefinition;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use crate::common::PathWithGuard;
+#[cfg(with_metrics)]
+use crate::metering::MeteredDatabase;
+#[cfg(with_testing)]
+use crate::store::TestKeyValueDatabase;
+use crate::{
+    batch::{Batch, WriteOperation},
+    common::get_upper_bound,
+    compressing::{CompressingConfig, CompressingDatabase, CompressingError},
+    encrypting::{EncryptingConfig, EncryptingDatabase, EncryptingError},
+    lru_caching::{LruCachingConfig, LruCachingDatabase},
+    store::{
+        KeyRange, KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore, WithError,
+        WritableKeyValueStore,
+    },
+    value_splitting::{ValueSplittingDatabase, ValueSplittingError},
+};
+
+/// The prefixes being used in the system
+static ROOT_KEY_DOMAIN: [u8; 1] = [0];
+static STORED_ROOT_KEYS_PREFIX: u8 = 1;
+
+/// The number of streams for the test
+#[cfg(with_testing)]
+const TEST_REDB_MAX_STREAM_QUERIES: usize = 10;
+
+// redb stores the lengths of keys and values as `u32`.
+// For offset reasons we stay well below 4 GiB.
+const MAX_VALUE_SIZE: usize = 3 * 1024 * 1024 * 1024 - 400;
+
+// We keep the same limit on keys as for RocksDB.
+const MAX_KEY_SIZE: usize = 8 * 1024 * 1024 - 400;
+
+/// The name of the database file in the directory of a namespace.
+const DATABASE_FILE_NAME: &str = "data.redb";
+
+/// The single table containing all the root keys of a namespace.
+const TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("linera");
+
+fn check_key_size(key: &[u8]) -> Result<(), RedbStoreInternalError> {
+    ensure!(
+        key.len() <= MAX_KEY_SIZE,
+        RedbStoreInternalError::KeyTooLong
+    );
+    Ok(())
+}
+
+/// Runs a blocking computation on the database outside of the asynchronous runtime.
+async fn spawn<F, O>(f: F) -> Result<O, RedbStoreInternalError>
+where
+    F: FnOnce() -> Result<O, RedbStoreInternalError> + Send + 'static,
+    O: Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await?
+}
+
+fn as_slice_bound(bound: &Bound<Vec<u8>>) -> Bound<&[u8]> {
+    bound.as_ref().map(Vec::as_slice)
+}
+
+#[derive(Clone)]
+struct RedbStoreExecutor {
+    db: Arc<redb::Database>,
+    start_key: Vec<u8>,
+}
+
+impl RedbStoreExecutor {
+    fn full_key(&self, key: &[u8]) -> Result<Vec<u8>, RedbStoreInternalError> {
+        check_key_size(key)?;
+        let mut full_key = self.start_key.clone();
+        full_key.extend(key);
+        Ok(full_key)
+    }
+
+    fn read_multi_values_bytes_internal(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, RedbStoreInternalError> {
+        let full_keys = keys
+            .iter()
+            .map(|key| self.full_key(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let transaction = self.db.begin_read()?;
+        let table = transaction.open_table(TABLE)?;
+        full_keys
+            .iter()
+            .map(|full_key| {
+                let value = table.get(full_key.as_slice())?;
+                Ok(value.map(|value| value.value().to_vec()))
+            })
+            .collect()
+    }
+
+    fn contains_keys_internal(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<bool>, RedbStoreInternalError> {
+        let full_keys = keys
+            .iter()
+            .map(|key| self.full_key(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let transaction = self.db.begin_read()?;
+        let table = transaction.open_table(TABLE)?;
+        full_keys
+            .iter()
+            .map(|full_key| Ok(table.get(full_key.as_slice())?.is_some()))
+            .collect()
+    }
+
+    /// Scans the entries of the range below `key_prefix`, following the direction and the
+    /// limit of the range. The prefix is removed from the keys passed to `entry`.
+    fn find_entries_internal<T>(
+        &self,
+        key_prefix: Vec<u8>,
+        range: KeyRange,
+        entry: impl Fn(&[u8], &[u8]) -> T,
+    ) -> Result<Vec<T>, RedbStoreInternalError> {
+        let prefix = self.full_key(&key_prefix)?;
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let len = prefix.len();
+        let (start, end) = range.bounds_for_prefix(&prefix);
+        let transaction = self.db.begin_read()?;
+        let table = transaction.open_table(TABLE)?;
+        let entries = table.range::<&[u8]>((as_slice_bound(&start), as_slice_bound(&end)))?;
+        range
+            .take_from(entries)
+            .into_iter()
+            .map(|item| {
+                let (key, value) = item?;
+                Ok(entry(&key.value()[len..], value.value()))
+            })
+            .collect()
+    }
+
+    fn write_batch_internal(
+        &self,
+        batch: Batch,
+        write_root_key: bool,
+    ) -> Result<(), RedbStoreInternalError> {
+        let transaction = self.db.begin_write()?;
+        {
+            let mut table = transaction.open_table(TABLE)?;
+            for operation in batch.operations {
+                match operation {
+                    WriteOperation::Delete { key } => {
+                        let full_key = self.full_key(&key)?;
+                        table.remove(full_key.as_slice())?;
+                    }
+                    WriteOperation::Put { key, value } => {
+                        let full_key = self.full_key(&key)?;
+                        table.insert(full_key.as_slice(), value.as_slice())?;
+                    }
+                    WriteOperation::DeletePrefix { key_prefix } => {
+                        let full_key = self.full_key(&key_prefix)?;
+                        let end = get_upper_bound(&full_key);
+                        table.retain_in::<&[u8], _>(
+                            (Included(full_key.as_slice()), as_slice_bound(&end)),
+                            |_, _| false,
+                        )?;
+                    }
+                }
+            }
+            if write_root_key {
+                let mut full_key = self.start_key.clone();
+                full_key[0] = STORED_ROOT_KEYS_PREFIX;
+                table.insert(full_key.as_slice(), [].as_slice())?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+/// The inner client
+#[derive(Clone)]
+pub struct RedbStoreInternal {
+    executor: RedbStoreExecutor,
+    _path_with_guard: PathWithGuard,
+    max_stream_queries: usize,
+    root_key_written: Arc<AtomicBool>,
+}
+
+/// Database-level connection to redb for managing namespaces and partitions.
+#[derive(Clone)]
+pub struct RedbDatabaseInternal {
+    executor: RedbStoreExecutor,
+    _path_with_guard: PathWithGuard,
+    max_stream_queries: usize,
+}
+
+impl WithError for RedbDatabaseInternal {
+    type Error = RedbStoreInternalError;
+}
+
+/// The initial configuration of the system
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RedbStoreInternalConfig {
+    /// The path to the storage containing the namespaces
+    pub path_with_guard: PathWithGuard,
+    /// Preferred buffer size for async streams.
+    pub max_stream_queries: usize,
+}
+
+impl RedbDatabaseInternal {
+    fn check_namespace(namespace: &str) -> Result<(), RedbStoreInternalError> {
+        if !namespace
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || character == '_')
+        {
+            return Err(RedbStoreInternalError::InvalidNamespace);
+        }
+        Ok(())
+    }
+
+    fn build(
+        config: &RedbStoreInternalConfig,
+        namespace: &str,
+    ) -> Result<RedbDatabaseInternal, RedbStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let mut path_with_guard = config.path_with_guard.clone();
+        path_with_guard.path_buf.push(namespace);
+        if !Path::exists(&path_with_guard.path_buf) {
+            std::fs::create_dir(&path_with_guard.path_buf)?;
+        }
+        let db = redb::Database::create(path_with_guard.path_buf.join(DATABASE_FILE_NAME))?;
+        // Read transactions cannot create the table, so we make sure it exists.
+        let transaction = db.begin_write()?;
+        transaction.open_table(TABLE)?;
+        transaction.commit()?;
+        let executor = RedbStoreExecutor {
+            db: Arc::new(db),
+            start_key: ROOT_KEY_DOMAIN.to_vec(),
+        };
+        Ok(RedbDatabaseInternal {
+            executor,
+            _path_with_guard: path_with_guard,
+            max_stream_queries: config.max_stream_queries,
+        })
+    }
+}
+
+impl WithError for RedbStoreInternal {
+    type Error = RedbStoreInternalError;
+}
+
+impl ReadableKeyValueStore for RedbStoreInternal {
+    const MAX_KEY_SIZE: usize = MAX_KEY_SIZE;
+
+    fn max_stream_queries(&self) -> usize {
+        self.max_stream_queries
+    }
+
+    fn root_key(&self) -> Result<Vec<u8>, RedbStoreInternalError> {
+        assert!(self.executor.start_key.starts_with(&ROOT_KEY_DOMAIN));
+        let root_key = self.executor.start_key[ROOT_KEY_DOMAIN.len()..].to_vec();
+        Ok(root_key)
+    }
+
+    async fn read_value_bytes(
+        &self,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, RedbStoreInternalError> {
+        let executor = self.executor.clone();
+        let keys = vec![key.to_vec()];
+        let mut values = spawn(move || executor.read_multi_values_bytes_internal(keys)).await?;
+        Ok(values.pop().flatten())
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, RedbStoreInternalError> {
+        let executor = self.executor.clone();
+        let keys = vec![key.to_vec()];
+        let results = spawn(move || executor.contains_keys_internal(keys)).await?;
+        Ok(results[0])
+    }
+
+    async fn contains_keys(&self, keys: &[Vec<u8>]) -> Result<Vec<bool>, RedbStoreInternalError> {
+        let executor = self.executor.clone();
+        let keys = keys.to_vec();
+        spawn(move || executor.contains_keys_internal(keys)).await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: &[Vec<u8>],
+    ) -> Result<Vec<Option<Vec<u8>>>, RedbStoreInternalError> {
+        let executor = self.executor.clone();
+        let keys = keys.to_vec();
+        spawn(move || executor.read_multi_values_bytes_internal(keys)).await
+    }
+
+    async fn find_keys_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Vec<Vec<u8>>, RedbStoreInternalError> {
+        self.find_keys_by_prefix_in_range(key_prefix, &KeyRange::full())
+            .await
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RedbStoreInternalError> {
+        self.find_key_values_by_prefix_in_range(key_prefix, &KeyRange::full())
+            .await
+    }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, RedbStoreInternalError> {
+        let executor = self.executor.clone();
+        let key_prefix = key_prefix.to_vec();
+        let range = range.clone();
+        spawn(move || executor.find_entries_internal(key_prefix, range, |key, _| key.to_vec()))
+            .await
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RedbStoreInternalError> {
+        let executor = self.executor.clone();
+        let key_prefix = key_prefix.to_vec();
+        let range = range.clone();
+        spawn(move || {
+            executor.find_entries_internal(key_prefix, range, |key, value| {
+                (key.to_vec(), value.to_vec())
+            })
+        })
+        .await
+    }
+}
+
+impl WritableKeyValueStore for RedbStoreInternal {
+    const MAX_VALUE_SIZE: usize = MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), RedbStoreInternalError> {
+        let write_root_key = !self.root_key_written.fetch_or(true, Ordering::SeqCst);
+        let executor = self.executor.clone();
+        spawn(move || executor.write_batch_internal(batch, write_root_key)).await
+    }
+
+    async fn clear_journal(&self) -> Result<(), RedbStoreInternalError> {
+        Ok(())
+    }
+}
+
+impl KeyValueDatabase for RedbDatabaseInternal {
+    type Config = RedbStoreInternalConfig;
+    type Store = RedbStoreInternal;
+
+    fn get_name() -> String {
+        "redb internal".to_string()
+    }
+
+    async fn connect(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<Self, RedbStoreInternalError> {
+        let config = config.clone();
+        let namespace = namespace.to_string();
+        spawn(move || Self::build(&config, &namespace)).await
+    }
+
+    fn open_shared(&self, root_key: &[u8]) -> Result<Self::Store, RedbStoreInternalError> {
+        let mut start_key = ROOT_KEY_DOMAIN.to_vec();
+        start_key.extend(root_key);
+        let mut executor = self.executor.clone();
+        executor.start_key = start_key;
+        Ok(RedbStoreInternal {
+            executor,
+            _path_with_guard: self._path_with_guard.clone(),
+            max_stream_queries: self.max_stream_queries,
+            root_key_written: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    fn open_exclusive(&self, root_key: &[u8]) -> Result<Self::Store, RedbStoreInternalError> {
+        self.open_shared(root_key)
+    }
+
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, RedbStoreInternalError> {
+        let entries = std::fs::read_dir(&config.path_with_guard.path_buf)?;
+        let mut namespaces = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                return Err(RedbStoreInternalError::NonDirectoryNamespace);
+            }
+            let namespace = entry
+                .file_name()
+                .into_string()
+                .map_err(RedbStoreInternalError::IntoStringError)?;
+            namespaces.push(namespace);
+        }
+        Ok(namespaces)
+    }
+
+    async fn list_root_keys(&self) -> Result<Vec<Vec<u8>>, RedbStoreInternalError> {
+        let mut store = self.open_shared(&[])?;
+        store.executor.start_key = vec![STORED_ROOT_KEYS_PREFIX];
+        store.find_keys_by_prefix(&[]).await
+    }
+
+    async fn delete_all(config: &Self::Config) -> Result<(), RedbStoreInternalError> {
+        let namespaces = Self::list_all(config).await?;
+        for namespace in namespaces {
+            let path_buf = config.path_with_guard.path_buf.join(&namespace);
+            std::fs::remove_dir_all(path_buf)?;
+        }
+        Ok(())
+    }
+
+    async fn exists(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<bool, RedbStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let path_buf = config.path_with_guard.path_buf.join(namespace);
+        Ok(Path::exists(&path_buf))
+    }
+
+    async fn create(config: &Self::Config, namespace: &str) -> Result<(), RedbStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let path_buf = config.path_with_guard.path_buf.join(namespace);
+        if Path::exists(&path_buf) {
+            return Err(RedbStoreInternalError::StoreAlreadyExists);
+        }
+        std::fs::create_dir_all(path_buf)?;
+        Ok(())
+    }
+
+    async fn delete(config: &Self::Config, namespace: &str) -> Result<(), RedbStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let path_buf = config.path_with_guard.path_buf.join(namespace);
+        std::fs::remove_dir_all(path_buf)?;
+        Ok(())
+    }
+}
+
+#[cfg(with_testing)]
+impl TestKeyValueDatabase for RedbDatabaseInternal {
+    async fn new_test_config() -> Result<RedbStoreInternalConfig, RedbStoreInternalError> {
+        let path_with_guard = PathWithGuard::new_testing();
+        let max_stream_queries = TEST_REDB_MAX_STREAM_QUERIES;
+        Ok(RedbStoreInternalConfig {
+            path_with_guard,
+            max_stream_queries,
+        })
+    }
+}
+
+/// The error type for [`RedbStoreInternal`]
+#[derive(Error, Debug)]
+pub enum RedbStoreInternalError {
+    /// Store already exists
+    #[error("Store already exists")]
+    StoreAlreadyExists,
+
+    /// Tokio join error in redb.
+    #[error("tokio join error: {0}")]
+    TokioJoinError(#[from] tokio::task::JoinError),
+
+    /// Error when opening the redb database.
+    #[error("redb database error: {0}")]
+    Database(#[from] redb::DatabaseError),
+
+    /// Error when starting a redb transaction.
+    #[error("redb transaction error: {0}")]
+    Transaction(#[from] redb::TransactionError),
+
+    /// Error when opening the redb table.
+    #[error("redb table error: {0}")]
+    Table(#[from] redb::TableError),
+
+    /// Error when accessing the redb storage.
+    #[error("redb storage error: {0}")]
+    Storage(#[from] redb::StorageError),
+
+    /// Error when committing a redb transaction.
+    #[error("redb commit error: {0}")]
+    Commit(#[from] redb::CommitError),
+
+    /// The database contains a file which is not a directory
+    #[error("Namespaces should be directories")]
+    NonDirectoryNamespace,
+
+    /// Error converting `OsString` to `String`
+    #[error("error in the conversion from OsString: {0:?}")]
+    IntoStringError(OsString),
+
+    /// The key must have at most 8 MiB
+    #[error("The key must have at most 8 MiB")]
+    KeyTooLong,
+
+    /// Namespace contains forbidden characters
+    #[error("Namespace contains forbidden characters")]
+    InvalidNamespace,
+
+    /// Filesystem error
+    #[error("Filesystem error: {0}")]
+    FsError(#[from] std::io::Error),
+
+    /// BCS serialization error.
+    #[error(transparent)]
+    BcsError(#[from] bcs::Error),
+}
+
+impl KeyValueStoreError for RedbStoreInternalError {
+    const BACKEND: &'static str = "redb";
+}
+
+/// The composed error type for the `RedbStore`
+pub type RedbStoreError =
+    CompressingError<EncryptingError<ValueSplittingError<RedbStoreInternalError>>>;
+
+/// The composed config type for the `RedbStore`
+pub type RedbStoreConfig =
+    LruCachingConfig<CompressingConfig<EncryptingConfig<RedbStoreInternalConfig>>>;
+
+/// The `RedbDatabase` composed type with metrics
+#[cfg(with_metrics)]
+pub type RedbDatabase = MeteredDatabase<
+    LruCachingDatabase<
+        CompressingDatabase<
+            EncryptingDatabase<
+                MeteredDatabase<ValueSplittingDatabase<MeteredDatabase<RedbDatabaseInternal>>>,
+            >,
+        >,
+    >,
+>;
+/// The `RedbDatabase` composed type
+#[cfg(not(with_metrics))]
+pub type RedbDatabase = LruCachingDatabase<
+    CompressingDatabase<EncryptingDatabase<ValueSplittingDatabase<RedbDatabaseInternal>>>,
+>;
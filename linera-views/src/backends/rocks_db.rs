@@ -6,7 +6,7 @@
 use std::{
     ffi::OsString,
     fmt::Display,
-    path::PathBuf,
+    ops::Bound::{Excluded, Included, Unbounded},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -17,9 +17,9 @@ use linera_base::ensure;
 use rocksdb::{BlockBasedOptions, Cache, DBCompactionStyle, SliceTransform};
 use serde::{Deserialize, Serialize};
 use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
-use tempfile::TempDir;
 use thiserror::Error;
 
+pub use crate::common::PathWithGuard;
 #[cfg(with_metrics)]
 use crate::metering::MeteredDatabase;
 #[cfg(with_testing)]
@@ -27,9 +27,11 @@ use crate::store::TestKeyValueDatabase;
 use crate::{
     batch::{Batch, WriteOperation},
     common::get_upper_bound_option,
+    compressing::{CompressingConfig, CompressingDatabase, CompressingError},
+    encrypting::{EncryptingConfig, EncryptingDatabase, EncryptingError},
     lru_caching::{LruCachingConfig, LruCachingDatabase},
     store::{
-        KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore, WithError,
+        KeyRange, KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore, WithError,
         WritableKeyValueStore,
     },
     value_splitting::{ValueSplittingDatabase, ValueSplittingError},
@@ -222,6 +224,112 @@ impl RocksDbStoreExecutor {
         Ok(key_values)
     }
 
+    fn get_find_range_iterator(
+        &self,
+        prefix: &[u8],
+        range: &KeyRange,
+    ) -> rocksdb::DBRawIteratorWithThreadMode<DB> {
+        let mut read_opts = rocksdb::ReadOptions::default();
+        read_opts.set_async_io(true);
+        // Reverse scans may leave the prefix used by the bloom filters.
+        read_opts.set_total_order_seek(true);
+
+        // RocksDB takes an inclusive lower bound and an exclusive upper bound. The smallest
+        // key greater than `key` is `key` followed by a zero byte.
+        let (start, end) = range.bounds_for_prefix(prefix);
+        let lower_bound = match start {
+            Included(key) => key,
+            Excluded(mut key) => {
+                key.push(0);
+                key
+            }
+            Unbounded => prefix.to_vec(),
+        };
+        let upper_bound = match end {
+            Included(mut key) => {
+                key.push(0);
+                Some(key)
+            }
+            Excluded(key) => Some(key),
+            Unbounded => None,
+        };
+        read_opts.set_iterate_lower_bound(lower_bound.clone());
+        if let Some(upper_bound) = &upper_bound {
+            read_opts.set_iterate_upper_bound(upper_bound.clone());
+        }
+
+        let mut iter = self.db.raw_iterator_opt(read_opts);
+        if !range.reverse {
+            iter.seek(lower_bound);
+        } else if let Some(upper_bound) = upper_bound {
+            iter.seek_for_prev(upper_bound);
+        } else {
+            iter.seek_to_last();
+        }
+        iter
+    }
+
+    fn find_keys_by_prefix_in_range_internal(
+        &self,
+        key_prefix: Vec<u8>,
+        range: KeyRange,
+    ) -> Result<Vec<Vec<u8>>, RocksDbStoreInternalError> {
+        check_key_size(&key_prefix)?;
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut prefix = self.start_key.clone();
+        prefix.extend(key_prefix);
+        let len = prefix.len();
+
+        let mut iter = self.get_find_range_iterator(&prefix, &range);
+        let limit = range.limit.unwrap_or(usize::MAX);
+        let mut keys = Vec::new();
+        while keys.len() < limit {
+            let Some(key) = iter.key() else {
+                break;
+            };
+            keys.push(key[len..].to_vec());
+            if range.reverse {
+                iter.prev();
+            } else {
+                iter.next();
+            }
+        }
+        Ok(keys)
+    }
+
+    #[expect(clippy::type_complexity)]
+    fn find_key_values_by_prefix_in_range_internal(
+        &self,
+        key_prefix: Vec<u8>,
+        range: KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RocksDbStoreInternalError> {
+        check_key_size(&key_prefix)?;
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut prefix = self.start_key.clone();
+        prefix.extend(key_prefix);
+        let len = prefix.len();
+
+        let mut iter = self.get_find_range_iterator(&prefix, &range);
+        let limit = range.limit.unwrap_or(usize::MAX);
+        let mut key_values = Vec::new();
+        while key_values.len() < limit {
+            let Some((key, value)) = iter.item() else {
+                break;
+            };
+            key_values.push((key[len..].to_vec(), value.to_vec()));
+            if range.reverse {
+                iter.prev();
+            } else {
+                iter.next();
+            }
+        }
+        Ok(key_values)
+    }
+
     fn write_batch_internal(
         &self,
         batch: Batch,
@@ -518,6 +626,40 @@ impl ReadableKeyValueStore for RocksDbStoreInternal {
             )
             .await
     }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, RocksDbStoreInternalError> {
+        let executor = self.executor.clone();
+        let input = (key_prefix.to_vec(), range.clone());
+        self.spawn_mode
+            .spawn(
+                move |(key_prefix, range)| {
+                    executor.find_keys_by_prefix_in_range_internal(key_prefix, range)
+                },
+                input,
+            )
+            .await
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RocksDbStoreInternalError> {
+        let executor = self.executor.clone();
+        let input = (key_prefix.to_vec(), range.clone());
+        self.spawn_mode
+            .spawn(
+                move |(key_prefix, range)| {
+                    executor.find_key_values_by_prefix_in_range_internal(key_prefix, range)
+                },
+                input,
+            )
+            .await
+    }
 }
 
 impl WritableKeyValueStore for RocksDbStoreInternal {
@@ -699,59 +841,31 @@ pub enum RocksDbStoreInternalError {
     BcsError(#[from] bcs::Error),
 }
 
-/// A path and the guard for the temporary directory if needed
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct PathWithGuard {
-    /// The path to the data
-    pub path_buf: PathBuf,
-    /// The guard for the directory if one is needed
-    #[serde(skip)]
-    _dir: Option<Arc<TempDir>>,
-}
-
-impl PathWithGuard {
-    /// Creates a `PathWithGuard` from an existing path.
-    pub fn new(path_buf: PathBuf) -> Self {
-        Self {
-            path_buf,
-            _dir: None,
-        }
-    }
-
-    /// Returns the test path for RocksDB without common config.
-    #[cfg(with_testing)]
-    fn new_testing() -> PathWithGuard {
-        let dir = TempDir::new().unwrap();
-        let path_buf = dir.path().to_path_buf();
-        let _dir = Some(Arc::new(dir));
-        PathWithGuard { path_buf, _dir }
-    }
-}
-
-impl PartialEq for PathWithGuard {
-    fn eq(&self, other: &Self) -> bool {
-        self.path_buf == other.path_buf
-    }
-}
-impl Eq for PathWithGuard {}
-
 impl KeyValueStoreError for RocksDbStoreInternalError {
     const BACKEND: &'static str = "rocks_db";
 }
 
 /// The composed error type for the `RocksDbStore`
-pub type RocksDbStoreError = ValueSplittingError<RocksDbStoreInternalError>;
+pub type RocksDbStoreError =
+    CompressingError<EncryptingError<ValueSplittingError<RocksDbStoreInternalError>>>;
 
 /// The composed config type for the `RocksDbStore`
-pub type RocksDbStoreConfig = LruCachingConfig<RocksDbStoreInternalConfig>;
+pub type RocksDbStoreConfig =
+    LruCachingConfig<CompressingConfig<EncryptingConfig<RocksDbStoreInternalConfig>>>;
 
 /// The `RocksDbDatabase` composed type with metrics
 #[cfg(with_metrics)]
 pub type RocksDbDatabase = MeteredDatabase<
     LruCachingDatabase<
-        MeteredDatabase<ValueSplittingDatabase<MeteredDatabase<RocksDbDatabaseInternal>>>,
+        CompressingDatabase<
+            EncryptingDatabase<
+                MeteredDatabase<ValueSplittingDatabase<MeteredDatabase<RocksDbDatabaseInternal>>>,
+            >,
+        >,
     >,
 >;
 /// The `RocksDbDatabase` composed type
 #[cfg(not(with_metrics))]
-pub type RocksDbDatabase = LruCachingDatabase<ValueSplittingDatabase<RocksDbDatabaseInternal>>;
+pub type RocksDbDatabase = LruCachingDatabase<
+    CompressingDatabase<EncryptingDatabase<ValueSplittingDatabase<RocksDbDatabaseInternal>>>,
+>;
@@ -9,7 +9,10 @@
 
 use std::{
     collections::{BTreeSet, HashMap},
-    ops::Deref,
+    ops::{
+        Bound::{Excluded, Included, Unbounded},
+        Deref,
+    },
     sync::Arc,
 };
 
@@ -19,6 +22,7 @@ use linera_base::{ensure, util::future::FutureSyncExt as _};
 use scylla::{
     client::{
         execution_profile::{ExecutionProfile, ExecutionProfileHandle},
+        pager::QueryPager,
         session::Session,
         session_builder::SessionBuilder,
     },
@@ -44,11 +48,13 @@ use crate::store::TestKeyValueDatabase;
 use crate::{
     batch::UnorderedBatch,
     common::{get_uleb128_size, get_upper_bound_option},
+    compressing::{CompressingConfig, CompressingDatabase, CompressingError},
+    encrypting::{EncryptingConfig, EncryptingDatabase, EncryptingError},
     journaling::{JournalConsistencyError, JournalingKeyValueDatabase},
     lru_caching::{LruCachingConfig, LruCachingDatabase},
     store::{
-        DirectWritableKeyValueStore, KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore,
-        WithError,
+        DirectWritableKeyValueStore, KeyRange, KeyValueDatabase, KeyValueStoreError,
+        ReadableKeyValueStore, WithError,
     },
     value_splitting::{ValueSplittingDatabase, ValueSplittingError},
 };
@@ -115,6 +121,7 @@ struct ScyllaDbClient {
     find_key_values_by_prefix_bounded: PreparedStatement,
     multi_key_values: papaya::HashMap<usize, PreparedStatement>,
     multi_keys: papaya::HashMap<usize, PreparedStatement>,
+    range_queries: papaya::HashMap<String, PreparedStatement>,
 }
 
 impl ScyllaDbClient {
@@ -205,6 +212,7 @@ impl ScyllaDbClient {
             find_key_values_by_prefix_bounded,
             multi_key_values: papaya::HashMap::new(),
             multi_keys: papaya::HashMap::new(),
+            range_queries: papaya::HashMap::new(),
         })
     }
 
@@ -283,6 +291,20 @@ impl ScyllaDbClient {
         Ok(prepared_statement)
     }
 
+    async fn get_range_statement(
+        &self,
+        query: String,
+    ) -> Result<PreparedStatement, ScyllaDbStoreInternalError> {
+        if let Some(prepared_statement) = self.range_queries.pin().get(&query) {
+            return Ok(prepared_statement.clone());
+        };
+        let prepared_statement = self.session.prepare(query.clone()).await?;
+        self.range_queries
+            .pin()
+            .insert(query, prepared_statement.clone());
+        Ok(prepared_statement)
+    }
+
     fn check_key_size(key: &[u8]) -> Result<(), ScyllaDbStoreInternalError> {
         ensure!(
             key.len() <= MAX_KEY_SIZE,
@@ -513,6 +535,121 @@ impl ScyllaDbClient {
         }
         Ok(key_values)
     }
+
+    /// Scans the rows of the given `columns` whose keys start with `key_prefix` and lie in
+    /// `range`. The range is expected to be non-empty.
+    async fn find_range_rows(
+        &self,
+        root_key: &[u8],
+        key_prefix: &[u8],
+        range: &KeyRange,
+        columns: &str,
+    ) -> Result<QueryPager, ScyllaDbStoreInternalError> {
+        let (start, end) = range.bounds_for_prefix(key_prefix);
+        let mut query = format!(
+            "SELECT {} FROM {}.{} WHERE root_key = ?",
+            columns, KEYSPACE, self.namespace
+        );
+        let lower_bound = match start {
+            Included(key) => {
+                query.push_str(" AND k >= ?");
+                key
+            }
+            Excluded(key) => {
+                query.push_str(" AND k > ?");
+                key
+            }
+            Unbounded => unreachable!("the lower bound of a prefix is always bounded"),
+        };
+        let upper_bound = match end {
+            Included(key) => {
+                query.push_str(" AND k <= ?");
+                Some(key)
+            }
+            Excluded(key) => {
+                query.push_str(" AND k < ?");
+                Some(key)
+            }
+            Unbounded => None,
+        };
+        if range.reverse {
+            query.push_str(" ORDER BY k DESC");
+        }
+        let limit = range
+            .limit
+            .map(|limit| i32::try_from(limit).unwrap_or(i32::MAX));
+        if limit.is_some() {
+            query.push_str(" LIMIT ?");
+        }
+        let statement = self.get_range_statement(query).await?;
+        let session = &self.session;
+        let root_key = root_key.to_vec();
+        let rows = match (upper_bound, limit) {
+            (None, None) => {
+                let values = (root_key, lower_bound);
+                Box::pin(session.execute_iter(statement, values)).await?
+            }
+            (Some(upper_bound), None) => {
+                let values = (root_key, lower_bound, upper_bound);
+                Box::pin(session.execute_iter(statement, values)).await?
+            }
+            (None, Some(limit)) => {
+                let values = (root_key, lower_bound, limit);
+                Box::pin(session.execute_iter(statement, values)).await?
+            }
+            (Some(upper_bound), Some(limit)) => {
+                let values = (root_key, lower_bound, upper_bound, limit);
+                Box::pin(session.execute_iter(statement, values)).await?
+            }
+        };
+        Ok(rows)
+    }
+
+    async fn find_keys_by_prefix_in_range_internal(
+        &self,
+        root_key: &[u8],
+        key_prefix: Vec<u8>,
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, ScyllaDbStoreInternalError> {
+        Self::check_key_size(&key_prefix)?;
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let len = key_prefix.len();
+        let rows = self
+            .find_range_rows(root_key, &key_prefix, range, "k")
+            .await?;
+        let mut rows = rows.rows_stream::<(Vec<u8>,)>()?;
+        let mut keys = Vec::new();
+        while let Some(row) = rows.next().await {
+            let (key,) = row?;
+            keys.push(key[len..].to_vec());
+        }
+        Ok(keys)
+    }
+
+    async fn find_key_values_by_prefix_in_range_internal(
+        &self,
+        root_key: &[u8],
+        key_prefix: Vec<u8>,
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, ScyllaDbStoreInternalError> {
+        Self::check_key_size(&key_prefix)?;
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let len = key_prefix.len();
+        let rows = self
+            .find_range_rows(root_key, &key_prefix, range, "k,v")
+            .await?;
+        let mut rows = rows.rows_stream::<(Vec<u8>, Vec<u8>)>()?;
+        let mut key_values = Vec::new();
+        while let Some(row) = rows.next().await {
+            let (key, value) = row?;
+            key_values.push((key[len..].to_vec(), value));
+        }
+        Ok(key_values)
+    }
 }
 
 /// The client itself and the keeping of the count of active connections.
@@ -690,6 +827,36 @@ impl ReadableKeyValueStore for ScyllaDbStoreInternal {
         Box::pin(store.find_key_values_by_prefix_internal(&self.root_key, key_prefix.to_vec()))
             .await
     }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, ScyllaDbStoreInternalError> {
+        let store = self.store.deref();
+        let _guard = self.acquire().await;
+        Box::pin(store.find_keys_by_prefix_in_range_internal(
+            &self.root_key,
+            key_prefix.to_vec(),
+            range,
+        ))
+        .await
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, ScyllaDbStoreInternalError> {
+        let store = self.store.deref();
+        let _guard = self.acquire().await;
+        Box::pin(store.find_key_values_by_prefix_in_range_internal(
+            &self.root_key,
+            key_prefix.to_vec(),
+            range,
+        ))
+        .await
+    }
 }
 
 impl DirectWritableKeyValueStore for ScyllaDbStoreInternal {
@@ -1001,9 +1168,13 @@ impl TestKeyValueDatabase for JournalingKeyValueDatabase<ScyllaDbDatabaseInterna
 #[cfg(with_metrics)]
 pub type ScyllaDbDatabase = MeteredDatabase<
     LruCachingDatabase<
-        MeteredDatabase<
-            ValueSplittingDatabase<
-                MeteredDatabase<JournalingKeyValueDatabase<ScyllaDbDatabaseInternal>>,
+        CompressingDatabase<
+            EncryptingDatabase<
+                MeteredDatabase<
+                    ValueSplittingDatabase<
+                        MeteredDatabase<JournalingKeyValueDatabase<ScyllaDbDatabaseInternal>>,
+                    >,
+                >,
             >,
         >,
     >,
@@ -1012,11 +1183,17 @@ pub type ScyllaDbDatabase = MeteredDatabase<
 /// The `ScyllaDbDatabase` composed type
 #[cfg(not(with_metrics))]
 pub type ScyllaDbDatabase = LruCachingDatabase<
-    ValueSplittingDatabase<JournalingKeyValueDatabase<ScyllaDbDatabaseInternal>>,
+    CompressingDatabase<
+        EncryptingDatabase<
+            ValueSplittingDatabase<JournalingKeyValueDatabase<ScyllaDbDatabaseInternal>>,
+        >,
+    >,
 >;
 
 /// The `ScyllaDbStoreConfig` input type
-pub type ScyllaDbStoreConfig = LruCachingConfig<ScyllaDbStoreInternalConfig>;
+pub type ScyllaDbStoreConfig =
+    LruCachingConfig<CompressingConfig<EncryptingConfig<ScyllaDbStoreInternalConfig>>>;
 
 /// The combined error type for the `ScyllaDbDatabase`.
-pub type ScyllaDbStoreError = ValueSplittingError<ScyllaDbStoreInternalError>;
+pub type ScyllaDbStoreError =
+    CompressingError<EncryptingError<ValueSplittingError<ScyllaDbStoreInternalError>>>;
@@ -9,7 +9,7 @@ use thiserror::Error;
 use crate::{
     batch::{Batch, WriteOperation},
     store::{
-        KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore, WithError,
+        KeyRange, KeyValueDatabase, KeyValueStoreError, ReadableKeyValueStore, WithError,
         WritableKeyValueStore,
     },
 };
@@ -245,6 +245,99 @@ where
         }
         Ok(key_values)
     }
+
+    // The first segment of a key `K` is stored at `K ++ [0, 0, 0, 0]`, so the range over the
+    // keys is the range over the first segments. Since the other segments are also scanned
+    // and count towards the limit, the scan is repeated until enough keys are found.
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        let mut keys = Vec::new();
+        let mut big_range = range.with_suffix(&[0, 0, 0, 0]);
+        loop {
+            big_range.limit = range.limit.map(|limit| limit - keys.len());
+            let big_keys = self
+                .store
+                .find_keys_by_prefix_in_range(key_prefix, &big_range)
+                .await?;
+            let exhausted = big_range.limit.is_none_or(|limit| big_keys.len() < limit);
+            for big_key in &big_keys {
+                if Self::read_index_from_key(big_key)? == 0 {
+                    keys.push(big_key[0..big_key.len() - 4].to_vec());
+                }
+            }
+            match big_keys.last() {
+                Some(last_key) if !exhausted && range.limit != Some(keys.len()) => {
+                    big_range = big_range.after(last_key);
+                }
+                _ => break,
+            }
+        }
+        Ok(keys)
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+        let mut key_values = Vec::new();
+        let mut counts = Vec::new();
+        let mut segment_keys = Vec::new();
+        let mut big_range = range.with_suffix(&[0, 0, 0, 0]);
+        loop {
+            big_range.limit = range.limit.map(|limit| limit - key_values.len());
+            let small_key_values = self
+                .store
+                .find_key_values_by_prefix_in_range(key_prefix, &big_range)
+                .await?;
+            let exhausted = big_range
+                .limit
+                .is_none_or(|limit| small_key_values.len() < limit);
+            let last_key = small_key_values.last().map(|(big_key, _)| big_key.clone());
+            for (mut big_key, value) in small_key_values {
+                if Self::read_index_from_key(&big_key)? != 0 {
+                    continue;
+                }
+                big_key.truncate(big_key.len() - 4);
+                let count = Self::read_count_from_value(&value)?;
+                if count > 1 {
+                    let full_key = [key_prefix, &big_key].concat();
+                    for idx in 1..count {
+                        segment_keys.push(Self::get_segment_key(&full_key, idx)?);
+                    }
+                }
+                counts.push(count);
+                key_values.push((big_key, value[4..].to_vec()));
+            }
+            match last_key {
+                Some(last_key) if !exhausted && range.limit != Some(key_values.len()) => {
+                    big_range = big_range.after(&last_key);
+                }
+                _ => break,
+            }
+        }
+        if !segment_keys.is_empty() {
+            // The missing segments are listed in the order of `key_values`.
+            let mut segments = self
+                .store
+                .read_multi_values_bytes(&segment_keys)
+                .await?
+                .into_iter();
+            for ((_, big_value), count) in key_values.iter_mut().zip(counts) {
+                for _ in 1..count {
+                    let segment = segments
+                        .next()
+                        .flatten()
+                        .ok_or(ValueSplittingError::MissingSegment)?;
+                    big_value.extend(segment);
+                }
+            }
+        }
+        Ok(key_values)
+    }
 }
 
 impl<K> WritableKeyValueStore for ValueSplittingStore<K>
@@ -468,6 +561,26 @@ impl ReadableKeyValueStore for LimitedTestMemoryStore {
     ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, MemoryStoreError> {
         self.inner.find_key_values_by_prefix(key_prefix).await
     }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, MemoryStoreError> {
+        self.inner
+            .find_keys_by_prefix_in_range(key_prefix, range)
+            .await
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, MemoryStoreError> {
+        self.inner
+            .find_key_values_by_prefix_in_range(key_prefix, range)
+            .await
+    }
 }
 
 #[cfg(with_testing)]
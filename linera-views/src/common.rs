@@ -4,7 +4,10 @@
 //! This provides some common code for the linera-views.
 
 use std::{
-    collections::BTreeSet,
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{btree_map, BTreeMap, BTreeSet, VecDeque},
+    iter::{Peekable, Rev},
     ops::{
         Bound,
         Bound::{Excluded, Included, Unbounded},
@@ -13,10 +16,14 @@ use std::{
 };
 
 use allocative::Allocative;
+use futures::{stream, Stream};
 use itertools::Either;
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Serialize};
 
-use crate::ViewError;
+use crate::{
+    store::{KeyRange, ReadableKeyValueStore, STREAM_PAGE_SIZE},
+    ViewError,
+};
 
 type HasherOutputSize = <sha3::Sha3_256 as sha3::digest::OutputSizeUser>::OutputSize;
 #[doc(hidden)]
@@ -203,6 +210,190 @@ pub(crate) fn insert_key_prefix(prefixes: &mut BTreeSet<Vec<u8>>, prefix: Vec<u8
     }
 }
 
+/// Whether we have a value or its serialization.
+pub(crate) enum ValueOrBytes<'a, T> {
+    /// The value itself.
+    Value(&'a T),
+    /// The serialization.
+    Bytes(Vec<u8>),
+}
+
+impl<'a, T> ValueOrBytes<'a, T>
+where
+    T: Clone + DeserializeOwned,
+{
+    /// Convert to a Cow.
+    pub(crate) fn to_value(&self) -> Result<Cow<'a, T>, ViewError> {
+        match self {
+            ValueOrBytes::Value(value) => Ok(Cow::Borrowed(value)),
+            ValueOrBytes::Bytes(bytes) => Ok(Cow::Owned(bcs::from_bytes(bytes)?)),
+        }
+    }
+}
+
+impl<T> ValueOrBytes<'_, T>
+where
+    T: Serialize,
+{
+    /// Convert to bytes.
+    pub(crate) fn into_bytes(self) -> Result<Vec<u8>, ViewError> {
+        match self {
+            ValueOrBytes::Value(value) => Ok(bcs::to_bytes(value)?),
+            ValueOrBytes::Bytes(bytes) => Ok(bytes),
+        }
+    }
+}
+
+/// The iterator over the pending updates of a view, in either direction.
+type PendingUpdates<'a, T> = Peekable<
+    Either<Rev<btree_map::Range<'a, Vec<u8>, Update<T>>>, btree_map::Range<'a, Vec<u8>, Update<T>>>,
+>;
+
+/// The state of a merge between the pending updates of a view and the entries of its
+/// storage, see [`merged_entries_stream`].
+struct MergedEntries<'a, S, T, F> {
+    store: &'a S,
+    key_prefix: Vec<u8>,
+    updates: PendingUpdates<'a, T>,
+    is_deleted: F,
+    with_values: bool,
+    reverse: bool,
+    limit: usize,
+    count: usize,
+    stored: VecDeque<(Vec<u8>, Option<Vec<u8>>)>,
+    stored_range: KeyRange,
+    stored_exhausted: bool,
+}
+
+impl<'a, S, T, F> MergedEntries<'a, S, T, F>
+where
+    S: ReadableKeyValueStore,
+    F: Fn(&[u8]) -> bool,
+{
+    /// Reads the next page of entries from the storage.
+    async fn read_stored_page(&mut self) -> Result<(), ViewError> {
+        let page_size = (self.limit - self.count).min(STREAM_PAGE_SIZE);
+        let page = self.stored_range.clone().with_limit(page_size);
+        let entries = if self.with_values {
+            self.store
+                .find_key_values_by_prefix_in_range(&self.key_prefix, &page)
+                .await?
+                .into_iter()
+                .map(|(key, bytes)| (key, Some(bytes)))
+                .collect::<Vec<_>>()
+        } else {
+            self.store
+                .find_keys_by_prefix_in_range(&self.key_prefix, &page)
+                .await?
+                .into_iter()
+                .map(|key| (key, None))
+                .collect()
+        };
+        self.stored_exhausted = entries.len() < page_size;
+        if let Some((key, _)) = entries.last() {
+            self.stored_range = self.stored_range.after(key);
+        }
+        self.stored.extend(entries);
+        Ok(())
+    }
+
+    /// Returns the next entry of the merge, if any.
+    async fn next_entry(
+        &mut self,
+    ) -> Result<Option<(Vec<u8>, Option<ValueOrBytes<'a, T>>)>, ViewError> {
+        while self.count < self.limit {
+            if self.stored.is_empty() && !self.stored_exhausted {
+                self.read_stored_page().await?;
+            }
+            // The pending update of a key takes precedence over its stored value.
+            let order = match (self.updates.peek(), self.stored.front()) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((update_key, _)), Some((stored_key, _))) => {
+                    let order = update_key.as_slice().cmp(stored_key.as_slice());
+                    if self.reverse {
+                        order.reverse()
+                    } else {
+                        order
+                    }
+                }
+            };
+            if order == Ordering::Greater {
+                let (key, bytes) = self.stored.pop_front().unwrap();
+                if !(self.is_deleted)(&key) {
+                    self.count += 1;
+                    return Ok(Some((key, bytes.map(ValueOrBytes::Bytes))));
+                }
+            } else {
+                if order == Ordering::Equal {
+                    self.stored.pop_front();
+                }
+                let (key, update) = self.updates.next().unwrap();
+                if let Update::Set(value) = update {
+                    self.count += 1;
+                    let value = self.with_values.then_some(ValueOrBytes::Value(value));
+                    return Ok(Some((key.clone(), value)));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Streams the entries of a view lying in `range`, in the order and up to the limit given
+/// by the range, together with their values if `with_values` is set.
+///
+/// The pending `updates` are merged with the entries stored under `key_prefix`. A stored
+/// key is skipped if `is_deleted` holds for it, and the storage is not read at all if
+/// `is_deleted` holds for the empty key. The storage is read by pages of
+/// [`STREAM_PAGE_SIZE`] entries, and only when the stream is polled.
+pub(crate) fn merged_entries_stream<'a, S, T, F>(
+    store: &'a S,
+    key_prefix: Vec<u8>,
+    range: KeyRange,
+    updates: &'a BTreeMap<Vec<u8>, Update<T>>,
+    is_deleted: F,
+    with_values: bool,
+) -> impl Stream<Item = Result<(Vec<u8>, Option<ValueOrBytes<'a, T>>), ViewError>> + 'a
+where
+    S: ReadableKeyValueStore,
+    T: 'a,
+    F: Fn(&[u8]) -> bool + 'a,
+{
+    let (limit, bounds) = if range.is_empty() {
+        // `BTreeMap::range` panics on some empty ranges, so we do not use their bounds.
+        (0, (Unbounded, Unbounded))
+    } else {
+        let limit = range.limit.unwrap_or(usize::MAX);
+        (limit, (range.start.clone(), range.end.clone()))
+    };
+    let updates = updates.range(bounds);
+    let updates = if range.reverse {
+        Either::Left(updates.rev())
+    } else {
+        Either::Right(updates)
+    }
+    .peekable();
+    let state = MergedEntries {
+        store,
+        key_prefix,
+        updates,
+        stored_exhausted: is_deleted(&[]),
+        is_deleted,
+        with_values,
+        reverse: range.reverse,
+        limit,
+        count: 0,
+        stored: VecDeque::new(),
+        stored_range: range,
+    };
+    stream::try_unfold(state, |mut state| async move {
+        let entry = state.next_entry().await?;
+        Ok::<_, ViewError>(entry.map(|entry| (entry, state)))
+    })
+}
+
 #[test]
 fn suffix_closed_set_test1_the_lower_bound() {
     let mut set = BTreeSet::<Vec<u8>>::new();
@@ -341,6 +532,47 @@ impl<T> SliceExt<T> for [T] {
     }
 }
 
+/// A path and the guard for the temporary directory if needed
+#[cfg(any(with_rocksdb, with_redb))]
+#[derive(Clone, Debug, serde::Deserialize, Serialize)]
+pub struct PathWithGuard {
+    /// The path to the data
+    pub path_buf: std::path::PathBuf,
+    /// The guard for the directory if one is needed
+    #[serde(skip)]
+    _dir: Option<std::sync::Arc<tempfile::TempDir>>,
+}
+
+#[cfg(any(with_rocksdb, with_redb))]
+impl PathWithGuard {
+    /// Creates a `PathWithGuard` from an existing path.
+    pub fn new(path_buf: std::path::PathBuf) -> Self {
+        Self {
+            path_buf,
+            _dir: None,
+        }
+    }
+
+    /// Returns a path to a fresh temporary directory, removed once the last clone is dropped.
+    #[cfg(with_testing)]
+    pub(crate) fn new_testing() -> PathWithGuard {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path_buf = dir.path().to_path_buf();
+        let _dir = Some(std::sync::Arc::new(dir));
+        PathWithGuard { path_buf, _dir }
+    }
+}
+
+#[cfg(any(with_rocksdb, with_redb))]
+impl PartialEq for PathWithGuard {
+    fn eq(&self, other: &Self) -> bool {
+        self.path_buf == other.path_buf
+    }
+}
+
+#[cfg(any(with_rocksdb, with_redb))]
+impl Eq for PathWithGuard {}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeSet;
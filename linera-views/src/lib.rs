@@ -19,6 +19,7 @@ The databases supported are of the NoSQL variety and they are key-value stores.
 We provide support for the following databases:
 * `MemoryDatabase` is using the memory
 * `RocksDbDatabase` is a disk-based key-value store
+* `RedbDatabase` is a disk-based key-value store over the pure-Rust embedded database redb.
 * `DynamoDbDatabase` is the AWS-based DynamoDB service.
 * `ScyllaDbDatabase` is a cloud-based Cassandra-compatible database.
 * `StorageServiceDatabase` is a gRPC-based storage that uses either memory or RocksDB. It is available in `linera-storage-service`.
@@ -102,12 +103,18 @@ pub mod random;
 #[cfg(with_testing)]
 pub mod test_utils;
 
+#[cfg(with_compression)]
+pub use backends::compressing;
 #[cfg(with_dynamodb)]
 pub use backends::dynamo_db;
+#[cfg(with_encryption)]
+pub use backends::encrypting;
 #[cfg(with_indexeddb)]
 pub use backends::indexed_db;
 #[cfg(with_metrics)]
 pub use backends::metering;
+#[cfg(with_redb)]
+pub use backends::redb;
 #[cfg(with_rocksdb)]
 pub use backends::rocks_db;
 #[cfg(with_scylladb)]
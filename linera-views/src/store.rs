@@ -3,15 +3,23 @@
 
 //! This provides the trait definitions for the stores.
 
-use std::{fmt::Debug, future::Future};
+use std::{
+    fmt::Debug,
+    future::Future,
+    ops::{
+        Bound::{self, Excluded, Included, Unbounded},
+        RangeBounds,
+    },
+};
 
+use futures::{stream, Stream, TryStreamExt as _};
 use serde::{de::DeserializeOwned, Serialize};
 
 #[cfg(with_testing)]
 use crate::random::generate_test_namespace;
 use crate::{
     batch::{Batch, SimplifiedBatch},
-    common::from_bytes_option,
+    common::{from_bytes_option, get_upper_bound},
     ViewError,
 };
 
@@ -38,6 +46,197 @@ pub trait WithError {
     type Error: KeyValueStoreError;
 }
 
+/// The number of entries read at once from storage by the streaming scans.
+pub const STREAM_PAGE_SIZE: usize = 1000;
+
+/// A bounded range of keys to be scanned below a key prefix, in either direction and with
+/// an optional limit on the number of entries.
+///
+/// The bounds apply to the keys with the prefix removed, that is to the keys as they are
+/// returned by the scan.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyRange {
+    /// The lower bound of the keys.
+    pub start: Bound<Vec<u8>>,
+    /// The upper bound of the keys.
+    pub end: Bound<Vec<u8>>,
+    /// Whether the keys are returned in decreasing order.
+    pub reverse: bool,
+    /// The maximal number of entries to return.
+    pub limit: Option<usize>,
+}
+
+impl Default for KeyRange {
+    fn default() -> Self {
+        Self::full()
+    }
+}
+
+impl KeyRange {
+    /// The range of all the keys, in increasing order and without limit.
+    pub fn full() -> Self {
+        Self {
+            start: Unbounded,
+            end: Unbounded,
+            reverse: false,
+            limit: None,
+        }
+    }
+
+    /// The keys within the given bounds, in increasing order and without limit.
+    pub fn new(range: impl RangeBounds<Vec<u8>>) -> Self {
+        Self {
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+            reverse: false,
+            limit: None,
+        }
+    }
+
+    /// The keys starting with `prefix`, in increasing order and without limit.
+    pub fn prefix(prefix: &[u8]) -> Self {
+        Self {
+            start: Included(prefix.to_vec()),
+            end: get_upper_bound(prefix),
+            reverse: false,
+            limit: None,
+        }
+    }
+
+    /// The keys within the given bounds, once serialized by `serialize`. The serialization
+    /// is expected to be injective.
+    pub(crate) fn try_from_bounds<Q: ?Sized, E>(
+        range: &impl RangeBounds<Q>,
+        serialize: impl Fn(&Q) -> Result<Vec<u8>, E>,
+    ) -> Result<Self, E> {
+        let serialize_bound = |bound: Bound<&Q>| -> Result<Bound<Vec<u8>>, E> {
+            Ok(match bound {
+                Included(index) => Included(serialize(index)?),
+                Excluded(index) => Excluded(serialize(index)?),
+                Unbounded => Unbounded,
+            })
+        };
+        Ok(Self {
+            start: serialize_bound(range.start_bound())?,
+            end: serialize_bound(range.end_bound())?,
+            reverse: false,
+            limit: None,
+        })
+    }
+
+    /// Returns the same range, scanned in decreasing order.
+    pub fn rev(mut self) -> Self {
+        self.reverse = !self.reverse;
+        self
+    }
+
+    /// Returns the same range, stopping after `limit` entries.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Tests whether the range cannot contain any entry.
+    pub fn is_empty(&self) -> bool {
+        if self.limit == Some(0) {
+            return true;
+        }
+        match (&self.start, &self.end) {
+            (Included(start), Included(end)) => start > end,
+            (Included(start) | Excluded(start), Included(end) | Excluded(end)) => start >= end,
+            _ => false,
+        }
+    }
+
+    /// Tests whether `key` lies within the bounds of the range.
+    pub fn contains(&self, key: &[u8]) -> bool {
+        let after_start = match &self.start {
+            Included(start) => key >= start.as_slice(),
+            Excluded(start) => key > start.as_slice(),
+            Unbounded => true,
+        };
+        let before_end = match &self.end {
+            Included(end) => key <= end.as_slice(),
+            Excluded(end) => key < end.as_slice(),
+            Unbounded => true,
+        };
+        after_start && before_end
+    }
+
+    /// Returns the rest of the range once the scan has reached `key`. Direction and limit
+    /// are unchanged.
+    pub fn after(&self, key: &[u8]) -> Self {
+        let mut range = self.clone();
+        if self.reverse {
+            range.end = Excluded(key.to_vec());
+        } else {
+            range.start = Excluded(key.to_vec());
+        }
+        range
+    }
+
+    /// Returns the same range with `suffix` appended to the bounds. Appending the same
+    /// suffix of zero bytes to all keys preserves their order, so the extended keys in the
+    /// returned range are exactly the extensions of the keys in `self`.
+    pub(crate) fn with_suffix(&self, suffix: &[u8]) -> Self {
+        let extend = |bound: &Bound<Vec<u8>>| {
+            bound.as_ref().map(|key| {
+                let mut key = key.clone();
+                key.extend_from_slice(suffix);
+                key
+            })
+        };
+        Self {
+            start: extend(&self.start),
+            end: extend(&self.end),
+            reverse: self.reverse,
+            limit: self.limit,
+        }
+    }
+
+    /// The bounds of the range over the full keys starting with `key_prefix`.
+    pub fn bounds_for_prefix(&self, key_prefix: &[u8]) -> (Bound<Vec<u8>>, Bound<Vec<u8>>) {
+        let full_key = |key: &Vec<u8>| {
+            let mut full_key = key_prefix.to_vec();
+            full_key.extend_from_slice(key);
+            full_key
+        };
+        let start = match &self.start {
+            Unbounded => Included(key_prefix.to_vec()),
+            bound => bound.as_ref().map(full_key),
+        };
+        let end = match &self.end {
+            Unbounded => get_upper_bound(key_prefix),
+            bound => bound.as_ref().map(full_key),
+        };
+        (start, end)
+    }
+
+    /// Takes the entries of a scan in increasing order, following the direction and the
+    /// limit of the range. The bounds are not checked.
+    pub fn take_from<I: DoubleEndedIterator>(&self, entries: I) -> Vec<I::Item> {
+        let limit = self.limit.unwrap_or(usize::MAX);
+        if self.reverse {
+            entries.rev().take(limit).collect()
+        } else {
+            entries.take(limit).collect()
+        }
+    }
+
+    /// Selects the keys in the range among keys given in increasing order.
+    pub fn select_keys(&self, keys: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        self.take_from(keys.into_iter().filter(|key| self.contains(key)))
+    }
+
+    /// Selects the entries in the range among entries given in increasing order of keys.
+    pub fn select_key_values(
+        &self,
+        key_values: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.take_from(key_values.into_iter().filter(|(key, _)| self.contains(key)))
+    }
+}
+
 /// Asynchronous read key-value operations.
 #[cfg_attr(not(web), trait_variant::make(Send + Sync))]
 pub trait ReadableKeyValueStore: WithError {
@@ -74,6 +273,90 @@ pub trait ReadableKeyValueStore: WithError {
         key_prefix: &[u8],
     ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error>;
 
+    /// Finds the keys matching the prefix and lying in `range`, in the order and up to the
+    /// limit given by `range`. The prefix is not included in the returned keys.
+    ///
+    /// The default implementation filters the result of `find_keys_by_prefix`. Backends
+    /// override it so that only the range is scanned.
+    fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> impl Future<Output = Result<Vec<Vec<u8>>, Self::Error>> {
+        async move {
+            if range.is_empty() {
+                return Ok(Vec::new());
+            }
+            let keys = self.find_keys_by_prefix(key_prefix).await?;
+            Ok(range.select_keys(keys))
+        }
+    }
+
+    /// Finds the `(key,value)` pairs matching the prefix and lying in `range`, in the order
+    /// and up to the limit given by `range`. The prefix is not included in the returned keys.
+    ///
+    /// The default implementation filters the result of `find_key_values_by_prefix`.
+    /// Backends override it so that only the range is scanned.
+    #[expect(clippy::type_complexity)]
+    fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> impl Future<Output = Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error>> {
+        async move {
+            if range.is_empty() {
+                return Ok(Vec::new());
+            }
+            let key_values = self.find_key_values_by_prefix(key_prefix).await?;
+            Ok(range.select_key_values(key_values))
+        }
+    }
+
+    /// Streams the keys matching the prefix in increasing order. The prefix is not
+    /// included in the returned keys.
+    ///
+    /// The keys are read by pages of [`STREAM_PAGE_SIZE`] entries with
+    /// `find_keys_by_prefix_in_range`, and the next page is only read once the stream is
+    /// polled past the current one. Stores without range scans override it, since every
+    /// page would read the whole prefix again.
+    fn find_keys_by_prefix_stream(
+        &self,
+        key_prefix: &[u8],
+    ) -> impl Stream<Item = Result<Vec<u8>, Self::Error>> {
+        let key_prefix = key_prefix.to_vec();
+        stream_pages(
+            move |range| {
+                let key_prefix = key_prefix.clone();
+                async move { self.find_keys_by_prefix_in_range(&key_prefix, &range).await }
+            },
+            |key| key,
+        )
+    }
+
+    /// Streams the `(key,value)` pairs matching the prefix in increasing order of keys.
+    /// The prefix is not included in the returned keys.
+    ///
+    /// The entries are read by pages of [`STREAM_PAGE_SIZE`] entries with
+    /// `find_key_values_by_prefix_in_range`, and the next page is only read once the
+    /// stream is polled past the current one. Stores without range scans override it, since
+    /// every page would read the whole prefix again.
+    fn find_key_values_by_prefix_stream(
+        &self,
+        key_prefix: &[u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), Self::Error>> {
+        let key_prefix = key_prefix.to_vec();
+        stream_pages(
+            move |range| {
+                let key_prefix = key_prefix.clone();
+                async move {
+                    self.find_key_values_by_prefix_in_range(&key_prefix, &range)
+                        .await
+                }
+            },
+            |(key, _)| key,
+        )
+    }
+
     // We can't use `async fn` here in the below implementations due to
     // https://github.com/rust-lang/impl-trait-utils/issues/17, but once that bug is fixed
     // we can revert them to `async fn` syntax, which is neater.
@@ -101,6 +384,38 @@ pub trait ReadableKeyValueStore: WithError {
     }
 }
 
+/// Streams the entries returned by `read_page` for consecutive pages of
+/// [`STREAM_PAGE_SIZE`] entries, each page starting after the last key of the previous one.
+fn stream_pages<T, E, F, Fut>(
+    mut read_page: F,
+    key_of: fn(&T) -> &Vec<u8>,
+) -> impl Stream<Item = Result<T, E>>
+where
+    F: FnMut(KeyRange) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, E>>,
+{
+    stream::try_unfold(Some(KeyRange::full()), move |range| {
+        let page = range
+            .as_ref()
+            .map(|range| read_page(range.clone().with_limit(STREAM_PAGE_SIZE)));
+        async move {
+            let (Some(range), Some(page)) = (range, page) else {
+                return Ok(None);
+            };
+            let entries = page.await?;
+            let next_range = match entries.last() {
+                Some(entry) if entries.len() == STREAM_PAGE_SIZE => {
+                    Some(range.after(key_of(entry)))
+                }
+                _ => None,
+            };
+            let entries = stream::iter(entries.into_iter().map(Ok::<T, E>));
+            Ok::<_, E>(Some((entries, next_range)))
+        }
+    })
+    .try_flatten()
+}
+
 /// Asynchronous write key-value operations.
 #[cfg_attr(not(web), trait_variant::make(Send + Sync))]
 pub trait WritableKeyValueStore: WithError {
@@ -7,8 +7,12 @@ pub mod test_views;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod performance;
 
-use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    ops::Bound::{Excluded, Included, Unbounded},
+};
 
+use futures::TryStreamExt as _;
 use rand::{seq::SliceRandom, Rng};
 
 use crate::{
@@ -18,7 +22,7 @@ use crate::{
     },
     random::{generate_test_namespace, make_deterministic_rng, make_nondeterministic_rng},
     store::{
-        KeyValueDatabase, KeyValueStore, ReadableKeyValueStore, TestKeyValueDatabase,
+        KeyRange, KeyValueDatabase, KeyValueStore, ReadableKeyValueStore, TestKeyValueDatabase,
         WritableKeyValueStore,
     },
 };
@@ -163,6 +167,9 @@ pub fn span_random_reordering_put_delete<R: Rng>(
 /// * `read_multi_values_bytes`
 /// * `find_keys_by_prefix` / `find_key_values_by_prefix`
 /// * The ordering of keys returned by `find_keys_by_prefix` and `find_key_values_by_prefix`
+/// * `find_keys_by_prefix_in_range` / `find_key_values_by_prefix_in_range` for bounded,
+///   reversed and limited ranges
+/// * `find_keys_by_prefix_stream` / `find_key_values_by_prefix_stream`
 pub async fn run_reads<S: KeyValueStore>(store: S, key_values: Vec<(Vec<u8>, Vec<u8>)>) {
     // We need a nontrivial key_prefix because dynamo requires a non-trivial prefix
     let mut batch = Batch::new();
@@ -203,8 +210,61 @@ pub async fn run_reads<S: KeyValueStore>(store: S, key_values: Vec<(Vec<u8>, Vec
         }
         assert_eq!(set_key_value1, set_key_value2);
     }
-    // Now checking the read_multi_values_bytes
     let mut rng = make_deterministic_rng();
+    // Now checking the range scans against the full prefix scans
+    let range_prefixes = keys
+        .iter()
+        .filter_map(|key| key.get(..1))
+        .collect::<BTreeSet<_>>();
+    for key_prefix in range_prefixes {
+        let keys_by_prefix = store.find_keys_by_prefix(key_prefix).await.unwrap();
+        let key_values_by_prefix = store.find_key_values_by_prefix(key_prefix).await.unwrap();
+        let keys_stream = store.find_keys_by_prefix_stream(key_prefix);
+        assert_eq!(
+            keys_stream.try_collect::<Vec<_>>().await.unwrap(),
+            keys_by_prefix
+        );
+        let key_values_stream = store.find_key_values_by_prefix_stream(key_prefix);
+        assert_eq!(
+            key_values_stream.try_collect::<Vec<_>>().await.unwrap(),
+            key_values_by_prefix
+        );
+        if keys_by_prefix.is_empty() {
+            continue;
+        }
+        let mut bound = || keys_by_prefix[rng.gen_range(0..keys_by_prefix.len())].clone();
+        let (lower, upper) = (bound(), bound());
+        let ranges = vec![
+            KeyRange::full(),
+            KeyRange::full().rev(),
+            KeyRange::full().with_limit(2),
+            KeyRange::full().rev().with_limit(2),
+            KeyRange::full().with_limit(0),
+            KeyRange::new((Included(lower.clone()), Excluded(upper.clone()))),
+            KeyRange::new((Excluded(lower.clone()), Included(upper.clone()))).rev(),
+            KeyRange::new((Excluded(lower.clone()), Unbounded)).with_limit(3),
+            KeyRange::new((Unbounded, Excluded(upper.clone())))
+                .rev()
+                .with_limit(3),
+            KeyRange::new((Included(upper), Included(lower))),
+        ];
+        for range in ranges {
+            let keys = store
+                .find_keys_by_prefix_in_range(key_prefix, &range)
+                .await
+                .unwrap();
+            assert_eq!(keys, range.select_keys(keys_by_prefix.clone()));
+            let key_values = store
+                .find_key_values_by_prefix_in_range(key_prefix, &range)
+                .await
+                .unwrap();
+            assert_eq!(
+                key_values,
+                range.select_key_values(key_values_by_prefix.clone())
+            );
+        }
+    }
+    // Now checking the read_multi_values_bytes
     for _ in 0..3 {
         let mut keys = Vec::new();
         let mut values = Vec::new();
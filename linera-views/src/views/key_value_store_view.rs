@@ -16,6 +16,7 @@
 use std::{collections::BTreeMap, fmt::Debug, ops::Bound::Included, sync::Mutex};
 
 use allocative::Allocative;
+use futures::{Stream, TryStreamExt as _};
 #[cfg(with_metrics)]
 use linera_base::prometheus_util::MeasureLatency as _;
 use linera_base::{data_types::ArithmeticError, ensure, visit_allocative_simple};
@@ -25,13 +26,14 @@ use crate::{
     batch::{Batch, WriteOperation},
     common::{
         from_bytes_option, from_bytes_option_or_default, get_key_range_for_prefix, get_upper_bound,
-        DeletionSet, HasherOutput, SuffixClosedSetIterator, Update,
+        merged_entries_stream, DeletionSet, HasherOutput, SuffixClosedSetIterator, Update,
+        ValueOrBytes,
     },
     context::Context,
     hashable_wrapper::WrappedHashableContainerView,
     historical_hash_wrapper::HistoricallyHashableView,
     map_view::ByteMapView,
-    store::ReadableKeyValueStore,
+    store::{KeyRange, ReadableKeyValueStore},
     views::{ClonableView, HashableView, Hasher, ReplaceContext, View, ViewError, MIN_VIEW_TAG},
 };
 
@@ -1163,6 +1165,157 @@ impl<C: Context> KeyValueStoreView<C> {
         Ok(key_values)
     }
 
+    /// Streams the entries whose keys match the given prefix and lie in `range`, together
+    /// with their values if `with_values` is set. The prefix is not included in the
+    /// returned keys.
+    #[expect(clippy::type_complexity)]
+    fn entries_stream(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+        with_values: bool,
+    ) -> Result<impl Stream<Item = Result<(Vec<u8>, Option<Vec<u8>>), ViewError>> + '_, ViewError>
+    {
+        ensure!(
+            key_prefix.len() <= self.max_key_size(),
+            ViewError::KeyTooLong
+        );
+        let len = key_prefix.len();
+        let (start, end) = range.bounds_for_prefix(key_prefix);
+        let range = KeyRange {
+            start,
+            end,
+            ..range.clone()
+        };
+        let entries = merged_entries_stream(
+            self.context.store(),
+            self.context.base_key().base_tag(KeyTag::Index as u8),
+            range,
+            &self.updates,
+            |index| self.deletion_set.contains_prefix_of(index),
+            with_values,
+        );
+        Ok(entries.map_ok(move |(index, value)| {
+            let value = value.map(|value| match value {
+                ValueOrBytes::Value(value) => value.clone(),
+                ValueOrBytes::Bytes(bytes) => bytes,
+            });
+            (index[len..].to_vec(), value)
+        }))
+    }
+
+    /// Streams the keys matching the given prefix in increasing order. The prefix is not
+    /// included in the returned keys. The keys of the storage are read page by page as
+    /// the stream is polled, so that large views can be iterated in bounded memory.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use futures::TryStreamExt as _;
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::key_value_store_view::KeyValueStoreView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut view = KeyValueStoreView::load(context).await.unwrap();
+    /// view.insert(vec![0, 1], vec![34]).await.unwrap();
+    /// view.insert(vec![3, 4], vec![42]).await.unwrap();
+    /// let keys = view.find_keys_by_prefix_stream(&[0]).unwrap();
+    /// assert_eq!(keys.try_collect::<Vec<_>>().await.unwrap(), vec![vec![1]]);
+    /// # })
+    /// ```
+    pub fn find_keys_by_prefix_stream(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<impl Stream<Item = Result<Vec<u8>, ViewError>> + '_, ViewError> {
+        let keys = self.entries_stream(key_prefix, &KeyRange::full(), false)?;
+        Ok(keys.map_ok(|(key, _)| key))
+    }
+
+    /// Streams the key-value pairs, for keys matching the given prefix, in increasing
+    /// order of keys. The prefix is not included in the returned keys. The entries of the
+    /// storage are read page by page as the stream is polled, so that large views can be
+    /// iterated in bounded memory.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use futures::TryStreamExt as _;
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::key_value_store_view::KeyValueStoreView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut view = KeyValueStoreView::load(context).await.unwrap();
+    /// view.insert(vec![0, 1], vec![34]).await.unwrap();
+    /// view.insert(vec![3, 4], vec![42]).await.unwrap();
+    /// let key_values = view.find_key_values_by_prefix_stream(&[0]).unwrap();
+    /// assert_eq!(
+    ///     key_values.try_collect::<Vec<_>>().await.unwrap(),
+    ///     vec![(vec![1], vec![34])]
+    /// );
+    /// # })
+    /// ```
+    #[expect(clippy::type_complexity)]
+    pub fn find_key_values_by_prefix_stream(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<impl Stream<Item = Result<(Vec<u8>, Vec<u8>), ViewError>> + '_, ViewError> {
+        let key_values = self.entries_stream(key_prefix, &KeyRange::full(), true)?;
+        Ok(key_values.map_ok(|(key, value)| (key, value.expect("values are requested"))))
+    }
+
+    /// Finds the keys matching the given prefix and lying in `range`, in the order and up
+    /// to the limit given by the range. The prefix is not included in the returned keys.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::key_value_store_view::KeyValueStoreView;
+    /// # use linera_views::store::KeyRange;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut view = KeyValueStoreView::load(context).await.unwrap();
+    /// view.insert(vec![0, 1], vec![34]).await.unwrap();
+    /// view.insert(vec![0, 2], vec![42]).await.unwrap();
+    /// let range = KeyRange::full().rev().with_limit(1);
+    /// let keys = view.find_keys_by_prefix_in_range(&[0], &range).await.unwrap();
+    /// assert_eq!(keys, vec![vec![2]]);
+    /// # })
+    /// ```
+    pub async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, ViewError> {
+        self.entries_stream(key_prefix, range, false)?
+            .map_ok(|(key, _)| key)
+            .try_collect()
+            .await
+    }
+
+    /// Finds the key-value pairs, for keys matching the given prefix and lying in `range`,
+    /// in the order and up to the limit given by the range. The prefix is not included in
+    /// the returned keys.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::key_value_store_view::KeyValueStoreView;
+    /// # use linera_views::store::KeyRange;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut view = KeyValueStoreView::load(context).await.unwrap();
+    /// view.insert(vec![0, 1], vec![34]).await.unwrap();
+    /// view.insert(vec![0, 2], vec![42]).await.unwrap();
+    /// let range = KeyRange::new(vec![2]..);
+    /// let key_values = view.find_key_values_by_prefix_in_range(&[0], &range).await.unwrap();
+    /// assert_eq!(key_values, vec![(vec![2], vec![42])]);
+    /// # })
+    /// ```
+    pub async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, ViewError> {
+        self.entries_stream(key_prefix, range, true)?
+            .map_ok(|(key, value)| (key, value.expect("values are requested")))
+            .try_collect()
+            .await
+    }
+
     async fn compute_hash(&self) -> Result<<sha3::Sha3_256 as Hasher>::Output, ViewError> {
         #[cfg(with_metrics)]
         let _hash_latency = metrics::KEY_VALUE_STORE_VIEW_HASH_LATENCY.measure_latency();
@@ -1297,6 +1450,26 @@ impl<C: Context> ReadableKeyValueStore for ViewContainer<C> {
         let view = self.view.read().await;
         Ok(view.find_key_values_by_prefix(key_prefix).await?)
     }
+
+    async fn find_keys_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<Vec<u8>>, ViewContainerError> {
+        let view = self.view.read().await;
+        Ok(view.find_keys_by_prefix_in_range(key_prefix, range).await?)
+    }
+
+    async fn find_key_values_by_prefix_in_range(
+        &self,
+        key_prefix: &[u8],
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, ViewContainerError> {
+        let view = self.view.read().await;
+        Ok(view
+            .find_key_values_by_prefix_in_range(key_prefix, range)
+            .await?)
+    }
 }
 
 #[cfg(with_testing)]
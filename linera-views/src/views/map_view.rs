@@ -41,21 +41,23 @@ use std::{
     borrow::{Borrow, Cow},
     collections::{btree_map::Entry, BTreeMap},
     marker::PhantomData,
+    ops::RangeBounds,
 };
 
 use allocative::Allocative;
+use futures::{Stream, StreamExt as _, TryStreamExt as _};
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
     batch::Batch,
     common::{
-        from_bytes_option, get_key_range_for_prefix, CustomSerialize, DeletionSet, HasherOutput,
-        SuffixClosedSetIterator, Update,
+        from_bytes_option, get_key_range_for_prefix, merged_entries_stream, CustomSerialize,
+        DeletionSet, HasherOutput, SuffixClosedSetIterator, Update, ValueOrBytes,
     },
     context::{BaseKey, Context},
     hashable_wrapper::WrappedHashableContainerView,
     historical_hash_wrapper::HistoricallyHashableView,
-    store::ReadableKeyValueStore as _,
+    store::{KeyRange, ReadableKeyValueStore as _},
     views::{ClonableView, HashableView, Hasher, ReplaceContext, View, ViewError},
 };
 
@@ -90,40 +92,6 @@ where
     }
 }
 
-/// Whether we have a value or its serialization.
-enum ValueOrBytes<'a, T> {
-    /// The value itself.
-    Value(&'a T),
-    /// The serialization.
-    Bytes(Vec<u8>),
-}
-
-impl<'a, T> ValueOrBytes<'a, T>
-where
-    T: Clone + DeserializeOwned,
-{
-    /// Convert to a Cow.
-    fn to_value(&self) -> Result<Cow<'a, T>, ViewError> {
-        match self {
-            ValueOrBytes::Value(value) => Ok(Cow::Borrowed(value)),
-            ValueOrBytes::Bytes(bytes) => Ok(Cow::Owned(bcs::from_bytes(bytes)?)),
-        }
-    }
-}
-
-impl<T> ValueOrBytes<'_, T>
-where
-    T: Serialize,
-{
-    /// Convert to bytes.
-    pub fn into_bytes(self) -> Result<Vec<u8>, ViewError> {
-        match self {
-            ValueOrBytes::Value(value) => Ok(bcs::to_bytes(value)?),
-            ValueOrBytes::Bytes(bytes) => Ok(bytes),
-        }
-    }
-}
-
 impl<C, V> View for ByteMapView<C, V>
 where
     C: Context,
@@ -811,6 +779,74 @@ where
         )
         .await
     }
+
+    /// Streams the keys of the map lying in `range`, together with their values if
+    /// `with_values` is set. The pending updates are merged with the entries of the
+    /// storage, which are read page by page so that only the range is loaded.
+    fn entries_in_range(
+        &self,
+        range: KeyRange,
+        with_values: bool,
+    ) -> impl Stream<Item = Result<(Vec<u8>, Option<ValueOrBytes<'_, V>>), ViewError>> + '_ {
+        merged_entries_stream(
+            self.context.store(),
+            self.context.base_key().base_index(&[]),
+            range,
+            &self.updates,
+            |key| self.deletion_set.contains_prefix_of(key),
+            with_values,
+        )
+    }
+
+    /// Returns the keys of the map lying in `range`, in the order and up to the limit
+    /// given by the range. Only the entries in the range are read from storage.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use linera_views::store::KeyRange;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = ByteMapView::load(context).await.unwrap();
+    /// map.insert(vec![0, 1], String::from("Hello"));
+    /// map.insert(vec![1, 2], String::from("Bonjour"));
+    /// map.insert(vec![2, 2], String::from("Hallo"));
+    /// let range = KeyRange::new(vec![1]..).rev().with_limit(1);
+    /// assert_eq!(map.keys_in_range(range).await.unwrap(), vec![vec![2, 2]]);
+    /// # })
+    /// ```
+    pub async fn keys_in_range(&self, range: KeyRange) -> Result<Vec<Vec<u8>>, ViewError> {
+        self.keys_stream(range).try_collect().await
+    }
+
+    /// Streams the keys of the map lying in `range`, in the order and up to the limit
+    /// given by the range. The entries of the storage are read page by page as the
+    /// stream is polled, so that large maps can be iterated in bounded memory.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use futures::TryStreamExt as _;
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use linera_views::store::KeyRange;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = ByteMapView::load(context).await.unwrap();
+    /// map.insert(vec![0, 1], String::from("Hello"));
+    /// map.insert(vec![1, 2], String::from("Bonjour"));
+    /// map.insert(vec![1, 3], String::from("Hallo"));
+    /// let keys = map.keys_stream(KeyRange::prefix(&[1]));
+    /// assert_eq!(
+    ///     keys.try_collect::<Vec<_>>().await.unwrap(),
+    ///     vec![vec![1, 2], vec![1, 3]]
+    /// );
+    /// # })
+    /// ```
+    pub fn keys_stream(
+        &self,
+        range: KeyRange,
+    ) -> impl Stream<Item = Result<Vec<u8>, ViewError>> + '_ {
+        self.entries_in_range(range, false).map_ok(|(key, _)| key)
+    }
 }
 
 impl<C, V> ByteMapView<C, V>
@@ -873,6 +909,66 @@ where
     pub async fn key_values(&self) -> Result<Vec<(Vec<u8>, V)>, ViewError> {
         self.key_values_by_prefix(Vec::new()).await
     }
+
+    /// Returns the keys and values of the map lying in `range`, in the order and up to
+    /// the limit given by the range. Only the entries in the range are read from storage.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use linera_views::store::KeyRange;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = ByteMapView::load(context).await.unwrap();
+    /// map.insert(vec![0, 1], String::from("Hello"));
+    /// map.insert(vec![1, 2], String::from("Bonjour"));
+    /// map.insert(vec![2, 2], String::from("Hallo"));
+    /// let range = KeyRange::new(vec![0, 2]..vec![2]);
+    /// assert_eq!(
+    ///     map.key_values_in_range(range).await.unwrap(),
+    ///     vec![(vec![1, 2], String::from("Bonjour"))]
+    /// );
+    /// # })
+    /// ```
+    pub async fn key_values_in_range(
+        &self,
+        range: KeyRange,
+    ) -> Result<Vec<(Vec<u8>, V)>, ViewError> {
+        self.key_values_stream(range).try_collect().await
+    }
+
+    /// Streams the keys and values of the map lying in `range`, in the order and up to
+    /// the limit given by the range. The entries of the storage are read page by page as
+    /// the stream is polled, so that large maps can be iterated in bounded memory.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use futures::TryStreamExt as _;
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use linera_views::store::KeyRange;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = ByteMapView::load(context).await.unwrap();
+    /// map.insert(vec![0, 1], String::from("Hello"));
+    /// map.insert(vec![1, 2], String::from("Bonjour"));
+    /// let mut key_values = std::pin::pin!(map.key_values_stream(KeyRange::full().rev()));
+    /// assert_eq!(
+    ///     key_values.try_next().await.unwrap(),
+    ///     Some((vec![1, 2], String::from("Bonjour")))
+    /// );
+    /// # })
+    /// ```
+    pub fn key_values_stream(
+        &self,
+        range: KeyRange,
+    ) -> impl Stream<Item = Result<(Vec<u8>, V), ViewError>> + '_ {
+        self.entries_in_range(range, true)
+            .map(|entry| -> Result<_, ViewError> {
+                let (key, value) = entry?;
+                let value = value.expect("values are requested").to_value()?;
+                Ok((key, value.into_owned()))
+            })
+    }
 }
 
 impl<C, V> ByteMapView<C, V>
@@ -1466,6 +1562,172 @@ where
     }
 }
 
+impl<C, I, V> MapView<C, I, V>
+where
+    C: Context,
+    I: Send + Serialize + DeserializeOwned,
+    V: Clone + Sync + Send + Serialize + DeserializeOwned + 'static,
+{
+    async fn index_values_in_range(&self, range: KeyRange) -> Result<Vec<(I, V)>, ViewError> {
+        self.index_values_stream_in_range(range).try_collect().await
+    }
+
+    fn index_values_stream_in_range(
+        &self,
+        range: KeyRange,
+    ) -> impl Stream<Item = Result<(I, V), ViewError>> + '_ {
+        self.map
+            .key_values_stream(range)
+            .map(|entry| -> Result<_, ViewError> {
+                let (key, value) = entry?;
+                Ok((BaseKey::deserialize_value(&key)?, value))
+            })
+    }
+
+    /// Streams the `(index,value)` pairs of the map in the order of serialization. The
+    /// entries of the storage are read page by page as the stream is polled, so that
+    /// large maps can be iterated in bounded memory.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use futures::TryStreamExt as _;
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::MapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map: MapView<_, u8, _> = MapView::load(context).await.unwrap();
+    /// map.insert(&2, String::from("Bonjour"));
+    /// map.insert(&1, String::from("Ciao"));
+    /// let index_values = map.index_values_stream();
+    /// assert_eq!(
+    ///     index_values.try_collect::<Vec<_>>().await.unwrap(),
+    ///     vec![(1, String::from("Ciao")), (2, String::from("Bonjour"))]
+    /// );
+    /// # })
+    /// ```
+    pub fn index_values_stream(&self) -> impl Stream<Item = Result<(I, V), ViewError>> + '_ {
+        self.index_values_stream_in_range(KeyRange::full())
+    }
+
+    /// Streams the indices of the map in the order of serialization. The keys of the
+    /// storage are read page by page as the stream is polled.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use futures::TryStreamExt as _;
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::MapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map: MapView<_, u8, _> = MapView::load(context).await.unwrap();
+    /// map.insert(&2, String::from("Bonjour"));
+    /// map.insert(&1, String::from("Ciao"));
+    /// let indices = map.indices_stream();
+    /// assert_eq!(indices.try_collect::<Vec<_>>().await.unwrap(), vec![1, 2]);
+    /// # })
+    /// ```
+    pub fn indices_stream(&self) -> impl Stream<Item = Result<I, ViewError>> + '_ {
+        self.map
+            .keys_stream(KeyRange::full())
+            .map(|key| -> Result<_, ViewError> { Ok(BaseKey::deserialize_value(&key?)?) })
+    }
+
+    /// Obtains the `(index,value)` pairs whose indices lie in `range`. As for the other
+    /// iterations, both the order and the bounds are those of the serialized indices.
+    /// Only the entries in the range are read from storage.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::MapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map: MapView<_, u8, _> = MapView::load(context).await.unwrap();
+    /// map.insert(&1, String::from("Ciao"));
+    /// map.insert(&2, String::from("Bonjour"));
+    /// map.insert(&3, String::from("Hallo"));
+    /// assert_eq!(
+    ///     map.range(2..).await.unwrap(),
+    ///     vec![(2, String::from("Bonjour")), (3, String::from("Hallo"))]
+    /// );
+    /// # })
+    /// ```
+    pub async fn range<Q, R>(&self, range: R) -> Result<Vec<(I, V)>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let range = KeyRange::try_from_bounds(&range, BaseKey::derive_short_key::<Q>)?;
+        self.index_values_in_range(range).await
+    }
+
+    /// Obtains the first `(index,value)` pair in the order of serialization, if any.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::MapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map: MapView<_, u8, _> = MapView::load(context).await.unwrap();
+    /// map.insert(&2, String::from("Bonjour"));
+    /// map.insert(&1, String::from("Ciao"));
+    /// assert_eq!(map.first().await.unwrap(), Some((1, String::from("Ciao"))));
+    /// # })
+    /// ```
+    pub async fn first(&self) -> Result<Option<(I, V)>, ViewError> {
+        let range = KeyRange::full().with_limit(1);
+        Ok(self.index_values_in_range(range).await?.pop())
+    }
+
+    /// Obtains the last `(index,value)` pair in the order of serialization, if any.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::MapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map: MapView<_, u8, _> = MapView::load(context).await.unwrap();
+    /// map.insert(&2, String::from("Bonjour"));
+    /// map.insert(&1, String::from("Ciao"));
+    /// assert_eq!(map.last().await.unwrap(), Some((2, String::from("Bonjour"))));
+    /// # })
+    /// ```
+    pub async fn last(&self) -> Result<Option<(I, V)>, ViewError> {
+        let range = KeyRange::full().rev().with_limit(1);
+        Ok(self.index_values_in_range(range).await?.pop())
+    }
+
+    /// Obtains at most `limit` `(index,value)` pairs following the index `after`, or
+    /// from the start if `after` is `None`. Passing the last index of a page gives the
+    /// next page.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::MapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map: MapView<_, u8, _> = MapView::load(context).await.unwrap();
+    /// map.insert(&1, String::from("Ciao"));
+    /// map.insert(&2, String::from("Bonjour"));
+    /// map.insert(&3, String::from("Hallo"));
+    /// let page = map.page(None, 2).await.unwrap();
+    /// assert_eq!(page.len(), 2);
+    /// let (last, _) = page.last().unwrap();
+    /// let page = map.page(Some(last), 2).await.unwrap();
+    /// assert_eq!(page, vec![(3, String::from("Hallo"))]);
+    /// # })
+    /// ```
+    pub async fn page<Q>(&self, after: Option<&Q>, limit: usize) -> Result<Vec<(I, V)>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let mut range = KeyRange::full().with_limit(limit);
+        if let Some(index) = after {
+            range = range.after(&BaseKey::derive_short_key(index)?);
+        }
+        self.index_values_in_range(range).await
+    }
+}
+
 impl<C, I, V> MapView<C, I, V>
 where
     C: Context,
@@ -1984,6 +2246,171 @@ where
     }
 }
 
+impl<C, I, V> CustomMapView<C, I, V>
+where
+    C: Context,
+    I: Send + CustomSerialize,
+    V: Clone + Sync + Send + Serialize + DeserializeOwned + 'static,
+{
+    async fn index_values_in_range(&self, range: KeyRange) -> Result<Vec<(I, V)>, ViewError> {
+        self.index_values_stream_in_range(range).try_collect().await
+    }
+
+    fn index_values_stream_in_range(
+        &self,
+        range: KeyRange,
+    ) -> impl Stream<Item = Result<(I, V), ViewError>> + '_ {
+        self.map
+            .key_values_stream(range)
+            .map(|entry| -> Result<_, ViewError> {
+                let (key, value) = entry?;
+                Ok((I::from_custom_bytes(&key)?, value))
+            })
+    }
+
+    /// Streams the `(index,value)` pairs of the map in the order of the custom
+    /// serialization. The entries of the storage are read page by page as the stream is
+    /// polled, so that large maps can be iterated in bounded memory.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use futures::TryStreamExt as _;
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::CustomMapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = CustomMapView::<_, u128, String>::load(context).await.unwrap();
+    /// map.insert(&(420 as u128), String::from("Bonjour"));
+    /// map.insert(&(37 as u128), String::from("Ciao"));
+    /// let index_values = map.index_values_stream();
+    /// assert_eq!(
+    ///     index_values.try_collect::<Vec<_>>().await.unwrap(),
+    ///     vec![(37, String::from("Ciao")), (420, String::from("Bonjour"))]
+    /// );
+    /// # })
+    /// ```
+    pub fn index_values_stream(&self) -> impl Stream<Item = Result<(I, V), ViewError>> + '_ {
+        self.index_values_stream_in_range(KeyRange::full())
+    }
+
+    /// Streams the indices of the map in the order of the custom serialization. The keys
+    /// of the storage are read page by page as the stream is polled.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use futures::TryStreamExt as _;
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::CustomMapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = CustomMapView::<_, u128, String>::load(context).await.unwrap();
+    /// map.insert(&(420 as u128), String::from("Bonjour"));
+    /// map.insert(&(37 as u128), String::from("Ciao"));
+    /// let indices = map.indices_stream();
+    /// assert_eq!(indices.try_collect::<Vec<_>>().await.unwrap(), vec![37, 420]);
+    /// # })
+    /// ```
+    pub fn indices_stream(&self) -> impl Stream<Item = Result<I, ViewError>> + '_ {
+        self.map
+            .keys_stream(KeyRange::full())
+            .map(|key| I::from_custom_bytes(&key?))
+    }
+
+    /// Obtains the `(index,value)` pairs whose indices lie in `range`, in the order
+    /// determined by the custom serialization. Only the entries in the range are read
+    /// from storage.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::CustomMapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = CustomMapView::<_, u128, _>::load(context).await.unwrap();
+    /// map.insert(&(24 as u128), String::from("Ciao"));
+    /// map.insert(&(37 as u128), String::from("Bonjour"));
+    /// map.insert(&(300 as u128), String::from("Hallo"));
+    /// assert_eq!(
+    ///     map.range(30..300).await.unwrap(),
+    ///     vec![(37, String::from("Bonjour"))]
+    /// );
+    /// # })
+    /// ```
+    pub async fn range<Q, R>(&self, range: R) -> Result<Vec<(I, V)>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: CustomSerialize,
+        R: RangeBounds<Q>,
+    {
+        let range = KeyRange::try_from_bounds(&range, Q::to_custom_bytes)?;
+        self.index_values_in_range(range).await
+    }
+
+    /// Obtains the first `(index,value)` pair in the order of the custom serialization,
+    /// if any.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::CustomMapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = CustomMapView::<_, u128, _>::load(context).await.unwrap();
+    /// map.insert(&(300 as u128), String::from("Hallo"));
+    /// map.insert(&(24 as u128), String::from("Ciao"));
+    /// assert_eq!(map.first().await.unwrap(), Some((24, String::from("Ciao"))));
+    /// # })
+    /// ```
+    pub async fn first(&self) -> Result<Option<(I, V)>, ViewError> {
+        let range = KeyRange::full().with_limit(1);
+        Ok(self.index_values_in_range(range).await?.pop())
+    }
+
+    /// Obtains the last `(index,value)` pair in the order of the custom serialization,
+    /// if any.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::CustomMapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = CustomMapView::<_, u128, _>::load(context).await.unwrap();
+    /// map.insert(&(300 as u128), String::from("Hallo"));
+    /// map.insert(&(24 as u128), String::from("Ciao"));
+    /// assert_eq!(map.last().await.unwrap(), Some((300, String::from("Hallo"))));
+    /// # })
+    /// ```
+    pub async fn last(&self) -> Result<Option<(I, V)>, ViewError> {
+        let range = KeyRange::full().rev().with_limit(1);
+        Ok(self.index_values_in_range(range).await?.pop())
+    }
+
+    /// Obtains at most `limit` `(index,value)` pairs following the index `after`, or
+    /// from the start if `after` is `None`. Passing the last index of a page gives the
+    /// next page.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::CustomMapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = CustomMapView::<_, u128, _>::load(context).await.unwrap();
+    /// map.insert(&(24 as u128), String::from("Ciao"));
+    /// map.insert(&(37 as u128), String::from("Bonjour"));
+    /// map.insert(&(300 as u128), String::from("Hallo"));
+    /// let page = map.page(Some(&37), 10).await.unwrap();
+    /// assert_eq!(page, vec![(300, String::from("Hallo"))]);
+    /// # })
+    /// ```
+    pub async fn page<Q>(&self, after: Option<&Q>, limit: usize) -> Result<Vec<(I, V)>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: CustomSerialize,
+    {
+        let mut range = KeyRange::full().with_limit(limit);
+        if let Some(index) = after {
+            range = range.after(&index.to_custom_bytes()?);
+        }
+        self.index_values_in_range(range).await
+    }
+}
+
 impl<C, I, V> CustomMapView<C, I, V>
 where
     C: Context,
@@ -2310,4 +2737,51 @@ pub mod tests {
         check_array_u8(vec![23, 67, 123]);
         check_array_u8([23, 67, 123]);
     }
+
+    #[tokio::test]
+    async fn test_key_values_in_range_merges_storage_and_updates() {
+        use crate::{
+            batch::Batch,
+            context::{Context as _, MemoryContext},
+            map_view::ByteMapView,
+            store::{KeyRange, WritableKeyValueStore as _},
+            views::View as _,
+        };
+
+        let context = MemoryContext::new_for_testing(());
+        let mut map = ByteMapView::<_, u8>::load(context).await.unwrap();
+        for key in [1, 2, 3, 4, 5] {
+            map.insert(vec![0, key], key);
+        }
+        map.insert(vec![1], 10);
+        let mut batch = Batch::new();
+        map.pre_save(&mut batch).unwrap();
+        map.context().store().write_batch(batch).await.unwrap();
+        map.post_save();
+
+        // Overwrite, remove, insert and remove a whole prefix before saving.
+        map.insert(vec![0, 2], 20);
+        map.remove(vec![0, 3]);
+        map.insert(vec![0, 6], 6);
+        map.remove_by_prefix(vec![1]);
+        assert_eq!(
+            map.key_values_in_range(KeyRange::full()).await.unwrap(),
+            vec![
+                (vec![0, 1], 1),
+                (vec![0, 2], 20),
+                (vec![0, 4], 4),
+                (vec![0, 5], 5),
+                (vec![0, 6], 6),
+            ]
+        );
+        let range = KeyRange::new(vec![0, 2]..=vec![0, 5]).rev().with_limit(2);
+        assert_eq!(
+            map.key_values_in_range(range.clone()).await.unwrap(),
+            vec![(vec![0, 5], 5), (vec![0, 4], 4)]
+        );
+        assert_eq!(
+            map.keys_in_range(range.after(&[0, 4])).await.unwrap(),
+            vec![vec![0, 2]]
+        );
+    }
 }
@@ -1,20 +1,21 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{borrow::Borrow, collections::BTreeMap, marker::PhantomData};
+use std::{borrow::Borrow, collections::BTreeMap, marker::PhantomData, ops::RangeBounds};
 
 use allocative::Allocative;
+use futures::{Stream, TryStreamExt as _};
 #[cfg(with_metrics)]
 use linera_base::prometheus_util::MeasureLatency as _;
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
     batch::Batch,
-    common::{CustomSerialize, HasherOutput, Update},
+    common::{merged_entries_stream, CustomSerialize, HasherOutput, Update},
     context::{BaseKey, Context},
     hashable_wrapper::WrappedHashableContainerView,
     historical_hash_wrapper::HistoricallyHashableView,
-    store::ReadableKeyValueStore as _,
+    store::{KeyRange, ReadableKeyValueStore as _},
     views::{ClonableView, HashableView, Hasher, ReplaceContext, View, ViewError},
 };
 
@@ -356,6 +357,65 @@ impl<C: Context> ByteSetView<C> {
     }
 }
 
+impl<C: Context> ByteSetView<C> {
+    /// Returns the keys of the set lying in `range`, in the order and up to the limit
+    /// given by the range. The pending updates are merged with the keys of the storage,
+    /// which are read page by page so that only the range is loaded.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, set_view::ByteSetView};
+    /// # use linera_views::store::KeyRange;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut set = ByteSetView::load(context).await.unwrap();
+    /// set.insert(vec![0, 1]);
+    /// set.insert(vec![0, 2]);
+    /// set.insert(vec![3]);
+    /// let range = KeyRange::new(..vec![3]).rev();
+    /// assert_eq!(
+    ///     set.keys_in_range(range).await.unwrap(),
+    ///     vec![vec![0, 2], vec![0, 1]]
+    /// );
+    /// # })
+    /// ```
+    pub async fn keys_in_range(&self, range: KeyRange) -> Result<Vec<Vec<u8>>, ViewError> {
+        self.keys_stream(range).try_collect().await
+    }
+
+    /// Streams the keys of the set lying in `range`, in the order and up to the limit
+    /// given by the range. The keys of the storage are read page by page as the stream
+    /// is polled, so that large sets can be iterated in bounded memory.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use futures::TryStreamExt as _;
+    /// # use linera_views::{context::MemoryContext, set_view::ByteSetView};
+    /// # use linera_views::store::KeyRange;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut set = ByteSetView::load(context).await.unwrap();
+    /// set.insert(vec![0, 1]);
+    /// set.insert(vec![3]);
+    /// let mut keys = std::pin::pin!(set.keys_stream(KeyRange::prefix(&[3])));
+    /// assert_eq!(keys.try_next().await.unwrap(), Some(vec![3]));
+    /// assert_eq!(keys.try_next().await.unwrap(), None);
+    /// # })
+    /// ```
+    pub fn keys_stream(
+        &self,
+        range: KeyRange,
+    ) -> impl Stream<Item = Result<Vec<u8>, ViewError>> + '_ {
+        merged_entries_stream(
+            self.context.store(),
+            self.context.base_key().bytes.clone(),
+            range,
+            &self.updates,
+            |_| self.delete_storage_first,
+            false,
+        )
+        .map_ok(|(key, _)| key)
+    }
+}
+
 impl<C: Context> HashableView for ByteSetView<C> {
     type Hasher = sha3::Sha3_256;
 
@@ -639,6 +699,101 @@ impl<C: Context, I: Serialize + DeserializeOwned + Send> SetView<C, I> {
             .await?;
         Ok(())
     }
+
+    /// Returns the indices of the set lying in `range`. As for the other iterations,
+    /// both the order and the bounds are those of the serialized indices. Only the
+    /// indices in the range are read from storage.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, set_view::SetView};
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut set: SetView<_, u8> = SetView::load(context).await.unwrap();
+    /// set.insert(&1);
+    /// set.insert(&2);
+    /// set.insert(&3);
+    /// assert_eq!(set.range(..=2).await.unwrap(), vec![1, 2]);
+    /// # })
+    /// ```
+    pub async fn range<Q, R>(&self, range: R) -> Result<Vec<I>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let range = KeyRange::try_from_bounds(&range, BaseKey::derive_short_key::<Q>)?;
+        self.indices_in_range(range).await
+    }
+
+    /// Returns the first index of the set in the order of serialization, if any.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, set_view::SetView};
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut set: SetView<_, u8> = SetView::load(context).await.unwrap();
+    /// set.insert(&2);
+    /// set.insert(&1);
+    /// assert_eq!(set.first().await.unwrap(), Some(1));
+    /// # })
+    /// ```
+    pub async fn first(&self) -> Result<Option<I>, ViewError> {
+        let range = KeyRange::full().with_limit(1);
+        Ok(self.indices_in_range(range).await?.pop())
+    }
+
+    /// Returns the last index of the set in the order of serialization, if any.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, set_view::SetView};
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut set: SetView<_, u8> = SetView::load(context).await.unwrap();
+    /// set.insert(&2);
+    /// set.insert(&1);
+    /// assert_eq!(set.last().await.unwrap(), Some(2));
+    /// # })
+    /// ```
+    pub async fn last(&self) -> Result<Option<I>, ViewError> {
+        let range = KeyRange::full().rev().with_limit(1);
+        Ok(self.indices_in_range(range).await?.pop())
+    }
+
+    /// Returns at most `limit` indices following the index `after`, or from the start if
+    /// `after` is `None`. Passing the last index of a page gives the next page.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, set_view::SetView};
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut set: SetView<_, u8> = SetView::load(context).await.unwrap();
+    /// set.insert(&1);
+    /// set.insert(&2);
+    /// set.insert(&3);
+    /// assert_eq!(set.page(None, 2).await.unwrap(), vec![1, 2]);
+    /// assert_eq!(set.page(Some(&2), 2).await.unwrap(), vec![3]);
+    /// # })
+    /// ```
+    pub async fn page<Q>(&self, after: Option<&Q>, limit: usize) -> Result<Vec<I>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let mut range = KeyRange::full().with_limit(limit);
+        if let Some(index) = after {
+            range = range.after(&BaseKey::derive_short_key(index)?);
+        }
+        self.indices_in_range(range).await
+    }
+
+    async fn indices_in_range(&self, range: KeyRange) -> Result<Vec<I>, ViewError> {
+        self.set
+            .keys_in_range(range)
+            .await?
+            .iter()
+            .map(|key| Ok(BaseKey::deserialize_value(key)?))
+            .collect()
+    }
 }
 
 impl<C, I> HashableView for SetView<C, I>
@@ -923,6 +1078,105 @@ where
             .await?;
         Ok(())
     }
+
+    /// Returns the indices of the set lying in `range`, in the order determined by the
+    /// custom serialization. Only the indices in the range are read from storage.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::set_view::CustomSetView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut set = CustomSetView::<_, u128>::load(context).await.unwrap();
+    /// set.insert(&(34 as u128));
+    /// set.insert(&(37 as u128));
+    /// set.insert(&(420 as u128));
+    /// assert_eq!(set.range(35..).await.unwrap(), vec![37, 420]);
+    /// # })
+    /// ```
+    pub async fn range<Q, R>(&self, range: R) -> Result<Vec<I>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: CustomSerialize,
+        R: RangeBounds<Q>,
+    {
+        let range = KeyRange::try_from_bounds(&range, Q::to_custom_bytes)?;
+        self.indices_in_range(range).await
+    }
+
+    /// Returns the first index of the set in the order of the custom serialization, if
+    /// any.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::set_view::CustomSetView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut set = CustomSetView::<_, u128>::load(context).await.unwrap();
+    /// set.insert(&(420 as u128));
+    /// set.insert(&(37 as u128));
+    /// assert_eq!(set.first().await.unwrap(), Some(37));
+    /// # })
+    /// ```
+    pub async fn first(&self) -> Result<Option<I>, ViewError> {
+        let range = KeyRange::full().with_limit(1);
+        Ok(self.indices_in_range(range).await?.pop())
+    }
+
+    /// Returns the last index of the set in the order of the custom serialization, if
+    /// any.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::set_view::CustomSetView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut set = CustomSetView::<_, u128>::load(context).await.unwrap();
+    /// set.insert(&(420 as u128));
+    /// set.insert(&(37 as u128));
+    /// assert_eq!(set.last().await.unwrap(), Some(420));
+    /// # })
+    /// ```
+    pub async fn last(&self) -> Result<Option<I>, ViewError> {
+        let range = KeyRange::full().rev().with_limit(1);
+        Ok(self.indices_in_range(range).await?.pop())
+    }
+
+    /// Returns at most `limit` indices following the index `after`, or from the start if
+    /// `after` is `None`. Passing the last index of a page gives the next page.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::set_view::CustomSetView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut set = CustomSetView::<_, u128>::load(context).await.unwrap();
+    /// set.insert(&(34 as u128));
+    /// set.insert(&(37 as u128));
+    /// set.insert(&(420 as u128));
+    /// assert_eq!(set.page(Some(&34), 1).await.unwrap(), vec![37]);
+    /// # })
+    /// ```
+    pub async fn page<Q>(&self, after: Option<&Q>, limit: usize) -> Result<Vec<I>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: CustomSerialize,
+    {
+        let mut range = KeyRange::full().with_limit(limit);
+        if let Some(index) = after {
+            range = range.after(&index.to_custom_bytes()?);
+        }
+        self.indices_in_range(range).await
+    }
+
+    async fn indices_in_range(&self, range: KeyRange) -> Result<Vec<I>, ViewError> {
+        self.set
+            .keys_in_range(range)
+            .await?
+            .iter()
+            .map(|key| I::from_custom_bytes(key))
+            .collect()
+    }
 }
 
 impl<C: Context, I> HashableView for CustomSetView<C, I>
@@ -2009,4 +2263,73 @@ mod tests {
             Ok(())
         }
     }
+
+    #[tokio::test]
+    async fn test_keys_in_range_merges_storage_and_updates() -> Result<(), ViewError> {
+        let context = MemoryContext::new_for_testing(());
+        let mut set = ByteSetView::load(context).await?;
+        for key in [vec![1], vec![3], vec![5], vec![7]] {
+            set.insert(key);
+        }
+        let mut batch = Batch::new();
+        set.pre_save(&mut batch)?;
+        set.context().store().write_batch(batch).await?;
+        set.post_save();
+
+        // Mix stored keys with pending insertions and removals.
+        set.insert(vec![2]);
+        set.insert(vec![5]);
+        set.remove(vec![3]);
+        assert_eq!(
+            set.keys_in_range(KeyRange::full()).await?,
+            vec![vec![1], vec![2], vec![5], vec![7]]
+        );
+        assert_eq!(
+            set.keys_in_range(KeyRange::full().rev().with_limit(3))
+                .await?,
+            vec![vec![7], vec![5], vec![2]]
+        );
+        let range = KeyRange::new(vec![2]..vec![7]);
+        assert_eq!(
+            set.keys_in_range(range.clone()).await?,
+            vec![vec![2], vec![5]]
+        );
+        assert_eq!(set.keys_in_range(range.after(&[2])).await?, vec![vec![5]]);
+        assert!(set
+            .keys_in_range(KeyRange::full().with_limit(0))
+            .await?
+            .is_empty());
+
+        set.clear();
+        set.insert(vec![4]);
+        assert_eq!(set.keys_in_range(KeyRange::full()).await?, vec![vec![4]]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_view_range_first_last_page() -> Result<(), ViewError> {
+        let context = MemoryContext::new_for_testing(());
+        let mut set: SetView<_, u32> = SetView::load(context).await?;
+        assert_eq!(set.first().await?, None);
+        assert_eq!(set.last().await?, None);
+        for index in 0..10u32 {
+            set.insert(&index)?;
+        }
+        set.remove(&4)?;
+        assert_eq!(set.first().await?, Some(0));
+        assert_eq!(set.last().await?, Some(9));
+        assert_eq!(set.range(3..6).await?, vec![3, 5]);
+        let mut indices = Vec::new();
+        let mut after = None;
+        loop {
+            let page = set.page(after.as_ref(), 4).await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            after = Some(*last);
+            indices.extend(page);
+        }
+        assert_eq!(indices, set.indices().await?);
+        Ok(())
+    }
 }
@@ -3,8 +3,14 @@
 
 use std::marker::PhantomData;
 
+#[cfg(with_compression)]
+use linera_views::compressing::CompressingDatabase;
 #[cfg(with_dynamodb)]
 use linera_views::dynamo_db::DynamoDbDatabase;
+#[cfg(with_encryption)]
+use linera_views::encrypting::EncryptingDatabase;
+#[cfg(with_redb)]
+use linera_views::redb::RedbDatabase;
 #[cfg(with_rocksdb)]
 use linera_views::rocks_db::RocksDbDatabase;
 #[cfg(with_scylladb)]
@@ -17,7 +23,10 @@ use linera_views::{
 use test_case::test_case;
 
 #[test_case(PhantomData::<MemoryDatabase>; "MemoryDatabase")]
+#[cfg_attr(with_compression, test_case(PhantomData::<CompressingDatabase<MemoryDatabase>>; "CompressingDatabase"))]
+#[cfg_attr(with_encryption, test_case(PhantomData::<EncryptingDatabase<MemoryDatabase>>; "EncryptingDatabase"))]
 #[cfg_attr(with_rocksdb, test_case(PhantomData::<RocksDbDatabase>; "RocksDbDatabase"))]
+#[cfg_attr(with_redb, test_case(PhantomData::<RedbDatabase>; "RedbDatabase"))]
 #[cfg_attr(with_dynamodb, test_case(PhantomData::<DynamoDbDatabase>; "DynamoDbDatabase"))]
 #[cfg_attr(with_scylladb, test_case(PhantomData::<ScyllaDbDatabase>; "ScyllaDbDatabase"))]
 #[tokio::test]
@@ -29,7 +38,10 @@ where
 }
 
 #[test_case(PhantomData::<MemoryDatabase>; "MemoryDatabase")]
+#[cfg_attr(with_compression, test_case(PhantomData::<CompressingDatabase<MemoryDatabase>>; "CompressingDatabase"))]
+#[cfg_attr(with_encryption, test_case(PhantomData::<EncryptingDatabase<MemoryDatabase>>; "EncryptingDatabase"))]
 #[cfg_attr(with_rocksdb, test_case(PhantomData::<RocksDbDatabase>; "RocksDbDatabase"))]
+#[cfg_attr(with_redb, test_case(PhantomData::<RedbDatabase>; "RedbDatabase"))]
 #[cfg_attr(with_dynamodb, test_case(PhantomData::<DynamoDbDatabase>; "DynamoDbDatabase"))]
 #[cfg_attr(with_scylladb, test_case(PhantomData::<ScyllaDbDatabase>; "ScyllaDbDatabase"))]
 #[tokio::test]
@@ -1,6 +1,10 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(with_compression)]
+use linera_views::compressing::CompressingDatabase;
+#[cfg(with_encryption)]
+use linera_views::encrypting::EncryptingDatabase;
 use linera_views::{
     batch::Batch,
     context::{Context as _, MemoryContext},
@@ -64,6 +68,28 @@ async fn test_reads_memory() {
     }
 }
 
+#[cfg(with_compression)]
+#[tokio::test]
+async fn test_reads_compressing_memory() {
+    for scenario in get_random_test_scenarios() {
+        let store = CompressingDatabase::<MemoryDatabase>::new_test_store()
+            .await
+            .unwrap();
+        run_reads(store, scenario).await;
+    }
+}
+
+#[cfg(with_encryption)]
+#[tokio::test]
+async fn test_reads_encrypting_memory() {
+    for scenario in get_random_test_scenarios() {
+        let store = EncryptingDatabase::<MemoryDatabase>::new_test_store()
+            .await
+            .unwrap();
+        run_reads(store, scenario).await;
+    }
+}
+
 #[cfg(with_rocksdb)]
 #[tokio::test]
 async fn test_reads_rocks_db() {
@@ -75,6 +101,17 @@ async fn test_reads_rocks_db() {
     }
 }
 
+#[cfg(with_redb)]
+#[tokio::test]
+async fn test_reads_redb() {
+    for scenario in get_random_test_scenarios() {
+        let store = linera_views::redb::RedbDatabase::new_test_store()
+            .await
+            .unwrap();
+        run_reads(store, scenario).await;
+    }
+}
+
 #[cfg(with_dynamodb)]
 #[tokio::test]
 async fn test_reads_dynamo_db() {
@@ -156,6 +193,24 @@ async fn test_memory_writes_from_blank() {
     run_writes_from_blank(&store).await;
 }
 
+#[cfg(with_compression)]
+#[tokio::test]
+async fn test_compressing_memory_writes_from_blank() {
+    let store = CompressingDatabase::<MemoryDatabase>::new_test_store()
+        .await
+        .unwrap();
+    run_writes_from_blank(&store).await;
+}
+
+#[cfg(with_encryption)]
+#[tokio::test]
+async fn test_encrypting_memory_writes_from_blank() {
+    let store = EncryptingDatabase::<MemoryDatabase>::new_test_store()
+        .await
+        .unwrap();
+    run_writes_from_blank(&store).await;
+}
+
 #[tokio::test]
 async fn test_key_value_store_view_memory_writes_from_blank() {
     let context = MemoryContext::new_for_testing(());
@@ -172,6 +227,15 @@ async fn test_rocks_db_writes_from_blank() {
     run_writes_from_blank(&store).await;
 }
 
+#[cfg(with_redb)]
+#[tokio::test]
+async fn test_redb_writes_from_blank() {
+    let store = linera_views::redb::RedbDatabase::new_test_store()
+        .await
+        .unwrap();
+    run_writes_from_blank(&store).await;
+}
+
 #[cfg(with_dynamodb)]
 #[tokio::test]
 async fn test_dynamo_db_writes_from_blank() {
@@ -243,6 +307,15 @@ async fn rocks_db_tombstone_triggering_test() {
     linera_views::test_utils::tombstone_triggering_test(store).await;
 }
 
+#[cfg(with_redb)]
+#[tokio::test]
+async fn redb_tombstone_triggering_test() {
+    let store = linera_views::redb::RedbDatabase::new_test_store()
+        .await
+        .unwrap();
+    linera_views::test_utils::tombstone_triggering_test(store).await;
+}
+
 #[cfg(with_scylladb)]
 #[tokio::test]
 async fn test_scylla_db_big_write_read() {
@@ -276,6 +349,17 @@ async fn test_rocks_db_big_write_read() {
     run_big_write_read(store, target_size, value_sizes).await;
 }
 
+#[cfg(with_redb)]
+#[tokio::test]
+async fn test_redb_big_write_read() {
+    let store = linera_views::redb::RedbDatabase::new_test_store()
+        .await
+        .unwrap();
+    let value_sizes = vec![100, 1000, 200000, 5000000];
+    let target_size = 20000000;
+    run_big_write_read(store, target_size, value_sizes).await;
+}
+
 #[cfg(with_indexeddb)]
 #[wasm_bindgen_test]
 async fn test_indexed_db_big_write_read() {
@@ -314,6 +398,15 @@ async fn test_rocks_db_writes_from_state() {
     run_writes_from_state(&store).await;
 }
 
+#[cfg(with_redb)]
+#[tokio::test]
+async fn test_redb_writes_from_state() {
+    let store = linera_views::redb::RedbDatabase::new_test_store()
+        .await
+        .unwrap();
+    run_writes_from_state(&store).await;
+}
+
 #[cfg(with_indexeddb)]
 #[wasm_bindgen_test]
 async fn test_indexed_db_writes_from_state() {
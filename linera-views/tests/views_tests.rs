@@ -4,8 +4,11 @@
 use std::collections::BTreeSet;
 
 use anyhow::Result;
+use futures::TryStreamExt as _;
 #[cfg(with_dynamodb)]
 use linera_views::dynamo_db::DynamoDbDatabase;
+#[cfg(with_redb)]
+use linera_views::redb::RedbDatabase;
 #[cfg(with_rocksdb)]
 use linera_views::rocks_db::RocksDbDatabase;
 #[cfg(with_scylladb)]
@@ -27,7 +30,10 @@ use linera_views::{
     reentrant_collection_view::HashedReentrantCollectionView,
     register_view::HashedRegisterView,
     set_view::HashedSetView,
-    store::{KeyValueDatabase, TestKeyValueDatabase as _, WritableKeyValueStore as _},
+    store::{
+        KeyRange, KeyValueDatabase, TestKeyValueDatabase as _, WritableKeyValueStore as _,
+        STREAM_PAGE_SIZE,
+    },
     test_utils::{
         get_random_byte_vector, get_random_key_value_operations, get_random_key_values,
         span_random_reordering_put_delete,
@@ -171,6 +177,34 @@ impl StateStorage for RocksDbTestStorage {
     }
 }
 
+#[cfg(with_redb)]
+pub struct RedbTestStorage {
+    database: RedbDatabase,
+    accessed_chains: BTreeSet<usize>,
+}
+
+#[cfg(with_redb)]
+impl StateStorage for RedbTestStorage {
+    type Context = ViewContext<usize, <RedbDatabase as KeyValueDatabase>::Store>;
+
+    async fn new() -> Self {
+        let database = RedbDatabase::connect_test_namespace().await.unwrap();
+        let accessed_chains = BTreeSet::new();
+        RedbTestStorage {
+            database,
+            accessed_chains,
+        }
+    }
+
+    async fn load(&mut self, id: usize) -> Result<StateView<Self::Context>, ViewError> {
+        self.accessed_chains.insert(id);
+        let root_key = bcs::to_bytes(&id)?;
+        let store = self.database.open_exclusive(&root_key)?;
+        let context = ViewContext::create_root_context(store, id).await?;
+        StateView::load(context).await
+    }
+}
+
 #[cfg(with_scylladb)]
 pub struct ScyllaDbTestStorage {
     database: ScyllaDbDatabase,
@@ -646,6 +680,56 @@ async fn test_byte_map_view() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_streams_merge_pending_updates() -> Result<()> {
+    let context = MemoryContext::new_for_testing(());
+    // Enough entries for the streams to read several pages from storage.
+    let num_entries = 2 * STREAM_PAGE_SIZE as u32 + 7;
+    {
+        let mut view = ByteMapStateView::load(context.clone()).await?;
+        for i in 0..num_entries {
+            view.map.insert(i.to_be_bytes().to_vec(), i as u8);
+        }
+        view.save().await?;
+    }
+    let mut view = ByteMapStateView::load(context.clone()).await?;
+    view.map.remove_by_prefix(vec![0, 0, 4]);
+    view.map.insert(vec![0, 0, 4, 7], 1);
+    view.map.insert(vec![0, 0, 0, 5], 2);
+    view.map.remove(vec![0, 0, 7, 0]);
+    view.map.insert(vec![1], 3);
+    let key_values = view.map.key_values().await?;
+    let streamed = view.map.key_values_stream(KeyRange::full());
+    assert_eq!(streamed.try_collect::<Vec<_>>().await?, key_values);
+    let reversed = view.map.key_values_stream(KeyRange::full().rev());
+    let key_values = key_values.into_iter().rev().collect::<Vec<_>>();
+    assert_eq!(reversed.try_collect::<Vec<_>>().await?, key_values);
+    let keys = view.map.keys_stream(KeyRange::full());
+    assert_eq!(keys.try_collect::<Vec<_>>().await?, view.map.keys().await?);
+
+    let context = MemoryContext::new_for_testing(());
+    let mut view = KeyValueStoreView::load(context.clone()).await?;
+    for i in 0..num_entries {
+        view.insert(i.to_be_bytes().to_vec(), vec![i as u8]).await?;
+    }
+    let mut batch = Batch::new();
+    view.pre_save(&mut batch)?;
+    context.store().write_batch(batch).await?;
+    view.post_save();
+    view.remove_by_prefix(vec![0, 0, 4]).await?;
+    view.insert(vec![0, 0, 4, 7], vec![1]).await?;
+    view.remove(vec![0, 0, 7, 0]).await?;
+    let key_values = view.find_key_values_by_prefix(&[0, 0]).await?;
+    let streamed = view.find_key_values_by_prefix_stream(&[0, 0])?;
+    assert_eq!(streamed.try_collect::<Vec<_>>().await?, key_values);
+    let keys = view.find_keys_by_prefix_stream(&[0, 0])?;
+    assert_eq!(
+        keys.try_collect::<Vec<_>>().await?,
+        view.find_keys_by_prefix(&[0, 0]).await?
+    );
+    Ok(())
+}
+
 #[cfg(test)]
 async fn test_views_in_lru_memory_param(config: &TestConfig) -> Result<()> {
     tracing::warn!("Testing config {:?} with lru memory", config);
@@ -723,6 +807,30 @@ async fn test_views_in_rocks_db() -> Result<()> {
     Ok(())
 }
 
+#[cfg(with_redb)]
+#[cfg(test)]
+async fn test_views_in_redb_param(config: &TestConfig) -> Result<()> {
+    tracing::warn!("Testing config {:?} with redb", config);
+
+    let mut store = RedbTestStorage::new().await;
+    let hash = test_store(&mut store, config).await?;
+    assert_eq!(store.accessed_chains.len(), 1);
+
+    let mut store = MemoryTestStorage::new().await;
+    let hash2 = test_store(&mut store, config).await?;
+    assert_eq!(hash, hash2);
+    Ok(())
+}
+
+#[cfg(with_redb)]
+#[tokio::test]
+async fn test_views_in_redb() -> Result<()> {
+    for config in TestConfig::samples() {
+        test_views_in_redb_param(&config).await?;
+    }
+    Ok(())
+}
+
 #[cfg(with_scylladb)]
 #[cfg(test)]
 async fn test_views_in_scylla_db_param(config: &TestConfig) -> Result<()> {
@@ -761,7 +869,7 @@ async fn test_views_in_dynamo_db() -> Result<()> {
     Ok(())
 }
 
-#[cfg(with_rocksdb)]
+#[cfg(any(with_rocksdb, with_redb))]
 #[cfg(test)]
 async fn test_store_rollback_kernel<S>(store: &mut S) -> Result<()>
 where
@@ -804,14 +912,23 @@ where
     .await
 }
 
-#[cfg(with_rocksdb)]
+#[cfg(any(with_rocksdb, with_redb))]
 #[tokio::test]
 async fn test_store_rollback() -> Result<()> {
     let mut store = MemoryTestStorage::new().await;
     test_store_rollback_kernel(&mut store).await?;
 
-    let mut store = RocksDbTestStorage::new().await;
-    test_store_rollback_kernel(&mut store).await?;
+    #[cfg(with_rocksdb)]
+    {
+        let mut store = RocksDbTestStorage::new().await;
+        test_store_rollback_kernel(&mut store).await?;
+    }
+
+    #[cfg(with_redb)]
+    {
+        let mut store = RedbTestStorage::new().await;
+        test_store_rollback_kernel(&mut store).await?;
+    }
     Ok(())
 }
 